@@ -14,61 +14,57 @@ mod mutf8 {
     pub fn to_string<T: IntoIterator<Item = u8>>(bytes: T) -> Result<String, MUtf8Error> {
         let mut s = String::new();
         let mut iterator = bytes.into_iter();
-        loop {
-            if let Some(b) = iterator.next() {
-                if b == 0b1110_1101 {
-                    let b2 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
-                    if !b2 & 0b1111_0000 == 0b1010_0000 {
-                        return Err(MUtf8Error::UnknownByte);
-                    }
-                    let b3 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
-                    if !b3 & 0b1100_0000 == 0b1000_0000 {
-                        return Err(MUtf8Error::UnknownByte);
-                    }
-                    if !iterator.next().ok_or(MUtf8Error::MissingByte)? & 0xFF == 0b1110_1101 {
-                        return Err(MUtf8Error::UnknownByte);
-                    }
-                    let b4 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
-                    if !b4 & 0b1111_0000 == 0b1011_0000 {
-                        return Err(MUtf8Error::UnknownByte);
-                    }
-                    let b5 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
-                    if !b5 & 0b1100_0000 == 0b1000_0000 {
-                        return Err(MUtf8Error::UnknownByte);
-                    }
-                    let codepoint: u32 = 0x10000
-                        + ((b2 as u32 & 0x0f) << 16)
-                        + ((b3 as u32 & 0x3f) << 10)
-                        + ((b4 as u32 & 0x0f) << 6)
-                        + (b5 as u32 & 0x3f);
-                    s.push(std::char::from_u32(codepoint).ok_or(MUtf8Error::InvalidChar)?);
-                } else if b & 0b1111_0000 == 0b1110_0000 {
-                    let b2 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
-                    if !b2 & 0b1100_0000 == 0b1000_0000 {
-                        return Err(MUtf8Error::UnknownByte);
-                    }
-                    let b3 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
-                    if !b3 & 0b1100_0000 == 0b1000_0000 {
-                        return Err(MUtf8Error::UnknownByte);
-                    }
-                    let codepoint: u32 = b3 as u32 & 0b11_1111
-                        | ((b2 as u32 & 0b11_1111) << 6)
-                        | ((b as u32 & 0b1_1111) << 12);
-                    s.push(std::char::from_u32(codepoint).ok_or(MUtf8Error::InvalidChar)?);
-                } else if b & 0b1110_0000 == 0b1100_0000 {
-                    let b2 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
-                    if !b2 & 0b1100_0000 == 0b1000_0000 {
-                        return Err(MUtf8Error::UnknownByte);
-                    }
-                    let codepoint: u32 = b2 as u32 & 0b11_1111 | ((b as u32 & 0b1_1111) << 6);
-                    s.push(std::char::from_u32(codepoint).ok_or(MUtf8Error::InvalidChar)?);
-                } else if b & 0b1000_0000 == 0 {
-                    s.push(b as char);
-                } else {
+        while let Some(b) = iterator.next() {
+            if b == 0b1110_1101 {
+                let b2 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
+                if !b2 & 0b1111_0000 == 0b1010_0000 {
                     return Err(MUtf8Error::UnknownByte);
                 }
+                let b3 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
+                if !b3 & 0b1100_0000 == 0b1000_0000 {
+                    return Err(MUtf8Error::UnknownByte);
+                }
+                if !iterator.next().ok_or(MUtf8Error::MissingByte)? == 0b1110_1101 {
+                    return Err(MUtf8Error::UnknownByte);
+                }
+                let b4 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
+                if !b4 & 0b1111_0000 == 0b1011_0000 {
+                    return Err(MUtf8Error::UnknownByte);
+                }
+                let b5 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
+                if !b5 & 0b1100_0000 == 0b1000_0000 {
+                    return Err(MUtf8Error::UnknownByte);
+                }
+                let codepoint: u32 = 0x10000
+                    + ((b2 as u32 & 0x0f) << 16)
+                    + ((b3 as u32 & 0x3f) << 10)
+                    + ((b4 as u32 & 0x0f) << 6)
+                    + (b5 as u32 & 0x3f);
+                s.push(std::char::from_u32(codepoint).ok_or(MUtf8Error::InvalidChar)?);
+            } else if b & 0b1111_0000 == 0b1110_0000 {
+                let b2 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
+                if !b2 & 0b1100_0000 == 0b1000_0000 {
+                    return Err(MUtf8Error::UnknownByte);
+                }
+                let b3 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
+                if !b3 & 0b1100_0000 == 0b1000_0000 {
+                    return Err(MUtf8Error::UnknownByte);
+                }
+                let codepoint: u32 = b3 as u32 & 0b11_1111
+                    | ((b2 as u32 & 0b11_1111) << 6)
+                    | ((b as u32 & 0b1_1111) << 12);
+                s.push(std::char::from_u32(codepoint).ok_or(MUtf8Error::InvalidChar)?);
+            } else if b & 0b1110_0000 == 0b1100_0000 {
+                let b2 = iterator.next().ok_or(MUtf8Error::MissingByte)?;
+                if !b2 & 0b1100_0000 == 0b1000_0000 {
+                    return Err(MUtf8Error::UnknownByte);
+                }
+                let codepoint: u32 = b2 as u32 & 0b11_1111 | ((b as u32 & 0b1_1111) << 6);
+                s.push(std::char::from_u32(codepoint).ok_or(MUtf8Error::InvalidChar)?);
+            } else if b & 0b1000_0000 == 0 {
+                s.push(b as char);
             } else {
-                break;
+                return Err(MUtf8Error::UnknownByte);
             }
         }
         Ok(s)
@@ -133,8 +129,7 @@ fn read_u16<T: Read>(data: &mut T) -> Result<u16, ClassFileError> {
     if amt < 2 {
         return Err(ClassFileError::EndOfFile);
     }
-    let r: u16 = unsafe { std::mem::transmute(buf) };
-    Ok(r.to_be())
+    Ok(u16::from_be_bytes(buf))
 }
 
 fn read_u32<T: Read>(data: &mut T) -> Result<u32, ClassFileError> {
@@ -143,8 +138,7 @@ fn read_u32<T: Read>(data: &mut T) -> Result<u32, ClassFileError> {
     if amt < 4 {
         return Err(ClassFileError::EndOfFile);
     }
-    let r: u32 = unsafe { std::mem::transmute(buf) };
-    Ok(r.to_be())
+    Ok(u32::from_be_bytes(buf))
 }
 
 #[derive(Debug, Clone)]
@@ -194,10 +188,20 @@ pub enum ConstantPoolInfo {
     MethodType {
         descriptor_index: u16,
     },
+    Dynamic {
+        bootstrap_method_attr_index: u16,
+        name_and_type_index: u16,
+    },
     InvokeDynamic {
         bootstrap_method_attr_index: u16,
         name_and_type_index: u16,
     },
+    Module {
+        name_index: u16,
+    },
+    Package {
+        name_index: u16,
+    },
 }
 
 #[derive(Debug)]
@@ -212,15 +216,15 @@ pub struct ConstClassData {
 
 #[derive(Debug, Clone)]
 pub struct ConstFieldData {
-    class: ConstClassData,
-    name_and_type: ConstNameTypeData,
+    pub class: ConstClassData,
+    pub name_and_type: ConstNameTypeData,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConstMethodData {
-    class: ConstClassData,
+    pub class: ConstClassData,
     pub name_and_type: ConstNameTypeData,
-    is_interface: bool,
+    pub is_interface: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -229,6 +233,24 @@ pub struct ConstNameTypeData {
     pub descriptor: String,
 }
 
+#[derive(Debug, Clone)]
+pub enum MethodHandleReference {
+    Field { field_data: ConstFieldData },
+    Method { method_data: ConstMethodData },
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstMethodHandleData {
+    pub reference_kind: u8,
+    pub reference: MethodHandleReference,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstInvokeDynamicData {
+    pub bootstrap_method_attr_index: u16,
+    pub name_and_type: ConstNameTypeData,
+}
+
 impl ConstantPool {
     pub fn get_entry(&self, index: u16) -> Result<ConstantPoolInfo, ClassFileError> {
         Ok(self
@@ -311,9 +333,83 @@ impl ConstantPool {
         }
     }
 
+    pub fn get_method_handle_entry(
+        &self,
+        index: u16,
+    ) -> Result<ConstMethodHandleData, ClassFileError> {
+        if let ConstantPoolInfo::MethodHandle {
+            reference_kind,
+            reference_index,
+        } = self.get_entry(index)?
+        {
+            // kinds 1 to 4 are field accesses, all others method invocations
+            let reference = match reference_kind {
+                1..=4 => MethodHandleReference::Field {
+                    field_data: self.get_field_entry(reference_index)?,
+                },
+                _ => MethodHandleReference::Method {
+                    method_data: self.get_method_or_interface_entry(reference_index)?,
+                },
+            };
+            Ok(ConstMethodHandleData {
+                reference_kind,
+                reference,
+            })
+        } else {
+            Err(ClassFileError::InvalidCPEntry)
+        }
+    }
+
+    pub fn get_method_type_entry(&self, index: u16) -> Result<String, ClassFileError> {
+        if let ConstantPoolInfo::MethodType { descriptor_index } = self.get_entry(index)? {
+            self.get_utf8_entry(descriptor_index)
+        } else {
+            Err(ClassFileError::InvalidCPEntry)
+        }
+    }
+
+    pub fn get_invoke_dynamic_entry(
+        &self,
+        index: u16,
+    ) -> Result<ConstInvokeDynamicData, ClassFileError> {
+        if let ConstantPoolInfo::InvokeDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        } = self.get_entry(index)?
+        {
+            Ok(ConstInvokeDynamicData {
+                bootstrap_method_attr_index,
+                name_and_type: self.get_name_type_entry(name_and_type_index)?,
+            })
+        } else {
+            Err(ClassFileError::InvalidCPEntry)
+        }
+    }
+
+    /// Dynamically-computed constants share the layout of call sites, with `name_and_type`
+    /// holding a field descriptor.
+    pub fn get_dynamic_entry(&self, index: u16) -> Result<ConstInvokeDynamicData, ClassFileError> {
+        if let ConstantPoolInfo::Dynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        } = self.get_entry(index)?
+        {
+            Ok(ConstInvokeDynamicData {
+                bootstrap_method_attr_index,
+                name_and_type: self.get_name_type_entry(name_and_type_index)?,
+            })
+        } else {
+            Err(ClassFileError::InvalidCPEntry)
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.data.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
 }
 
 fn read_constant_pool<T: Read>(data: &mut T) -> Result<ConstantPool, ClassFileError> {
@@ -342,23 +438,23 @@ fn read_constant_pool<T: Read>(data: &mut T) -> Result<ConstantPool, ClassFileEr
                 string_index: read_u16(data)?,
             },
             3 => ConstantPoolInfo::Integer {
-                data: unsafe { std::mem::transmute(read_u32(data)?) },
+                data: read_u32(data)? as i32,
             },
             4 => ConstantPoolInfo::Float {
-                data: unsafe { std::mem::transmute(read_u32(data)?) },
+                data: f32::from_bits(read_u32(data)?),
             },
             5 => {
-                let high = read_u32(data)?;
-                let low = read_u32(data)?;
+                let high = read_u32(data)? as u64;
+                let low = read_u32(data)? as u64;
                 ConstantPoolInfo::Long {
-                    data: unsafe { std::mem::transmute([low, high]) },
+                    data: ((high << 32) | low) as i64,
                 }
             }
             6 => {
-                let high = read_u32(data)?;
-                let low = read_u32(data)?;
+                let high = read_u32(data)? as u64;
+                let low = read_u32(data)? as u64;
                 ConstantPoolInfo::Double {
-                    data: unsafe { std::mem::transmute([low, high]) },
+                    data: f64::from_bits((high << 32) | low),
                 }
             }
             12 => ConstantPoolInfo::NameAndType {
@@ -367,8 +463,7 @@ fn read_constant_pool<T: Read>(data: &mut T) -> Result<ConstantPool, ClassFileEr
             },
             1 => {
                 let length = read_u16(data)?;
-                let bytes_result: Result<Vec<_>, _> =
-                    (0..length).into_iter().map(|_| read_u8(data)).collect();
+                let bytes_result: Result<Vec<_>, _> = (0..length).map(|_| read_u8(data)).collect();
                 ConstantPoolInfo::Utf8 {
                     length,
                     string: mutf8::to_string(bytes_result?)?,
@@ -381,10 +476,20 @@ fn read_constant_pool<T: Read>(data: &mut T) -> Result<ConstantPool, ClassFileEr
             16 => ConstantPoolInfo::MethodType {
                 descriptor_index: read_u16(data)?,
             },
+            17 => ConstantPoolInfo::Dynamic {
+                bootstrap_method_attr_index: read_u16(data)?,
+                name_and_type_index: read_u16(data)?,
+            },
             18 => ConstantPoolInfo::InvokeDynamic {
                 bootstrap_method_attr_index: read_u16(data)?,
                 name_and_type_index: read_u16(data)?,
             },
+            19 => ConstantPoolInfo::Module {
+                name_index: read_u16(data)?,
+            },
+            20 => ConstantPoolInfo::Package {
+                name_index: read_u16(data)?,
+            },
             _ => return Err(ClassFileError::InvalidCPType),
         };
         constant_pool.insert(i, entry);
@@ -426,19 +531,68 @@ fn read_class_access_flags<T: Read>(data: &mut T) -> Result<ClassAccessFlags, Cl
 
 fn read_interfaces<T: Read>(data: &mut T) -> Result<Vec<u16>, ClassFileError> {
     let interaces_count = read_u16(data)?;
-    let interaces_result: Result<Vec<_>, _> = (0..interaces_count)
-        .into_iter()
-        .map(|_| read_u16(data))
-        .collect();
-    Ok(interaces_result?)
+    (0..interaces_count).map(|_| read_u16(data)).collect()
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExceptionTableInfo {
-    start_pc: u16,
-    end_pc: u16,
-    handler_pc: u16,
-    catch_type: u16,
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    pub catch_type: u16,
+}
+
+#[derive(Debug)]
+pub struct BootstrapMethodInfo {
+    pub bootstrap_method_ref: u16,
+    pub bootstrap_arguments: Vec<u16>,
+}
+
+#[derive(Debug)]
+pub struct InnerClassAccessFlags {
+    pub acc_public: bool,
+    pub acc_private: bool,
+    pub acc_protected: bool,
+    pub acc_static: bool,
+    pub acc_final: bool,
+    pub acc_interface: bool,
+    pub acc_abstract: bool,
+    pub acc_synthetic: bool,
+    pub acc_annotation: bool,
+    pub acc_enum: bool,
+}
+
+fn read_inner_class_access_flags<T: Read>(
+    data: &mut T,
+) -> Result<InnerClassAccessFlags, ClassFileError> {
+    let flags = read_u16(data)?;
+    Ok(InnerClassAccessFlags {
+        acc_public: flags & 0x0001 > 0,
+        acc_private: flags & 0x0002 > 0,
+        acc_protected: flags & 0x0004 > 0,
+        acc_static: flags & 0x0008 > 0,
+        acc_final: flags & 0x0010 > 0,
+        acc_interface: flags & 0x0200 > 0,
+        acc_abstract: flags & 0x0400 > 0,
+        acc_synthetic: flags & 0x1000 > 0,
+        acc_annotation: flags & 0x2000 > 0,
+        acc_enum: flags & 0x4000 > 0,
+    })
+}
+
+#[derive(Debug)]
+pub struct InnerClassInfo {
+    pub inner_class_info_index: u16,
+    pub outer_class_info_index: u16,
+    pub inner_name_index: u16,
+    pub inner_class_access_flags: InnerClassAccessFlags,
+}
+
+#[derive(Debug)]
+pub struct RecordComponentInfo {
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Vec<AttributeInfo>,
 }
 
 #[derive(Debug)]
@@ -460,6 +614,34 @@ pub enum AttributeInfo {
     SourceFile {
         sourcefile_index: u16,
     },
+    Signature {
+        signature_index: u16,
+    },
+    Exceptions {
+        exception_index_table: Vec<u16>,
+    },
+    BootstrapMethods {
+        bootstrap_methods: Vec<BootstrapMethodInfo>,
+    },
+    Record {
+        components: Vec<RecordComponentInfo>,
+    },
+    PermittedSubclasses {
+        classes: Vec<u16>,
+    },
+    NestHost {
+        host_class_index: u16,
+    },
+    NestMembers {
+        classes: Vec<u16>,
+    },
+    InnerClasses {
+        classes: Vec<InnerClassInfo>,
+    },
+    EnclosingMethod {
+        class_index: u16,
+        method_index: u16,
+    },
 }
 
 fn read_attributes<T: Read>(
@@ -481,14 +663,87 @@ fn read_attributes<T: Read>(
             "SourceFile" => AttributeInfo::SourceFile {
                 sourcefile_index: read_u16(data)?,
             },
+            "Signature" => AttributeInfo::Signature {
+                signature_index: read_u16(data)?,
+            },
+            "Exceptions" => {
+                let number_of_exceptions = read_u16(data)?;
+                let exceptions_result: Result<Vec<_>, _> =
+                    (0..number_of_exceptions).map(|_| read_u16(data)).collect();
+                AttributeInfo::Exceptions {
+                    exception_index_table: exceptions_result?,
+                }
+            }
+            "BootstrapMethods" => {
+                let num_bootstrap_methods = read_u16(data)?;
+                let mut bootstrap_methods = Vec::with_capacity(num_bootstrap_methods as usize);
+                for _ in 0..num_bootstrap_methods {
+                    let bootstrap_method_ref = read_u16(data)?;
+                    let num_bootstrap_arguments = read_u16(data)?;
+                    let arguments_result: Result<Vec<_>, _> = (0..num_bootstrap_arguments)
+                        .map(|_| read_u16(data))
+                        .collect();
+                    bootstrap_methods.push(BootstrapMethodInfo {
+                        bootstrap_method_ref,
+                        bootstrap_arguments: arguments_result?,
+                    });
+                }
+                AttributeInfo::BootstrapMethods { bootstrap_methods }
+            }
+            "Record" => {
+                let components_count = read_u16(data)?;
+                let mut components = Vec::with_capacity(components_count as usize);
+                for _ in 0..components_count {
+                    components.push(RecordComponentInfo {
+                        name_index: read_u16(data)?,
+                        descriptor_index: read_u16(data)?,
+                        attributes: read_attributes(data, constant_pool)?,
+                    });
+                }
+                AttributeInfo::Record { components }
+            }
+            "PermittedSubclasses" => {
+                let number_of_classes = read_u16(data)?;
+                let classes_result: Result<Vec<_>, _> =
+                    (0..number_of_classes).map(|_| read_u16(data)).collect();
+                AttributeInfo::PermittedSubclasses {
+                    classes: classes_result?,
+                }
+            }
+            "NestHost" => AttributeInfo::NestHost {
+                host_class_index: read_u16(data)?,
+            },
+            "NestMembers" => {
+                let number_of_classes = read_u16(data)?;
+                let classes_result: Result<Vec<_>, _> =
+                    (0..number_of_classes).map(|_| read_u16(data)).collect();
+                AttributeInfo::NestMembers {
+                    classes: classes_result?,
+                }
+            }
+            "InnerClasses" => {
+                let number_of_classes = read_u16(data)?;
+                let mut classes = Vec::with_capacity(number_of_classes as usize);
+                for _ in 0..number_of_classes {
+                    classes.push(InnerClassInfo {
+                        inner_class_info_index: read_u16(data)?,
+                        outer_class_info_index: read_u16(data)?,
+                        inner_name_index: read_u16(data)?,
+                        inner_class_access_flags: read_inner_class_access_flags(data)?,
+                    });
+                }
+                AttributeInfo::InnerClasses { classes }
+            }
+            "EnclosingMethod" => AttributeInfo::EnclosingMethod {
+                class_index: read_u16(data)?,
+                method_index: read_u16(data)?,
+            },
             "Code" => {
                 let max_stack = read_u16(data)?;
                 let max_locals = read_u16(data)?;
                 let code_length = read_u32(data)?;
-                let code_result: Result<Vec<_>, _> = (0..code_length)
-                    .into_iter()
-                    .map(|_| read_u8(data))
-                    .collect();
+                let code_result: Result<Vec<_>, _> =
+                    (0..code_length).map(|_| read_u8(data)).collect();
                 let exception_table_length = read_u16(data)?;
                 let mut exception_table = Vec::with_capacity(exception_table_length as usize);
                 for _ in 0..exception_table_length {
@@ -509,10 +764,8 @@ fn read_attributes<T: Read>(
                 }
             }
             _ => {
-                let bytes_result: Result<Vec<_>, _> = (0..attribute_length)
-                    .into_iter()
-                    .map(|_| read_u8(data))
-                    .collect();
+                let bytes_result: Result<Vec<_>, _> =
+                    (0..attribute_length).map(|_| read_u8(data)).collect();
                 AttributeInfo::Raw {
                     attribute_name,
                     info: bytes_result?,
@@ -679,7 +932,7 @@ pub fn read_classfile<T: Read>(data: &mut T) -> Result<ClassFile, ClassFileError
     let methods = read_methods(data, &constant_pool)?;
     let attributes = read_attributes(data, &constant_pool)?;
 
-    if let Ok(_) = read_u8(data) {
+    if read_u8(data).is_ok() {
         return Err(ClassFileError::MoreData);
     }
 
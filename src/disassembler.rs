@@ -22,8 +22,7 @@ fn read_u16<T: Read>(data: &mut T) -> Result<u16, DecompilerError> {
     if amt < 2 {
         return Err(DecompilerError::EndOfCode);
     }
-    let r: u16 = unsafe { std::mem::transmute(buf) };
-    Ok(r.to_be())
+    Ok(u16::from_be_bytes(buf))
 }
 
 fn read_u32<T: Read>(data: &mut T) -> Result<u32, DecompilerError> {
@@ -32,8 +31,7 @@ fn read_u32<T: Read>(data: &mut T) -> Result<u32, DecompilerError> {
     if amt < 4 {
         return Err(DecompilerError::EndOfCode);
     }
-    let r: u32 = unsafe { std::mem::transmute(buf) };
-    Ok(r.to_be())
+    Ok(u32::from_be_bytes(buf))
 }
 
 #[derive(Debug, Clone)]
@@ -616,7 +614,7 @@ fn read_instruction(
             let high = read_u32(data)?;
 
             let mut offsets = Vec::new();
-            for _ in low..=high {
+            for _ in (low as i32)..=(high as i32) {
                 offsets.push((pos + (read_u32(data)? as i32)) as u32);
             }
             Instruction::TableSwitch {
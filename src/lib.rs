@@ -1,18 +1,32 @@
 mod disassembler;
+mod names;
+mod signatures;
+mod structure;
+mod subroutines;
 
 use disassembler::Instruction;
+use javaclass::ConstNameTypeData;
+use javaclass::ExceptionTableInfo;
+use javaclass::InnerClassInfo;
+use javaclass::MethodHandleReference;
 use javaclass::{AttributeInfo, ClassFile, ClassFileError, ConstantPool, ConstantPoolInfo};
-use javaclass::{ConstClassData, ConstFieldData, ConstMethodData};
-use std::collections::HashMap;
-use std::convert::From;
+use javaclass::{BootstrapMethodInfo, ConstInvokeDynamicData, ConstMethodHandleData};
+use javaclass::{ConstClassData, ConstFieldData, ConstMethodData, FieldInfo, MethodInfo};
+use names::NameResolver;
+use signatures::{ClassSignature, MethodSignature, TypeSignature};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::{From, TryFrom};
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use structure::{Exit, Handler, Node, TryRegion};
 
 mod descriptors {
+    use super::names::NameResolver;
     use std::error::Error;
     use std::fmt::{Display, Formatter, Result as FmtResult};
     use std::iter::Peekable;
 
+    #[allow(clippy::upper_case_acronyms)]
     #[derive(Debug)]
     pub enum DescriptorParseError {
         EOF,
@@ -27,7 +41,7 @@ mod descriptors {
                 f,
                 "{}",
                 match self {
-                    DescriptorParseError::EOF => format!("end of iterator"),
+                    DescriptorParseError::EOF => String::from("end of iterator"),
                     DescriptorParseError::Expect { expected, got } =>
                         format!("unexpected char: expected '{}' got '{}'", expected, got),
                 }
@@ -35,7 +49,7 @@ mod descriptors {
         }
     }
 
-    fn expect<T: Iterator<Item = char>>(
+    pub fn expect<T: Iterator<Item = char>>(
         iter: &mut Peekable<T>,
         expected: char,
     ) -> Result<(), DescriptorParseError> {
@@ -50,28 +64,28 @@ mod descriptors {
         }
     }
 
-    fn accept<T: Iterator<Item = char>>(
+    pub fn accept<T: Iterator<Item = char>>(
         iter: &mut Peekable<T>,
         expected: char,
     ) -> Result<bool, DescriptorParseError> {
         let next_ch: char = peek(iter)?;
-        Ok(if next_ch != expected { false } else { true })
+        Ok(next_ch == expected)
     }
 
-    fn peek<T: Iterator<Item = char>>(
+    pub fn peek<T: Iterator<Item = char>>(
         iter: &mut Peekable<T>,
     ) -> Result<char, DescriptorParseError> {
         Ok(*iter.peek().ok_or(DescriptorParseError::EOF)?)
     }
 
-    fn consume<T: Iterator<Item = char>>(
+    pub fn consume<T: Iterator<Item = char>>(
         iter: &mut Peekable<T>,
     ) -> Result<(), DescriptorParseError> {
         iter.next().ok_or(DescriptorParseError::EOF)?;
         Ok(())
     }
 
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, Clone, PartialEq)]
     pub enum FieldType {
         Void,
         Byte,
@@ -86,7 +100,7 @@ mod descriptors {
         Array { inner: Box<FieldType> },
     }
 
-    fn parse_field_type<T: Iterator<Item = char>>(
+    pub fn parse_field_type<T: Iterator<Item = char>>(
         iter: &mut Peekable<T>,
     ) -> Result<FieldType, DescriptorParseError> {
         let ch = peek(iter)?;
@@ -108,9 +122,12 @@ mod descriptors {
                 }
                 FieldType::Reference { name }
             }
-            '[' => FieldType::Array {
-                inner: Box::new(parse_field_type(iter)?),
-            },
+            '[' => {
+                consume(iter)?;
+                return Ok(FieldType::Array {
+                    inner: Box::new(parse_field_type(iter)?),
+                });
+            }
             _ => {
                 return Err(DescriptorParseError::Expect {
                     expected: String::from("field type"),
@@ -122,7 +139,7 @@ mod descriptors {
         Ok(field_type)
     }
 
-    fn parse_return_desc<T: Iterator<Item = char>>(
+    pub fn parse_return_desc<T: Iterator<Item = char>>(
         iter: &mut Peekable<T>,
     ) -> Result<FieldType, DescriptorParseError> {
         if accept(iter, 'V')? {
@@ -133,6 +150,39 @@ mod descriptors {
         }
     }
 
+    impl FieldType {
+        pub fn to_java(&self, names: &NameResolver) -> String {
+            match self {
+                FieldType::Void => String::from("void"),
+                FieldType::Byte => String::from("byte"),
+                FieldType::Char => String::from("char"),
+                FieldType::Double => String::from("double"),
+                FieldType::Float => String::from("float"),
+                FieldType::Int => String::from("int"),
+                FieldType::Long => String::from("long"),
+                FieldType::Short => String::from("short"),
+                FieldType::Boolean => String::from("boolean"),
+                FieldType::Reference { name } => names.get_class_name(name),
+                FieldType::Array { inner } => format!("{}[]", inner.to_java(names)),
+            }
+        }
+
+        pub fn size(&self) -> u16 {
+            match self {
+                FieldType::Long | FieldType::Double => 2,
+                FieldType::Void => 0,
+                _ => 1,
+            }
+        }
+    }
+
+    pub fn parse_field<T: IntoIterator<Item = char>>(
+        into: T,
+    ) -> Result<FieldType, DescriptorParseError> {
+        let mut iter = into.into_iter().peekable();
+        parse_field_type(&mut iter)
+    }
+
     pub fn parse_method<T: IntoIterator<Item = char>>(
         into: T,
     ) -> Result<(Vec<FieldType>, FieldType), DescriptorParseError> {
@@ -171,6 +221,15 @@ pub enum DecompilerError {
         error: descriptors::DescriptorParseError,
     },
     EmptyStack,
+    UnsupportedInstr {
+        instruction: Instruction,
+    },
+    UnstructuredControlFlow {
+        position: u64,
+    },
+    Subroutine {
+        position: u64,
+    },
 }
 
 impl Error for DecompilerError {}
@@ -188,12 +247,16 @@ impl Display for DecompilerError {
                 DecompilerError::UnknownArrayType { type_id } =>
                     format!("unknown array type: {}", type_id),
                 DecompilerError::ClassFileError { error } => format!("{}", error),
-                DecompilerError::StackSize { size } => format!(
-                    "unexpected amount of elements on stack after decompiling block: {}",
-                    size
-                ),
-                DecompilerError::EmptyStack => format!("expected element but stack was empty"),
+                DecompilerError::StackSize { size } =>
+                    format!("unexpected amount of elements left on stack: {}", size),
+                DecompilerError::EmptyStack => String::from("expected element but stack was empty"),
                 DecompilerError::DescriptorParsing { error } => format!("{}", error),
+                DecompilerError::UnsupportedInstr { instruction } =>
+                    format!("unsupported instruction: {:?}", instruction),
+                DecompilerError::UnstructuredControlFlow { position } =>
+                    format!("could not structure control flow at position {}", position),
+                DecompilerError::Subroutine { position } =>
+                    format!("could not inline subroutine at position {}", position),
             }
         )
     }
@@ -211,7 +274,7 @@ impl From<descriptors::DescriptorParseError> for DecompilerError {
     }
 }
 
-fn get_index_for_pos(instructions: &Vec<(u64, Instruction)>, pos: u16) -> Option<usize> {
+fn get_index_for_pos(instructions: &[(u64, Instruction)], pos: u16) -> Option<usize> {
     for (i, (i_pos, _)) in instructions.iter().enumerate() {
         if i_pos == &(pos as u64) {
             return Some(i);
@@ -228,37 +291,48 @@ struct Block {
 
 fn split_at_multiple<T: Clone>(vec: Vec<T>, split_indices: Vec<usize>) -> Vec<Vec<T>> {
     let mut split_indices = split_indices;
+    split_indices.retain(|index| *index > 0 && *index < vec.len());
     split_indices.sort();
     split_indices.dedup();
     let mut output = Vec::with_capacity(split_indices.len() + 1);
 
-    if split_indices.len() == 0 {
-        return vec![vec];
-    } else {
-        if split_indices[0] == 0 {
-            split_indices.remove(0);
-        }
-        if *split_indices.last().unwrap() == vec.len() {
-            split_indices.remove(vec.len() - 1);
-        }
-        let mut split_vector = vec;
-        for i in 0..split_indices.len() {
-            let index = split_indices[i] - if i == 0 { 0 } else { split_indices[i - 1] };
-            let (first, second) = split_vector.split_at(index);
-
-            output.push(first.to_vec());
-            if i + 1 == split_indices.len() {
-                output.push(second.to_vec());
-            }
-            split_vector = second.to_vec();
-        }
+    let mut start = 0;
+    for index in split_indices {
+        output.push(vec[start..index].to_vec());
+        start = index;
     }
+    output.push(vec[start..].to_vec());
     output
 }
 
-fn gen_control_flow_graph(instructions: &Vec<(u64, Instruction)>) -> HashMap<u64, Block> {
+/// Returns the positions a `tableswitch` or `lookupswitch` jumps to, `default` first.
+fn get_switch_targets(instruction: &Instruction) -> Vec<u64> {
+    match instruction {
+        Instruction::TableSwitch {
+            default, offsets, ..
+        } => std::iter::once(default)
+            .chain(offsets)
+            .map(|target| *target as u64)
+            .collect(),
+        Instruction::LookupSwitch { default, pairs } => std::iter::once(default)
+            .chain(pairs.iter().map(|(_, target)| target))
+            .map(|target| *target as u64)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn gen_control_flow_graph(
+    instructions: &[(u64, Instruction)],
+    exception_table: &[ExceptionTableInfo],
+) -> HashMap<u64, Block> {
     //get jump indices
     let mut jump_indices = Vec::new();
+    for exception in exception_table {
+        for pos in [exception.start_pc, exception.end_pc, exception.handler_pc] {
+            jump_indices.extend(get_index_for_pos(instructions, pos));
+        }
+    }
     for (i, (_, instr)) in instructions.iter().enumerate() {
         match &instr {
             Instruction::IfNe { branch }
@@ -272,21 +346,41 @@ fn gen_control_flow_graph(instructions: &Vec<(u64, Instruction)>) -> HashMap<u64
             | Instruction::IfICmpGt { branch }
             | Instruction::IfICmpGe { branch }
             | Instruction::IfICmpLt { branch }
-            | Instruction::IfICmpLe { branch } => {
-                let true_pos = get_index_for_pos(&instructions, *branch).unwrap();
+            | Instruction::IfICmpLe { branch }
+            | Instruction::IfACmpEq { branch }
+            | Instruction::IfACmpNe { branch }
+            | Instruction::IfNull { branch }
+            | Instruction::IfNonNull { branch } => {
+                let true_pos = get_index_for_pos(instructions, *branch).unwrap();
                 jump_indices.push(true_pos);
                 let false_pos = i + 1;
                 jump_indices.push(false_pos);
             }
             Instruction::Goto { branch } => {
-                let jump_pos = get_index_for_pos(&instructions, *branch).unwrap();
+                let jump_pos = get_index_for_pos(instructions, *branch).unwrap();
                 jump_indices.push(jump_pos);
+                jump_indices.push(i + 1);
+            }
+            Instruction::TableSwitch { .. } | Instruction::LookupSwitch { .. } => {
+                for target in get_switch_targets(instr) {
+                    jump_indices.extend(get_index_for_pos(instructions, target as u16));
+                }
+                jump_indices.push(i + 1);
+            }
+            Instruction::Return
+            | Instruction::AReturn
+            | Instruction::IReturn
+            | Instruction::LReturn
+            | Instruction::DReturn
+            | Instruction::FReturn
+            | Instruction::AThrow => {
+                jump_indices.push(i + 1);
             }
             _ => {}
         }
     }
 
-    let raw_blocks = split_at_multiple(instructions.clone(), jump_indices);
+    let raw_blocks = split_at_multiple(instructions.to_vec(), jump_indices);
     let mut blocks: HashMap<u64, Block> = raw_blocks
         .iter()
         .map(|el| {
@@ -301,12 +395,9 @@ fn gen_control_flow_graph(instructions: &Vec<(u64, Instruction)>) -> HashMap<u64
         .collect();
 
     //store jumps
-    for (_, block) in &mut blocks {
+    for block in blocks.values_mut() {
         let (last_pos, last_instr) = block.instructions.last().unwrap();
-        let next = instructions
-            .iter()
-            .skip_while(|el| el.0 <= *last_pos)
-            .next();
+        let next = instructions.iter().find(|el| el.0 > *last_pos);
 
         match last_instr {
             Instruction::IfNe { branch }
@@ -320,7 +411,11 @@ fn gen_control_flow_graph(instructions: &Vec<(u64, Instruction)>) -> HashMap<u64
             | Instruction::IfICmpGt { branch }
             | Instruction::IfICmpGe { branch }
             | Instruction::IfICmpLt { branch }
-            | Instruction::IfICmpLe { branch } => {
+            | Instruction::IfICmpLe { branch }
+            | Instruction::IfACmpEq { branch }
+            | Instruction::IfACmpNe { branch }
+            | Instruction::IfNull { branch }
+            | Instruction::IfNonNull { branch } => {
                 let next_pos = next.unwrap().0;
                 block.branches.push(*branch as u64);
                 block.branches.push(next_pos);
@@ -328,12 +423,16 @@ fn gen_control_flow_graph(instructions: &Vec<(u64, Instruction)>) -> HashMap<u64
             Instruction::Goto { branch } => {
                 block.branches.push(*branch as u64);
             }
+            Instruction::TableSwitch { .. } | Instruction::LookupSwitch { .. } => {
+                block.branches.extend(get_switch_targets(last_instr));
+            }
             Instruction::Return
             | Instruction::AReturn
             | Instruction::IReturn
             | Instruction::LReturn
             | Instruction::DReturn
-            | Instruction::FReturn => {}
+            | Instruction::FReturn
+            | Instruction::AThrow => {}
             _ => {
                 let next_pos = next.unwrap().0;
                 block.branches.push(next_pos);
@@ -343,33 +442,6 @@ fn gen_control_flow_graph(instructions: &Vec<(u64, Instruction)>) -> HashMap<u64
     blocks
 }
 
-fn find_paths(blocks: &HashMap<u64, Block>, node: u64, path_in: Vec<u64>) -> Vec<Vec<u64>> {
-    let block: &Block = blocks.get(&node).unwrap();
-    let start_vector = vec![node];
-    let mut path = path_in;
-    path.push(node);
-
-    let mut paths = Vec::new();
-    if block.branches.len() == 0 {
-        paths.push(start_vector);
-    } else {
-        for b in &block.branches {
-            if !path.contains(b) {
-                for p in find_paths(blocks, *b, path.clone()) {
-                    let mut v = start_vector.clone();
-                    v.extend(p.iter());
-                    paths.push(v);
-                }
-            } else {
-                let mut v = start_vector.clone();
-                v.push(*b);
-                paths.push(v);
-            }
-        }
-    }
-    paths
-}
-
 #[derive(Debug, Clone)]
 enum VarType {
     Reference,
@@ -378,6 +450,8 @@ enum VarType {
     Long,
     Double,
     Byte,
+    Char,
+    Short,
 }
 
 impl Display for VarType {
@@ -392,11 +466,342 @@ impl Display for VarType {
                 VarType::Double => "double",
                 VarType::Long => "long",
                 VarType::Byte => "byte",
+                VarType::Char => "char",
+                VarType::Short => "short",
             }
         )
     }
 }
 
+/// Escapes a UTF-16 code unit for use in a java char or string literal delimited by `quote`.
+fn escape_char(ch: u16, quote: char) -> String {
+    match std::char::from_u32(ch as u32) {
+        Some('\u{8}') => String::from("\\b"),
+        Some('\t') => String::from("\\t"),
+        Some('\n') => String::from("\\n"),
+        Some('\u{c}') => String::from("\\f"),
+        Some('\r') => String::from("\\r"),
+        Some('\\') => String::from("\\\\"),
+        Some(ch) if ch == quote => format!("\\{}", ch),
+        Some(ch) if (' '..='~').contains(&ch) => ch.to_string(),
+        _ => format!("\\u{:04x}", ch),
+    }
+}
+
+/// Returns the name of the constant in `Float` or `Double` a value should be rendered as.
+fn get_special_float_name(
+    value: f64,
+    max_value: f64,
+    min_normal: f64,
+    min_value: f64,
+) -> Option<&'static str> {
+    if value.is_nan() {
+        Some("NaN")
+    } else if value == f64::INFINITY {
+        Some("POSITIVE_INFINITY")
+    } else if value == f64::NEG_INFINITY {
+        Some("NEGATIVE_INFINITY")
+    } else if value == max_value {
+        Some("MAX_VALUE")
+    } else if value == min_normal {
+        Some("MIN_NORMAL")
+    } else if value == min_value {
+        Some("MIN_VALUE")
+    } else {
+        None
+    }
+}
+
+/// Formats a floating point value with the shortest representation that reads back exactly.
+fn float_to_java<T: Display + std::fmt::LowerExp + Into<f64> + Copy>(value: T) -> String {
+    let magnitude = value.into().abs();
+    let java = if magnitude != 0.0 && !(1e-3..1e7).contains(&magnitude) {
+        format!("{:e}", value)
+    } else {
+        format!("{}", value)
+    };
+    if java.contains(['.', 'e']) {
+        java
+    } else {
+        format!("{}.0", java)
+    }
+}
+
+/// The precedence levels of java expressions, from the loosest to the tightest binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Lambda,
+    Ternary,
+    Or,
+    And,
+    BitOr,
+    BitXOr,
+    BitAnd,
+    Equality,
+    Relational,
+    Shift,
+    Additive,
+    Multiplicative,
+    Unary,
+    Primary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOperator {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    ShL,
+    ShR,
+    UShR,
+    BitAnd,
+    BitOr,
+    BitXOr,
+    Equal,
+    NotEqual,
+    Less,
+    GreaterEqual,
+    Greater,
+    LessEqual,
+    And,
+    Or,
+}
+
+impl BinaryOperator {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "/",
+            BinaryOperator::Rem => "%",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::ShL => "<<",
+            BinaryOperator::ShR => ">>",
+            BinaryOperator::UShR => ">>>",
+            BinaryOperator::BitAnd => "&",
+            BinaryOperator::BitOr => "|",
+            BinaryOperator::BitXOr => "^",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::And => "&&",
+            BinaryOperator::Or => "||",
+        }
+    }
+
+    fn precedence(&self) -> Precedence {
+        match self {
+            BinaryOperator::Mul | BinaryOperator::Div | BinaryOperator::Rem => {
+                Precedence::Multiplicative
+            }
+            BinaryOperator::Add | BinaryOperator::Sub => Precedence::Additive,
+            BinaryOperator::ShL | BinaryOperator::ShR | BinaryOperator::UShR => Precedence::Shift,
+            BinaryOperator::BitAnd => Precedence::BitAnd,
+            BinaryOperator::BitOr => Precedence::BitOr,
+            BinaryOperator::BitXOr => Precedence::BitXOr,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => Precedence::Equality,
+            BinaryOperator::Less
+            | BinaryOperator::GreaterEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::LessEqual => Precedence::Relational,
+            BinaryOperator::And => Precedence::And,
+            BinaryOperator::Or => Precedence::Or,
+        }
+    }
+
+    /// Returns the comparison that holds exactly if this one does not.
+    fn negated(&self) -> Option<BinaryOperator> {
+        match self {
+            BinaryOperator::Equal => Some(BinaryOperator::NotEqual),
+            BinaryOperator::NotEqual => Some(BinaryOperator::Equal),
+            BinaryOperator::Less => Some(BinaryOperator::GreaterEqual),
+            BinaryOperator::GreaterEqual => Some(BinaryOperator::Less),
+            BinaryOperator::Greater => Some(BinaryOperator::LessEqual),
+            BinaryOperator::LessEqual => Some(BinaryOperator::Greater),
+            _ => None,
+        }
+    }
+
+    fn is_comparison(&self) -> bool {
+        self.negated().is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UnaryOperator {
+    Neg,
+    BitNot,
+    Not,
+}
+
+impl UnaryOperator {
+    fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOperator::Neg => "-",
+            UnaryOperator::BitNot => "~",
+            UnaryOperator::Not => "!",
+        }
+    }
+}
+
+/// A `catch` clause, `index` is the local the exception is stored in.
+#[derive(Debug, Clone)]
+struct Catch {
+    classes: Vec<ConstClassData>,
+    index: u16,
+    body: Vec<AST>,
+}
+
+/// A pattern of an `instanceof` check or of a `case` label.
+#[derive(Debug, Clone)]
+enum Pattern {
+    /// Matches values of `pattern_type` and binds them to the local `binding` if there is one.
+    Type {
+        pattern_type: descriptors::FieldType,
+        binding: Option<u16>,
+    },
+    /// Matches instances of the record `class_data` whose components match `components`.
+    Record {
+        class_data: ConstClassData,
+        components: Vec<Pattern>,
+    },
+}
+
+impl Pattern {
+    fn from_class(class_data: &ConstClassData, binding: Option<u16>) -> Pattern {
+        // the names of array classes are descriptors
+        let pattern_type = match descriptors::parse_field(class_data.name.chars()) {
+            Ok(array @ descriptors::FieldType::Array { .. }) => array,
+            _ => descriptors::FieldType::Reference {
+                name: class_data.name.clone(),
+            },
+        };
+        Pattern::Type {
+            pattern_type,
+            binding,
+        }
+    }
+
+    fn renumber(&mut self, renumber: &dyn Fn(u16) -> u16) {
+        match self {
+            Pattern::Type { binding, .. } => {
+                if let Some(binding) = binding {
+                    *binding = renumber(*binding);
+                }
+            }
+            Pattern::Record { components, .. } => {
+                for component in components {
+                    component.renumber(renumber);
+                }
+            }
+        }
+    }
+
+    fn binds(&self, index: u16) -> bool {
+        match self {
+            Pattern::Type { binding, .. } => *binding == Some(index),
+            Pattern::Record { components, .. } => {
+                components.iter().any(|component| component.binds(index))
+            }
+        }
+    }
+
+    fn has_type(&self, field_type: &descriptors::FieldType) -> bool {
+        matches!(self, Pattern::Type { pattern_type, .. } if pattern_type == field_type)
+    }
+
+    fn to_java(&self, names: &NameResolver) -> String {
+        match self {
+            Pattern::Type {
+                pattern_type,
+                binding: Some(binding),
+            } => format!("{} var{}", pattern_type.to_java(names), binding),
+            Pattern::Type { pattern_type, .. } => pattern_type.to_java(names),
+            Pattern::Record {
+                class_data,
+                components,
+            } => {
+                let components: Vec<String> = components
+                    .iter()
+                    .map(|component| component.to_java(names))
+                    .collect();
+                format!(
+                    "{}({})",
+                    names.get_class_name(&class_data.name),
+                    components.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// A group of `case` labels of a `switch` statement and the code following them.
+#[derive(Debug, Clone)]
+struct Case {
+    labels: Vec<AST>,
+    /// The pattern of a pattern `case`, which has no other labels.
+    pattern: Option<Pattern>,
+    /// The `when` clause of a pattern `case`.
+    guard: Option<AST>,
+    is_default: bool,
+    body: Vec<AST>,
+}
+
+impl Case {
+    /// Returns the constants or the pattern the case matches, without `default`.
+    fn get_labels(&self, is_static: bool, names: &NameResolver) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .labels
+            .iter()
+            .map(|label| match label {
+                // enum constants are referred to by their simple name
+                AST::Static { field_data } => field_data.name_and_type.name.clone(),
+                label => label.to_java(is_static, names),
+            })
+            .collect();
+        if let Some(pattern) = &self.pattern {
+            let mut label = pattern.to_java(names);
+            if let Some(guard) = &self.guard {
+                label.push_str(&format!(" when {}", guard.to_java(is_static, names)));
+            }
+            labels.push(label);
+        }
+        labels
+    }
+
+    fn write_labels(&self, output: &mut String, is_static: bool, names: &NameResolver) {
+        for label in self.get_labels(is_static, names) {
+            output.push_str(&format!("    case {}:\n", label));
+        }
+        if self.is_default {
+            output.push_str("    default:\n");
+        }
+    }
+}
+
+/// A label of a pattern `switch` as passed to `typeSwitch` or `enumSwitch`.
+#[derive(Debug, Clone)]
+enum PatternLabel {
+    /// A type or record pattern matching instances of the class.
+    Class(ConstClassData),
+    /// A constant, enum constants are given by their field.
+    Constant(AST),
+}
+
+/// A resource of a try-with-resources statement, `class_data` is the class it is closed by.
+#[derive(Debug, Clone)]
+struct Resource {
+    index: u16,
+    class_data: ConstClassData,
+    value: AST,
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 enum AST {
     BasicCast {
@@ -412,6 +817,7 @@ enum AST {
     },
     Variable {
         index: u16,
+        #[allow(dead_code)]
         vartype: VarType,
     },
     Call {
@@ -419,253 +825,8513 @@ enum AST {
         reference: Box<AST>,
         args: Vec<AST>,
     },
+    StaticCall {
+        method_data: ConstMethodData,
+        args: Vec<AST>,
+    },
     ArrayLength {
         reference: Box<AST>,
     },
+    ArrayElement {
+        array: Box<AST>,
+        index: Box<AST>,
+    },
+    SetArrayElement {
+        array: Box<AST>,
+        index: Box<AST>,
+        value: Box<AST>,
+    },
+    /// An assignment used as a value, as in `a = b = 0`.
+    Assign {
+        target: Box<AST>,
+        value: Box<AST>,
+    },
+    /// A compound assignment like `x += 5`, written as `x++` or `x--` when adding one unless it
+    /// appends to a string.
+    CompoundAssign {
+        target: Box<AST>,
+        operator: BinaryOperator,
+        value: Box<AST>,
+        is_concat: bool,
+    },
+    /// An increment or decrement of a variable, field or array element used as a value.
+    Increment {
+        target: Box<AST>,
+        operator: BinaryOperator,
+        is_prefix: bool,
+    },
+    /// A new array, `elements` holds the values of its initializer if it has one.
+    NewArray {
+        array_type: descriptors::FieldType,
+        dimensions: Vec<AST>,
+        elements: Vec<AST>,
+    },
     ConstInt {
+        value: i32,
+    },
+    ConstLong {
         value: i64,
     },
     ConstFloat {
+        value: f32,
+    },
+    ConstDouble {
         value: f64,
     },
+    ConstChar {
+        value: u16,
+    },
+    ConstBoolean {
+        value: bool,
+    },
     ConstString {
         value: String,
     },
+    ConstNull,
     VoidReturn,
+    Return {
+        value: Box<AST>,
+    },
+    If {
+        condition: Box<AST>,
+        then: Vec<AST>,
+        otherwise: Vec<AST>,
+    },
+    While {
+        condition: Box<AST>,
+        body: Vec<AST>,
+    },
+    DoWhile {
+        body: Vec<AST>,
+        condition: Box<AST>,
+    },
+    For {
+        condition: Box<AST>,
+        update: Vec<AST>,
+        body: Vec<AST>,
+    },
+    /// An enhanced for loop, `var_type` is the type of the variable if it is known.
+    ForEach {
+        index: u16,
+        var_type: Option<descriptors::FieldType>,
+        iterable: Box<AST>,
+        body: Vec<AST>,
+    },
+    Break,
+    Continue,
+    Try {
+        resources: Vec<Resource>,
+        body: Vec<AST>,
+        catches: Vec<Catch>,
+        finally: Vec<AST>,
+    },
+    Throw {
+        value: Box<AST>,
+    },
+    Assert {
+        condition: Box<AST>,
+        message: Option<Box<AST>>,
+    },
+    MonitorEnter {
+        value: Box<AST>,
+    },
+    MonitorExit {
+        value: Box<AST>,
+    },
+    Synchronized {
+        lock: Box<AST>,
+        body: Vec<AST>,
+    },
+    Switch {
+        value: Box<AST>,
+        cases: Vec<Case>,
+    },
+    /// A `switch` whose cases yield a value.
+    SwitchExpression {
+        value: Box<AST>,
+        cases: Vec<Case>,
+    },
+    Yield {
+        value: Box<AST>,
+    },
+    /// The index of the first of `labels` from `restart` on that matches `value`, which is what
+    /// a pattern `switch` switches on.
+    PatternSwitchIndex {
+        value: Box<AST>,
+        restart: Box<AST>,
+        labels: Vec<PatternLabel>,
+    },
+    /// Continues matching a pattern `switch` at `label` after a guard did not hold.
+    PatternRestart {
+        label: i32,
+    },
     Set {
         index: u16,
         value: Box<AST>,
     },
-    Mul {
+    Field {
+        field_data: ConstFieldData,
+        reference: Box<AST>,
+    },
+    SetField {
+        field_data: ConstFieldData,
+        reference: Box<AST>,
+        value: Box<AST>,
+    },
+    SetStatic {
+        field_data: ConstFieldData,
+        value: Box<AST>,
+    },
+    Binary {
+        operator: BinaryOperator,
+        lhs: Box<AST>,
+        rhs: Box<AST>,
+    },
+    Unary {
+        operator: UnaryOperator,
+        value: Box<AST>,
+    },
+    InstanceOf {
+        value: Box<AST>,
+        pattern: Pattern,
+    },
+    Ternary {
+        condition: Box<AST>,
+        then: Box<AST>,
+        otherwise: Box<AST>,
+    },
+    /// A lambda expression, `captured` holds the values it captures from the enclosing method.
+    Lambda {
+        params: Vec<u16>,
+        body: Vec<AST>,
+        captured: Vec<AST>,
+    },
+    MethodReference {
+        method_data: ConstMethodData,
+        reference: Option<Box<AST>>,
+    },
+    /// A class literal like `String.class` or `int[].class`.
+    ClassLiteral {
+        class_type: descriptors::FieldType,
+    },
+    /// A `MethodType` constant, which has no literal in Java and is written as a lookup.
+    MethodTypeConstant {
+        descriptor: String,
+    },
+    /// A `MethodHandle` constant, which has no literal in Java and is written as a lookup.
+    MethodHandleConstant {
+        handle: ConstMethodHandleData,
+    },
+    /// A dynamically-computed constant, written as the call of its bootstrap method.
+    DynamicConstant {
+        bootstrap_method: ConstMethodData,
+        name_and_type: ConstNameTypeData,
+        args: Vec<AST>,
+    },
+    /// An object created by `new` whose constructor has not been called yet.
+    Uninitialized {
+        class_data: ConstClassData,
+    },
+    New {
+        method_data: ConstMethodData,
+        args: Vec<AST>,
+    },
+    /// An instance of an anonymous class, which is declared where it is created.
+    AnonymousClass {
+        class_name: String,
+        supertype: TypeSignature,
+        args: Vec<AST>,
+    },
+    /// The declaration of a local class.
+    LocalClass {
+        class_name: String,
+    },
+    Concat {
+        values: Vec<AST>,
+    },
+    /// The result of `lcmp`, `fcmpl`, `fcmpg`, `dcmpl` or `dcmpg`.
+    Compare {
         lhs: Box<AST>,
         rhs: Box<AST>,
+        compare_type: VarType,
+        /// The result if either value is NaN, which is -1 for `fcmpl` and `dcmpl` and 1 for
+        /// `fcmpg` and `dcmpg`.
+        nan_result: Option<i32>,
+    },
+    /// A value pushed by a preceding block, `index` counts from the top of its stack.
+    StackInput {
+        index: usize,
+    },
+    /// A variable referred to by its name, e.g. a parameter of the canonical constructor of a
+    /// record, which is named after its component, or a variable captured by a local class.
+    NamedVariable {
+        name: String,
     },
 }
 
 impl AST {
-    fn to_java(&self, is_static: bool, get_class_name: fn(&str) -> String) -> String {
+    /// Returns the comparison a condition on the result of `fcmpl`, `fcmpg`, `dcmpl` or `dcmpg`
+    /// is written as. Comparisons other than `!=` are false if a value is NaN, so a condition
+    /// that holds for NaN is written as the negation of the opposite comparison.
+    fn get_float_comparison(&self) -> Option<AST> {
+        let (operator, lhs, rhs, nan_result) = match self {
+            AST::Binary { operator, lhs, rhs } => match (lhs.as_ref(), rhs.as_ref()) {
+                (
+                    AST::Compare {
+                        lhs,
+                        rhs,
+                        nan_result: Some(nan_result),
+                        ..
+                    },
+                    AST::ConstInt { value: 0 },
+                ) => (*operator, lhs, rhs, *nan_result),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let holds_for_nan = match operator {
+            BinaryOperator::Equal => nan_result == 0,
+            BinaryOperator::NotEqual => nan_result != 0,
+            BinaryOperator::Less => nan_result < 0,
+            BinaryOperator::GreaterEqual => nan_result >= 0,
+            BinaryOperator::Greater => nan_result > 0,
+            BinaryOperator::LessEqual => nan_result <= 0,
+            _ => return None,
+        };
+        let comparison = |operator| AST::Binary {
+            operator,
+            lhs: lhs.clone(),
+            rhs: rhs.clone(),
+        };
+        if holds_for_nan == (operator == BinaryOperator::NotEqual) {
+            Some(comparison(operator))
+        } else {
+            Some(AST::Unary {
+                operator: UnaryOperator::Not,
+                value: Box::new(comparison(operator.negated()?)),
+            })
+        }
+    }
+
+    /// Checks if the node is an expression that is used as a statement, which has to be
+    /// terminated by a semicolon. An assignment is left as an expression if it replaced the call
+    /// of an accessor.
+    fn is_expression_statement(&self) -> bool {
+        matches!(
+            self,
+            AST::Call { .. }
+                | AST::StaticCall { .. }
+                | AST::New { .. }
+                | AST::AnonymousClass { .. }
+                | AST::Assign { .. }
+                | AST::Increment { .. }
+                | AST::StackInput { .. }
+        )
+    }
+
+    fn precedence(&self) -> Precedence {
+        if let Some(comparison) = self.get_float_comparison() {
+            return comparison.precedence();
+        }
         match self {
-            AST::Set { index, value } => {
-                let var_name = if *index == 0 && !is_static {
-                    format!("this")
-                } else {
-                    format!("var{}", index)
-                };
-                format!(
-                    "{} = {};",
-                    var_name,
-                    value.to_java(is_static, get_class_name)
-                )
+            AST::Binary { operator, .. } => operator.precedence(),
+            AST::Unary { .. } | AST::BasicCast { .. } | AST::ClassCast { .. } => Precedence::Unary,
+            AST::InstanceOf { .. } => Precedence::Relational,
+            AST::SwitchExpression { .. } | AST::Increment { .. } => Precedence::Unary,
+            AST::Concat { .. } => Precedence::Additive,
+            AST::Ternary { .. } => Precedence::Ternary,
+            AST::Lambda { .. } | AST::Assign { .. } => Precedence::Lambda,
+            // negative literals behave like a unary minus applied to a literal
+            AST::ConstInt { value } if *value < 0 && *value != i32::MIN => Precedence::Unary,
+            AST::ConstLong { value } if *value < 0 && *value != i64::MIN => Precedence::Unary,
+            AST::ConstFloat { value } if value.is_sign_negative() && value.is_finite() => {
+                Precedence::Unary
             }
-            AST::Variable { index, vartype: _ } => {
-                if *index == 0 && !is_static {
-                    format!("this")
-                } else {
-                    format!("var{}", index)
-                }
+            AST::ConstDouble { value } if value.is_sign_negative() && value.is_finite() => {
+                Precedence::Unary
             }
-            AST::Call {
-                method_data,
-                reference,
+            AST::Set { .. }
+            | AST::SetField { .. }
+            | AST::SetStatic { .. }
+            | AST::SetArrayElement { .. }
+            | AST::CompoundAssign { .. }
+            | AST::VoidReturn
+            | AST::Return { .. }
+            | AST::If { .. }
+            | AST::While { .. }
+            | AST::DoWhile { .. }
+            | AST::For { .. }
+            | AST::ForEach { .. }
+            | AST::Break
+            | AST::Continue
+            | AST::Try { .. }
+            | AST::Throw { .. }
+            | AST::Assert { .. }
+            | AST::MonitorEnter { .. }
+            | AST::MonitorExit { .. }
+            | AST::Synchronized { .. }
+            | AST::Switch { .. }
+            | AST::Yield { .. }
+            | AST::LocalClass { .. }
+            | AST::PatternRestart { .. } => Precedence::Lambda,
+            _ => Precedence::Primary,
+        }
+    }
+
+    /// Returns the condition that holds exactly if this one does not, applying De Morgan's laws.
+    fn negate(self) -> AST {
+        match self {
+            AST::Binary { operator, lhs, rhs } => match (operator.negated(), operator) {
+                (Some(operator), _) => AST::Binary { operator, lhs, rhs },
+                (None, BinaryOperator::And) => AST::Binary {
+                    operator: BinaryOperator::Or,
+                    lhs: Box::new(lhs.negate()),
+                    rhs: Box::new(rhs.negate()),
+                },
+                (None, BinaryOperator::Or) => AST::Binary {
+                    operator: BinaryOperator::And,
+                    lhs: Box::new(lhs.negate()),
+                    rhs: Box::new(rhs.negate()),
+                },
+                (None, operator) => AST::Unary {
+                    operator: UnaryOperator::Not,
+                    value: Box::new(AST::Binary { operator, lhs, rhs }),
+                },
+            },
+            AST::Unary {
+                operator: UnaryOperator::Not,
+                value,
+            } => *value,
+            AST::ConstBoolean { value } => AST::ConstBoolean { value: !value },
+            AST::Ternary {
+                condition,
+                then,
+                otherwise,
+            } => AST::Ternary {
+                condition,
+                then: Box::new(then.negate()),
+                otherwise: Box::new(otherwise.negate()),
+            },
+            value => AST::Unary {
+                operator: UnaryOperator::Not,
+                value: Box::new(value),
+            },
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut AST> {
+        match self {
+            AST::BasicCast { value, .. }
+            | AST::ClassCast { value, .. }
+            | AST::Set { value, .. }
+            | AST::SetStatic { value, .. }
+            | AST::Unary { value, .. }
+            | AST::InstanceOf { value, .. }
+            | AST::Throw { value }
+            | AST::MonitorEnter { value }
+            | AST::MonitorExit { value }
+            | AST::Yield { value }
+            | AST::Return { value } => vec![value.as_mut()],
+            AST::Increment { target, .. }
+            | AST::Field {
+                reference: target, ..
+            } => vec![target.as_mut()],
+            AST::Assign { target, value } => vec![target.as_mut(), value.as_mut()],
+            AST::Assert { condition, message } => {
+                let mut children = vec![condition.as_mut()];
+                children.extend(message.as_deref_mut());
+                children
+            }
+            AST::CompoundAssign { target, value, .. } => vec![target.as_mut(), value.as_mut()],
+            AST::ArrayLength { reference } => vec![reference.as_mut()],
+            AST::ArrayElement { array, index } => vec![array.as_mut(), index.as_mut()],
+            AST::SetArrayElement {
+                array,
+                index,
+                value,
+            } => vec![array.as_mut(), index.as_mut(), value.as_mut()],
+            AST::NewArray {
+                dimensions,
+                elements,
+                ..
+            } => dimensions.iter_mut().chain(elements.iter_mut()).collect(),
+            AST::Lambda { body, captured, .. } => {
+                body.iter_mut().chain(captured.iter_mut()).collect()
+            }
+            AST::MethodReference { reference, .. } => {
+                reference.iter_mut().map(|r| r.as_mut()).collect()
+            }
+            AST::SetField {
+                reference, value, ..
+            } => vec![reference.as_mut(), value.as_mut()],
+            AST::Binary { lhs, rhs, .. } | AST::Compare { lhs, rhs, .. } => {
+                vec![lhs.as_mut(), rhs.as_mut()]
+            }
+            AST::Call {
+                reference, args, ..
+            } => {
+                let mut children = vec![reference.as_mut()];
+                children.extend(args.iter_mut());
+                children
+            }
+            AST::New { args, .. }
+            | AST::StaticCall { args, .. }
+            | AST::AnonymousClass { args, .. }
+            | AST::DynamicConstant { args, .. } => args.iter_mut().collect(),
+            AST::Concat { values } => values.iter_mut().collect(),
+            AST::Ternary {
+                condition,
+                then,
+                otherwise,
+            } => vec![condition.as_mut(), then.as_mut(), otherwise.as_mut()],
+            AST::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let mut children = vec![condition.as_mut()];
+                children.extend(then.iter_mut().chain(otherwise.iter_mut()));
+                children
+            }
+            AST::While { condition, body } | AST::DoWhile { body, condition } => {
+                let mut children = vec![condition.as_mut()];
+                children.extend(body.iter_mut());
+                children
+            }
+            AST::For {
+                condition,
+                update,
+                body,
+            } => {
+                let mut children = vec![condition.as_mut()];
+                children.extend(update.iter_mut().chain(body.iter_mut()));
+                children
+            }
+            AST::ForEach {
+                iterable: value,
+                body,
+                ..
+            }
+            | AST::Synchronized { lock: value, body } => {
+                let mut children = vec![value.as_mut()];
+                children.extend(body.iter_mut());
+                children
+            }
+            AST::Switch { value, cases } | AST::SwitchExpression { value, cases } => {
+                let mut children = vec![value.as_mut()];
+                for case in cases {
+                    children.extend(case.guard.as_mut());
+                    children.extend(case.body.iter_mut());
+                }
+                children
+            }
+            AST::PatternSwitchIndex { value, restart, .. } => {
+                vec![value.as_mut(), restart.as_mut()]
+            }
+            AST::Try {
+                resources,
+                body,
+                catches,
+                finally,
+            } => resources
+                .iter_mut()
+                .map(|resource| &mut resource.value)
+                .chain(body.iter_mut())
+                .chain(catches.iter_mut().flat_map(|catch| catch.body.iter_mut()))
+                .chain(finally.iter_mut())
+                .collect(),
+            AST::Static { .. }
+            | AST::Variable { .. }
+            | AST::ConstInt { .. }
+            | AST::ConstLong { .. }
+            | AST::ConstFloat { .. }
+            | AST::ConstDouble { .. }
+            | AST::ConstChar { .. }
+            | AST::ConstBoolean { .. }
+            | AST::ConstString { .. }
+            | AST::ConstNull
+            | AST::ClassLiteral { .. }
+            | AST::MethodTypeConstant { .. }
+            | AST::MethodHandleConstant { .. }
+            | AST::VoidReturn
+            | AST::Break
+            | AST::Continue
+            | AST::Uninitialized { .. }
+            | AST::PatternRestart { .. }
+            | AST::LocalClass { .. }
+            | AST::StackInput { .. }
+            | AST::NamedVariable { .. } => Vec::new(),
+        }
+    }
+
+    /// Returns the blocks of statements nested in a statement, including the cases of `switch`
+    /// expressions it evaluates.
+    fn statement_lists_mut(&mut self) -> Vec<&mut Vec<AST>> {
+        match self {
+            AST::If {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            AST::While { body, .. }
+            | AST::DoWhile { body, .. }
+            | AST::For { body, .. }
+            | AST::ForEach { body, .. }
+            | AST::Synchronized { body, .. } => vec![body],
+            AST::Try {
+                body,
+                catches,
+                finally,
+                ..
+            } => {
+                let mut lists = vec![body];
+                lists.extend(catches.iter_mut().map(|catch| &mut catch.body));
+                lists.push(finally);
+                lists
+            }
+            AST::Switch { cases, .. } | AST::SwitchExpression { cases, .. } => {
+                cases.iter_mut().map(|case| &mut case.body).collect()
+            }
+            // lambda bodies are decompiled on their own
+            AST::Lambda { .. } => Vec::new(),
+            _ => self
+                .children_mut()
+                .into_iter()
+                .flat_map(|child| child.statement_lists_mut())
+                .collect(),
+        }
+    }
+
+    /// Checks if the statement contains blocks of other statements.
+    fn is_block_statement(&self) -> bool {
+        matches!(
+            self,
+            AST::If { .. }
+                | AST::While { .. }
+                | AST::DoWhile { .. }
+                | AST::For { .. }
+                | AST::ForEach { .. }
+                | AST::Synchronized { .. }
+                | AST::Try { .. }
+                | AST::Switch { .. }
+        )
+    }
+
+    /// Returns the value and the cases of a `switch` statement, or of the first `switch`
+    /// expression evaluated by a statement.
+    fn get_switch_mut(&mut self) -> Option<(&mut Box<AST>, &mut Vec<Case>)> {
+        match self {
+            AST::Switch { value, cases } | AST::SwitchExpression { value, cases } => {
+                Some((value, cases))
+            }
+            AST::Lambda { .. } => None,
+            _ if self.is_block_statement() => None,
+            _ => self
+                .children_mut()
+                .into_iter()
+                .find_map(|child| child.get_switch_mut()),
+        }
+    }
+
+    /// Replaces the placeholders for values of a preceding block, `values[0]` being its top.
+    fn substitute_inputs(&mut self, values: &[AST]) {
+        match self {
+            AST::StackInput { index } => *self = values[*index].clone(),
+            _ => {
+                for child in self.children_mut() {
+                    child.substitute_inputs(values);
+                }
+            }
+        }
+    }
+
+    /// Renders the expression as the operand of another expression, adding parentheses if the
+    /// operand binds looser than `precedence` requires.
+    fn operand_to_java(
+        &self,
+        precedence: Precedence,
+        is_static: bool,
+        names: &NameResolver,
+    ) -> String {
+        let java = self.to_java(is_static, names);
+        if self.precedence() < precedence {
+            format!("({})", java)
+        } else {
+            java
+        }
+    }
+
+    fn to_java(&self, is_static: bool, names: &NameResolver) -> String {
+        match self {
+            AST::Set { index, value } => {
+                let var_name = if *index == 0 && !is_static {
+                    String::from("this")
+                } else {
+                    format!("var{}", index)
+                };
+                format!("{} = {};", var_name, value.to_java(is_static, names))
+            }
+            AST::SetField {
+                field_data,
+                reference,
+                value,
+            } => format!(
+                "{}.{} = {};",
+                reference.operand_to_java(Precedence::Primary, is_static, names),
+                field_data.name_and_type.name,
+                value.to_java(is_static, names)
+            ),
+            AST::SetStatic { field_data, value } => format!(
+                "{} = {};",
+                static_field_to_java(field_data, names),
+                value.to_java(is_static, names)
+            ),
+            AST::Variable { index, vartype: _ } => {
+                if *index == 0 && !is_static {
+                    String::from("this")
+                } else {
+                    format!("var{}", index)
+                }
+            }
+            AST::Call {
+                method_data,
+                reference,
                 args,
             } => {
-                let reference = reference.to_java(is_static, get_class_name);
                 let name = &method_data.name_and_type.name;
                 let args = args
                     .iter()
-                    .map(|e| e.to_java(is_static, get_class_name))
+                    .map(|e| e.to_java(is_static, names))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                // a constructor called on the object being initialized is `this(...)` or `super(...)`
+                if name == "<init>" && is_variable(reference, 0) {
+                    if names.is_current_class(&method_data.class.name) {
+                        format!("this({})", args)
+                    } else {
+                        format!("super({})", args)
+                    }
+                } else {
+                    let reference =
+                        reference.operand_to_java(Precedence::Primary, is_static, names);
+                    format!("{}.{}({})", reference, name, args)
+                }
+            }
+            AST::StaticCall { method_data, args } => {
+                let args = args
+                    .iter()
+                    .map(|e| e.to_java(is_static, names))
                     .collect::<Vec<String>>()
                     .join(", ");
-                format!("{}.{}({});", reference, name, args)
+                // static methods of the class being written are called without qualification
+                if names.is_current_class(&method_data.class.name) {
+                    format!("{}({})", method_data.name_and_type.name, args)
+                } else {
+                    format!(
+                        "{}.{}({})",
+                        names.get_class_name(&method_data.class.name),
+                        method_data.name_and_type.name,
+                        args
+                    )
+                }
+            }
+            AST::Binary { .. } if self.get_float_comparison().is_some() => self
+                .get_float_comparison()
+                .unwrap()
+                .to_java(is_static, names),
+            AST::Binary { operator, lhs, rhs } => {
+                // all binary operators are left associative, so only the left side may bind equally
+                let precedence = operator.precedence();
+                let rhs_java = rhs.to_java(is_static, names);
+                let rhs_java = if rhs.precedence() <= precedence {
+                    format!("({})", rhs_java)
+                } else {
+                    rhs_java
+                };
+                format!(
+                    "{} {} {}",
+                    lhs.operand_to_java(precedence, is_static, names),
+                    operator.symbol(),
+                    rhs_java
+                )
+            }
+            AST::Unary { operator, value } => {
+                let value_java = value.operand_to_java(Precedence::Unary, is_static, names);
+                if value_java.starts_with(['-', '+']) {
+                    // avoid emitting "--" or "-+", which would be read as different operators
+                    format!("{}({})", operator.symbol(), value_java)
+                } else {
+                    format!("{}{}", operator.symbol(), value_java)
+                }
             }
-            AST::Mul { lhs, rhs } => format!(
-                "{} * {}",
-                lhs.to_java(is_static, get_class_name),
-                rhs.to_java(is_static, get_class_name)
+            AST::InstanceOf { value, pattern } => format!(
+                "{} instanceof {}",
+                value.operand_to_java(Precedence::Relational, is_static, names),
+                pattern.to_java(names)
             ),
-            AST::ConstInt { value } => format!("{}", value),
-            AST::ConstFloat { value } => format!("{}", value),
-            AST::VoidReturn => String::from("return;"),
-            AST::BasicCast { cast_type, value } => format!(
-                "(({}) ({}))",
-                cast_type,
-                value.to_java(is_static, get_class_name)
+            AST::Ternary {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let otherwise_java = otherwise.to_java(is_static, names);
+                let otherwise_java = match otherwise.precedence() {
+                    Precedence::Lambda if !matches!(otherwise.as_ref(), AST::Lambda { .. }) => {
+                        format!("({})", otherwise_java)
+                    }
+                    _ => otherwise_java,
+                };
+                let condition_java = condition.to_java(is_static, names);
+                let condition_java = if condition.precedence() <= Precedence::Ternary {
+                    format!("({})", condition_java)
+                } else {
+                    condition_java
+                };
+                format!(
+                    "{} ? {} : {}",
+                    condition_java,
+                    then.to_java(is_static, names),
+                    otherwise_java
+                )
+            }
+            AST::Lambda { params, body, .. } => {
+                let params: Vec<String> =
+                    params.iter().map(|index| format!("var{}", index)).collect();
+                let params = match params.as_slice() {
+                    [param] => param.clone(),
+                    _ => format!("({})", params.join(", ")),
+                };
+                let body = match body.as_slice() {
+                    [] => String::from("{}"),
+                    [AST::Return { value }] => value.to_java(is_static, names),
+                    [statement] if statement.is_expression_statement() => {
+                        statement.to_java(is_static, names)
+                    }
+                    _ => format!("{{\n{}}}", block_to_java(body, is_static, names)),
+                };
+                format!("{} -> {}", params, body)
+            }
+            AST::MethodReference {
+                method_data,
+                reference,
+            } => {
+                let reference = match reference {
+                    Some(reference) => {
+                        reference.operand_to_java(Precedence::Primary, is_static, names)
+                    }
+                    None => names.get_class_name(&method_data.class.name),
+                };
+                let name = match method_data.name_and_type.name.as_str() {
+                    "<init>" => "new",
+                    name => name,
+                };
+                format!("{}::{}", reference, name)
+            }
+            AST::Static { field_data } => static_field_to_java(field_data, names),
+            AST::Field {
+                field_data,
+                reference,
+            } => match (reference.as_ref(), &field_data.name_and_type) {
+                // the enclosing instance of an inner class
+                (AST::Variable { index: 0, .. }, name_and_type)
+                    if !is_static && name_and_type.name.starts_with("this$") =>
+                {
+                    match descriptors::parse_field(name_and_type.descriptor.chars()) {
+                        Ok(field_type) => format!("{}.this", field_type.to_java(names)),
+                        Err(_) => format!("this.{}", name_and_type.name),
+                    }
+                }
+                (reference, name_and_type) => format!(
+                    "{}.{}",
+                    reference.operand_to_java(Precedence::Primary, is_static, names),
+                    name_and_type.name
+                ),
+            },
+            AST::Assign { target, value } => format!(
+                "{} = {}",
+                target.to_java(is_static, names),
+                value.to_java(is_static, names)
+            ),
+            AST::ClassLiteral { class_type } => format!("{}.class", class_type.to_java(names)),
+            AST::MethodTypeConstant { descriptor } => method_type_to_java(descriptor, names),
+            AST::MethodHandleConstant { handle } => method_handle_to_java(handle, names),
+            AST::DynamicConstant {
+                bootstrap_method,
+                name_and_type,
+                args,
+            } => {
+                // bootstrap methods are called with a lookup, the name and the type first
+                let class_type = match descriptors::parse_field(name_and_type.descriptor.chars()) {
+                    Ok(field_type) => field_type.to_java(names),
+                    Err(_) => names.get_class_name("java/lang/Object"),
+                };
+                let mut java_args = vec![
+                    format!("{}.lookup()", names.get_class_name(METHOD_HANDLES)),
+                    format!("\"{}\"", name_and_type.name),
+                    format!("{}.class", class_type),
+                ];
+                java_args.extend(args.iter().map(|arg| arg.to_java(is_static, names)));
+                format!(
+                    "{}.{}({})",
+                    names.get_class_name(&bootstrap_method.class.name),
+                    bootstrap_method.name_and_type.name,
+                    java_args.join(", ")
+                )
+            }
+            AST::ArrayElement { array, index } => format!(
+                "{}[{}]",
+                array.operand_to_java(Precedence::Primary, is_static, names),
+                index.to_java(is_static, names)
             ),
-            AST::ClassCast { cast_type, value } => format!(
-                "(({}) ({}))",
-                get_class_name(&cast_type.name),
-                value.to_java(is_static, get_class_name)
+            AST::SetArrayElement {
+                array,
+                index,
+                value,
+            } => format!(
+                "{}[{}] = {};",
+                array.operand_to_java(Precedence::Primary, is_static, names),
+                index.to_java(is_static, names),
+                value.to_java(is_static, names)
             ),
-            _ => unimplemented!("{:?}", self),
-        }
-    }
-}
-
-fn decompile_block(
-    block: &Block,
-    constant_pool: &ConstantPool,
-) -> Result<Vec<AST>, DecompilerError> {
-    let mut statements = Vec::new();
-
-    let mut stack: Vec<AST> = Vec::new();
-    for (pos, code) in &block.instructions {
-        println!("{}: {:?}", pos, code);
-        match code {
-            Instruction::ILoad { index } => {
-                stack.push(AST::Variable {
-                    index: *index,
-                    vartype: VarType::Int,
-                });
+            AST::CompoundAssign {
+                target,
+                operator,
+                value,
+                is_concat,
+            } => {
+                let target = target.to_java(is_static, names);
+                match (operator, !is_concat && is_one(value)) {
+                    (BinaryOperator::Add, true) => format!("{}++;", target),
+                    (BinaryOperator::Sub, true) => format!("{}--;", target),
+                    _ => format!(
+                        "{} {}= {};",
+                        target,
+                        operator.symbol(),
+                        value.to_java(is_static, names)
+                    ),
+                }
             }
-            Instruction::LLoad { index } => {
-                stack.push(AST::Variable {
-                    index: *index,
-                    vartype: VarType::Long,
-                });
+            AST::Increment {
+                target,
+                operator,
+                is_prefix,
+            } => {
+                let target = target.operand_to_java(Precedence::Primary, is_static, names);
+                let symbol = match operator {
+                    BinaryOperator::Sub => "--",
+                    _ => "++",
+                };
+                match is_prefix {
+                    true => format!("{}{}", symbol, target),
+                    false => format!("{}{}", target, symbol),
+                }
             }
-            Instruction::FLoad { index } => {
-                stack.push(AST::Variable {
-                    index: *index,
-                    vartype: VarType::Float,
-                });
+            AST::NewArray {
+                array_type,
+                dimensions,
+                elements,
+            } => {
+                if !elements.is_empty() {
+                    return format!(
+                        "new {}{}",
+                        array_type.to_java(names),
+                        array_initializer_to_java(elements, is_static, names)
+                    );
+                }
+                let mut element_type = array_type;
+                let mut depth = 0;
+                while let descriptors::FieldType::Array { inner } = element_type {
+                    element_type = inner;
+                    depth += 1;
+                }
+                let mut java = format!("new {}", element_type.to_java(names));
+                for dimension in dimensions.iter() {
+                    java.push_str(&format!("[{}]", dimension.to_java(is_static, names)));
+                }
+                for _ in dimensions.len()..depth {
+                    java.push_str("[]");
+                }
+                java
             }
-            Instruction::DLoad { index } => {
-                stack.push(AST::Variable {
-                    index: *index,
-                    vartype: VarType::Double,
-                });
+            AST::ArrayLength { reference } => format!(
+                "{}.length",
+                reference.operand_to_java(Precedence::Primary, is_static, names)
+            ),
+            AST::ConstInt { value } => match *value {
+                i32::MIN => format!("{}.MIN_VALUE", names.get_class_name("java/lang/Integer")),
+                i32::MAX => format!("{}.MAX_VALUE", names.get_class_name("java/lang/Integer")),
+                value => format!("{}", value),
+            },
+            AST::ConstLong { value } => match *value {
+                i64::MIN => format!("{}.MIN_VALUE", names.get_class_name("java/lang/Long")),
+                i64::MAX => format!("{}.MAX_VALUE", names.get_class_name("java/lang/Long")),
+                value => format!("{}L", value),
+            },
+            AST::ConstFloat { value } => {
+                let class_name = names.get_class_name("java/lang/Float");
+                match get_special_float_name(
+                    *value as f64,
+                    f32::MAX as f64,
+                    f32::MIN_POSITIVE as f64,
+                    f32::from_bits(1) as f64,
+                ) {
+                    Some(name) => format!("{}.{}", class_name, name),
+                    None => format!("{}F", float_to_java(*value)),
+                }
             }
-            Instruction::ALoad { index } => {
-                stack.push(AST::Variable {
-                    index: *index,
-                    vartype: VarType::Reference,
-                });
+            AST::ConstDouble { value } => {
+                let class_name = names.get_class_name("java/lang/Double");
+                match get_special_float_name(*value, f64::MAX, f64::MIN_POSITIVE, f64::from_bits(1))
+                {
+                    Some(name) => format!("{}.{}", class_name, name),
+                    None => float_to_java(*value),
+                }
             }
-            Instruction::InvokeSpecial { index } | Instruction::InvokeVirtual { index } => {
-                let method = constant_pool.get_method_or_interface_entry(*index)?;
-                let descriptor =
-                    descriptors::parse_method(method.name_and_type.descriptor.chars())?;
-                println!("{:?}", descriptor);
-                let mut args = Vec::new();
-                for _ in 0..descriptor.0.len() {
-                    args.push(stack.pop().ok_or(DecompilerError::EmptyStack)?);
+            AST::ConstChar { value } => format!("'{}'", escape_char(*value, '\'')),
+            AST::ConstBoolean { value } => format!("{}", value),
+            AST::ConstString { value } => {
+                let escaped: String = value
+                    .encode_utf16()
+                    .map(|ch| escape_char(ch, '"'))
+                    .collect();
+                format!("\"{}\"", escaped)
+            }
+            AST::ConstNull => String::from("null"),
+            AST::VoidReturn => String::from("return;"),
+            AST::Return { value } => format!("return {};", value.to_java(is_static, names)),
+            AST::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let mut java = format!(
+                    "if ({}) {{\n{}}}",
+                    condition.to_java(is_static, names),
+                    block_to_java(then, is_static, names)
+                );
+                match otherwise.as_slice() {
+                    [] => {}
+                    [else_if @ AST::If { .. }] => {
+                        java.push_str(" else ");
+                        java.push_str(&else_if.to_java(is_static, names));
+                    }
+                    _ => java.push_str(&format!(
+                        " else {{\n{}}}",
+                        block_to_java(otherwise, is_static, names)
+                    )),
                 }
-                args.reverse();
-                let reference = Box::new(stack.pop().ok_or(DecompilerError::EmptyStack)?);
-                if descriptor.1 == descriptors::FieldType::Void {
-                    statements.push(AST::Call {
-                        method_data: method,
-                        reference,
-                        args,
-                    });
-                } else {
-                    stack.push(AST::Call {
-                        method_data: method,
-                        reference,
-                        args,
-                    });
+                java
+            }
+            AST::While { condition, body } => format!(
+                "while ({}) {{\n{}}}",
+                condition.to_java(is_static, names),
+                block_to_java(body, is_static, names)
+            ),
+            AST::DoWhile { body, condition } => format!(
+                "do {{\n{}}} while ({});",
+                block_to_java(body, is_static, names),
+                condition.to_java(is_static, names)
+            ),
+            AST::For {
+                condition,
+                update,
+                body,
+            } => {
+                let update: Vec<String> = update
+                    .iter()
+                    .map(|statement| {
+                        let java = statement.to_java(is_static, names);
+                        String::from(java.strip_suffix(';').unwrap_or(&java))
+                    })
+                    .collect();
+                format!(
+                    "for (; {}; {}) {{\n{}}}",
+                    condition.to_java(is_static, names),
+                    update.join(", "),
+                    block_to_java(body, is_static, names)
+                )
+            }
+            AST::ForEach {
+                index,
+                var_type,
+                iterable,
+                body,
+            } => format!(
+                "for ({} var{} : {}) {{\n{}}}",
+                var_type
+                    .as_ref()
+                    .map_or_else(|| String::from("var"), |var_type| var_type.to_java(names)),
+                index,
+                iterable.to_java(is_static, names),
+                block_to_java(body, is_static, names)
+            ),
+            AST::Try {
+                resources,
+                body,
+                catches,
+                finally,
+            } => {
+                let mut java = String::from("try ");
+                if !resources.is_empty() {
+                    let resources: Vec<String> = resources
+                        .iter()
+                        .map(|resource| {
+                            format!(
+                                "{} var{} = {}",
+                                names.get_class_name(&resource.class_data.name),
+                                resource.index,
+                                resource.value.to_java(is_static, names)
+                            )
+                        })
+                        .collect();
+                    java.push_str(&format!("({}) ", resources.join("; ")));
                 }
+                java.push_str(&format!("{{\n{}}}", block_to_java(body, is_static, names)));
+                for catch in catches {
+                    let classes: Vec<String> = catch
+                        .classes
+                        .iter()
+                        .map(|class_data| names.get_class_name(&class_data.name))
+                        .collect();
+                    java.push_str(&format!(
+                        " catch ({} var{}) {{\n{}}}",
+                        classes.join(" | "),
+                        catch.index,
+                        block_to_java(&catch.body, is_static, names)
+                    ));
+                }
+                if !finally.is_empty() {
+                    java.push_str(&format!(
+                        " finally {{\n{}}}",
+                        block_to_java(finally, is_static, names)
+                    ));
+                }
+                java
             }
-            Instruction::Return => {
-                statements.push(AST::VoidReturn);
+            AST::Throw { value } => format!("throw {};", value.to_java(is_static, names)),
+            AST::Assert { condition, message } => match message {
+                Some(message) => format!(
+                    "assert {} : {};",
+                    condition.to_java(is_static, names),
+                    message.to_java(is_static, names)
+                ),
+                None => format!("assert {};", condition.to_java(is_static, names)),
+            },
+            // only left if the pattern `switch` could not be recovered
+            AST::PatternSwitchIndex {
+                value,
+                restart,
+                labels,
+            } => {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|label| match label {
+                        PatternLabel::Class(class_data) => names.get_class_name(&class_data.name),
+                        PatternLabel::Constant(value) => value.to_java(is_static, names),
+                    })
+                    .collect();
+                format!(
+                    "/* match {} from {} */ {}",
+                    labels.join(", "),
+                    restart.to_java(is_static, names),
+                    value.to_java(is_static, names)
+                )
             }
-            Instruction::IStore { index }
-            | Instruction::LStore { index }
-            | Instruction::FStore { index }
-            | Instruction::DStore { index }
-            | Instruction::AStore { index } => {
-                statements.push(AST::Set {
-                    index: *index,
-                    value: Box::new(stack.pop().ok_or(DecompilerError::EmptyStack)?),
-                });
+            AST::PatternRestart { label } => format!("/* continue matching at {} */", label),
+            // only left if the monitor is not used by a `synchronized` statement
+            AST::MonitorEnter { value } => {
+                format!("/* monitorenter */ {};", value.to_java(is_static, names))
             }
-            Instruction::GetStatic { index } => {
-                let field = constant_pool.get_field_entry(*index)?;
-                stack.push(AST::Static { field_data: field });
+            AST::MonitorExit { value } => {
+                format!("/* monitorexit */ {};", value.to_java(is_static, names))
             }
-            Instruction::ArrayLength => {
-                let reference = Box::new(stack.pop().ok_or(DecompilerError::EmptyStack)?);
-                stack.push(AST::ArrayLength { reference });
+            AST::Synchronized { lock, body } => format!(
+                "synchronized ({}) {{\n{}}}",
+                lock.to_java(is_static, names),
+                block_to_java(body, is_static, names)
+            ),
+            AST::Switch { value, cases } => {
+                let mut java = format!("switch ({}) {{\n", value.to_java(is_static, names));
+                for case in cases {
+                    case.write_labels(&mut java, is_static, names);
+                    write_statements(&mut java, &case.body, is_static, 8, names);
+                }
+                java.push('}');
+                java
             }
-            Instruction::LoadConst { index } => {
-                let value = match constant_pool.get_entry(*index)? {
-                    ConstantPoolInfo::String { string_index } => AST::ConstString {
-                        value: constant_pool.get_utf8_entry(string_index)?,
-                    },
-                    ConstantPoolInfo::Long { data } => AST::ConstInt { value: data },
-                    ConstantPoolInfo::Integer { data } => AST::ConstInt { value: data as i64 },
-                    ConstantPoolInfo::Double { data } => AST::ConstFloat { value: data },
-                    ConstantPoolInfo::Float { data } => AST::ConstFloat { value: data as f64 },
-                    _ => unimplemented!(),
+            AST::SwitchExpression { value, cases } => {
+                let mut java = format!("switch ({}) {{\n", value.to_java(is_static, names));
+                // cases that do not fall through can be written with arrows
+                let has_arrows = cases.iter().all(|case| {
+                    matches!(
+                        case.body.last(),
+                        Some(AST::Yield { .. } | AST::Throw { .. })
+                    ) && (!case.is_default
+                        || matches!(case.labels.as_slice(), [] | [AST::ConstNull]))
+                });
+                for case in cases {
+                    if !has_arrows {
+                        case.write_labels(&mut java, is_static, names);
+                        write_statements(&mut java, &case.body, is_static, 8, names);
+                        continue;
+                    }
+                    let labels = case.get_labels(is_static, names).join(", ");
+                    let labels = match (labels.is_empty(), case.is_default) {
+                        (true, _) => String::from("default"),
+                        (false, true) => format!("case {}, default", labels),
+                        (false, false) => format!("case {}", labels),
+                    };
+                    let body = match case.body.as_slice() {
+                        [AST::Yield { value }] => format!("{};", value.to_java(is_static, names)),
+                        [statement @ AST::Throw { .. }] => statement.to_java(is_static, names),
+                        body => format!("{{\n{}}}", block_to_java(body, is_static, names)),
+                    };
+                    for line in format!("{} -> {}", labels, body).lines() {
+                        java.push_str(&format!("    {}\n", line));
+                    }
+                }
+                java.push('}');
+                java
+            }
+            AST::Yield { value } => format!("yield {};", value.to_java(is_static, names)),
+            AST::Break => String::from("break;"),
+            AST::Continue => String::from("continue;"),
+            AST::Compare {
+                lhs,
+                rhs,
+                compare_type,
+                ..
+            } => {
+                let class_name = match compare_type {
+                    VarType::Long => "java/lang/Long",
+                    VarType::Float => "java/lang/Float",
+                    _ => "java/lang/Double",
                 };
-                stack.push(value);
+                format!(
+                    "{}.compare({}, {})",
+                    names.get_class_name(class_name),
+                    lhs.to_java(is_static, names),
+                    rhs.to_java(is_static, names)
+                )
             }
-            Instruction::IConst { value } => stack.push(AST::ConstInt {
-                value: *value as i64,
-            }),
-            Instruction::IMul => {
-                let rhs = Box::new(stack.pop().ok_or(DecompilerError::EmptyStack)?);
-                let lhs = Box::new(stack.pop().ok_or(DecompilerError::EmptyStack)?);
-                stack.push(AST::Mul { lhs, rhs });
-            }
-            Instruction::I2b => {
-                let cast_type = VarType::Byte;
-                let value = Box::new(stack.pop().ok_or(DecompilerError::EmptyStack)?);
-                stack.push(AST::BasicCast { cast_type, value })
+            AST::StackInput { index } => format!("stack{}", index),
+            AST::NamedVariable { name } => name.clone(),
+            AST::Uninitialized { class_data } => {
+                format!("new {}", names.get_class_name(&class_data.name))
             }
-            Instruction::CheckCast { index } => {
-                let cast_type = constant_pool.get_class_entry(*index)?;
-                let value = Box::new(stack.pop().ok_or(DecompilerError::EmptyStack)?);
-                stack.push(AST::ClassCast { cast_type, value })
+            AST::New { method_data, args } => {
+                let args: Vec<String> = args
+                    .iter()
+                    .map(|arg| arg.to_java(is_static, names))
+                    .collect();
+                format!(
+                    "new {}({})",
+                    names.get_class_name(&method_data.class.name),
+                    args.join(", ")
+                )
             }
-            _ => unimplemented!(),
-        }
-    }
-    if stack.len() != 0 {
-        return Err(DecompilerError::StackSize { size: stack.len() });
-    }
+            AST::AnonymousClass {
+                class_name,
+                supertype,
+                args,
+            } => {
+                let args: Vec<String> = args
+                    .iter()
+                    .map(|arg| arg.to_java(is_static, names))
+                    .collect();
+                let body = match names.get_declaration(class_name) {
+                    Some(body) if !body.is_empty() => format!("{{\n{}}}", body),
+                    _ => String::from("{}"),
+                };
+                format!(
+                    "new {}({}) {}",
+                    supertype.to_java(names),
+                    args.join(", "),
+                    body
+                )
+            }
+            AST::LocalClass { class_name } => names
+                .get_declaration(class_name)
+                .unwrap_or_else(|| format!("/* class {} */", class_name)),
+            AST::Concat { values } => {
+                let values: Vec<String> = values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        // like all binary operators concatenation is left associative
+                        if i > 0 && value.precedence() <= Precedence::Additive {
+                            format!("({})", value.to_java(is_static, names))
+                        } else {
+                            value.operand_to_java(Precedence::Additive, is_static, names)
+                        }
+                    })
+                    .collect();
+                values.join(" + ")
+            }
+            AST::BasicCast { cast_type, value } => format!(
+                "({}) {}",
+                cast_type,
+                value.operand_to_java(Precedence::Unary, is_static, names)
+            ),
+            AST::ClassCast { cast_type, value } => {
+                let value_java = value.operand_to_java(Precedence::Unary, is_static, names);
+                // a reference cast followed by a sign would be parsed as a binary operation
+                let value_java = if value_java.starts_with(['-', '+']) {
+                    format!("({})", value_java)
+                } else {
+                    value_java
+                };
+                format!("({}) {}", names.get_class_name(&cast_type.name), value_java)
+            }
+        }
+    }
+}
+
+fn get_constant(
+    entry: ConstantPoolInfo,
+    constant_pool: &ConstantPool,
+) -> Result<Option<AST>, DecompilerError> {
+    Ok(Some(match entry {
+        ConstantPoolInfo::String { string_index } => AST::ConstString {
+            value: constant_pool.get_utf8_entry(string_index)?,
+        },
+        ConstantPoolInfo::Integer { data } => AST::ConstInt { value: data },
+        ConstantPoolInfo::Long { data } => AST::ConstLong { value: data },
+        ConstantPoolInfo::Float { data } => AST::ConstFloat { value: data },
+        ConstantPoolInfo::Double { data } => AST::ConstDouble { value: data },
+        ConstantPoolInfo::Class { name_index } => {
+            get_class_literal(&constant_pool.get_utf8_entry(name_index)?)?
+        }
+        ConstantPoolInfo::MethodType { descriptor_index } => AST::MethodTypeConstant {
+            descriptor: constant_pool.get_utf8_entry(descriptor_index)?,
+        },
+        ConstantPoolInfo::MethodHandle {
+            reference_kind,
+            reference_index,
+        } => {
+            let reference = match reference_kind {
+                1..=4 => MethodHandleReference::Field {
+                    field_data: constant_pool.get_field_entry(reference_index)?,
+                },
+                _ => MethodHandleReference::Method {
+                    method_data: constant_pool.get_method_or_interface_entry(reference_index)?,
+                },
+            };
+            AST::MethodHandleConstant {
+                handle: ConstMethodHandleData {
+                    reference_kind,
+                    reference,
+                },
+            }
+        }
+        _ => return Ok(None),
+    }))
+}
+
+const METHOD_HANDLES: &str = "java/lang/invoke/MethodHandles";
+
+/// Returns the class literal of a class constant, which holds the descriptor of array types
+/// and the internal name of all others.
+fn get_class_literal(name: &str) -> Result<AST, DecompilerError> {
+    let class_type = match name.starts_with('[') {
+        true => descriptors::parse_field(name.chars())?,
+        false => descriptors::FieldType::Reference {
+            name: String::from(name),
+        },
+    };
+    Ok(AST::ClassLiteral { class_type })
+}
+
+fn method_type_to_java(descriptor: &str, names: &NameResolver) -> String {
+    let method_type = names.get_class_name("java/lang/invoke/MethodType");
+    match descriptors::parse_method(descriptor.chars()) {
+        Ok((params, return_type)) => {
+            let classes: Vec<String> = std::iter::once(&return_type)
+                .chain(&params)
+                .map(|field_type| format!("{}.class", field_type.to_java(names)))
+                .collect();
+            format!("{}.methodType({})", method_type, classes.join(", "))
+        }
+        Err(_) => format!(
+            "{}.fromMethodDescriptorString(\"{}\", null)",
+            method_type, descriptor
+        ),
+    }
+}
+
+/// Writes a method handle constant as the lookup of the field or method it refers to.
+fn method_handle_to_java(handle: &ConstMethodHandleData, names: &NameResolver) -> String {
+    let lookup = format!("{}.lookup()", names.get_class_name(METHOD_HANDLES));
+    match &handle.reference {
+        MethodHandleReference::Field { field_data } => {
+            let find = match handle.reference_kind {
+                1 => "findGetter",
+                2 => "findStaticGetter",
+                3 => "findSetter",
+                _ => "findStaticSetter",
+            };
+            let field_type =
+                match descriptors::parse_field(field_data.name_and_type.descriptor.chars()) {
+                    Ok(field_type) => field_type.to_java(names),
+                    Err(_) => names.get_class_name("java/lang/Object"),
+                };
+            format!(
+                "{}.{}({}.class, \"{}\", {}.class)",
+                lookup,
+                find,
+                names.get_class_name(&field_data.class.name),
+                field_data.name_and_type.name,
+                field_type
+            )
+        }
+        MethodHandleReference::Method { method_data } => {
+            let class_name = names.get_class_name(&method_data.class.name);
+            let method_type = method_type_to_java(&method_data.name_and_type.descriptor, names);
+            match handle.reference_kind {
+                // constructors are looked up by their type returning `void`
+                8 => format!(
+                    "{}.findConstructor({}.class, {})",
+                    lookup, class_name, method_type
+                ),
+                7 => format!(
+                    "{}.findSpecial({}.class, \"{}\", {}, {}.class)",
+                    lookup, class_name, method_data.name_and_type.name, method_type, class_name
+                ),
+                kind => format!(
+                    "{}.{}({}.class, \"{}\", {})",
+                    lookup,
+                    if kind == 6 {
+                        "findStatic"
+                    } else {
+                        "findVirtual"
+                    },
+                    class_name,
+                    method_data.name_and_type.name,
+                    method_type
+                ),
+            }
+        }
+    }
+}
+
+/// Returns the class literal of a primitive type, which javac reads from the `TYPE` field of
+/// its wrapper class.
+fn get_primitive_class_literal(field_data: &ConstFieldData) -> Option<AST> {
+    if field_data.name_and_type.name != "TYPE"
+        || field_data.name_and_type.descriptor != "Ljava/lang/Class;"
+    {
+        return None;
+    }
+    let class_type = match field_data.class.name.as_str() {
+        "java/lang/Void" => descriptors::FieldType::Void,
+        name => {
+            let (_, _, descriptor) = BOXED_TYPES.iter().find(|(class, _, _)| *class == name)?;
+            descriptors::parse_field(descriptor.chars()).ok()?
+        }
+    };
+    Some(AST::ClassLiteral { class_type })
+}
+
+/// The synthetic method javac used before class literals were compiled to `ldc`. It calls
+/// `Class.forName`, and its result is cached in synthetic static fields.
+const CLASS_HELPER: (&str, &str) = ("class$", "(Ljava/lang/String;)Ljava/lang/Class;");
+
+/// Refers to the static fields of the class being written by their simple name.
+fn static_field_to_java(field_data: &ConstFieldData, names: &NameResolver) -> String {
+    match names.is_current_class(&field_data.class.name) {
+        true => field_data.name_and_type.name.clone(),
+        false => format!(
+            "{}.{}",
+            names.get_class_name(&field_data.class.name),
+            field_data.name_and_type.name
+        ),
+    }
+}
+
+/// Checks if a field caches the result of `class$()`, like `class$java$lang$String`.
+fn is_class_cache(name: &str, descriptor: &str) -> bool {
+    (name.starts_with("class$") || name.starts_with("array$")) && descriptor == "Ljava/lang/Class;"
+}
+
+/// Turns the reads of a class literal cached by `class$()`, `C == null ? (C = class$(..)) : C`,
+/// back into the class literal, as the assignment of the cache is left out.
+fn inline_class_caches(value: &mut AST) {
+    for child in value.children_mut() {
+        inline_class_caches(child);
+    }
+    let literal = match value {
+        AST::Ternary {
+            then, otherwise, ..
+        } => match (then.as_ref(), otherwise.as_ref()) {
+            (literal @ AST::ClassLiteral { .. }, AST::Static { field_data })
+            | (AST::Static { field_data }, literal @ AST::ClassLiteral { .. })
+                if is_class_cache(
+                    &field_data.name_and_type.name,
+                    &field_data.name_and_type.descriptor,
+                ) =>
+            {
+                literal.clone()
+            }
+            _ => return,
+        },
+        _ => return,
+    };
+    *value = literal;
+}
+
+/// Returns the call of the bootstrap method computing a dynamic constant.
+fn decompile_dynamic_constant(
+    index: u16,
+    context: &CodeContext,
+) -> Result<Option<AST>, DecompilerError> {
+    let constant_pool = &context.class.constant_pool;
+    let constant = constant_pool.get_dynamic_entry(index)?;
+    let bootstrap_method = match get_bootstrap_methods(context.class)
+        .get(constant.bootstrap_method_attr_index as usize)
+    {
+        Some(bootstrap_method) => bootstrap_method,
+        None => return Ok(None),
+    };
+    let method_data = match constant_pool
+        .get_method_handle_entry(bootstrap_method.bootstrap_method_ref)?
+        .reference
+    {
+        MethodHandleReference::Method { method_data } => method_data,
+        MethodHandleReference::Field { .. } => return Ok(None),
+    };
+    let mut args = Vec::new();
+    for index in &bootstrap_method.bootstrap_arguments {
+        match get_constant(constant_pool.get_entry(*index)?, constant_pool)? {
+            Some(arg) => args.push(arg),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(AST::DynamicConstant {
+        bootstrap_method: method_data,
+        name_and_type: constant.name_and_type,
+        args,
+    }))
+}
+
+/// Turns int constants into char or boolean literals if they are used as such, and conditional
+/// expressions selecting between `1` and `0` into the condition itself, also as operands of the
+/// logical operators evaluating both of them.
+fn with_type(value: AST, field_type: &descriptors::FieldType) -> AST {
+    match (value, field_type) {
+        (AST::ConstInt { value }, descriptors::FieldType::Char) => AST::ConstChar {
+            value: value as u16,
+        },
+        (AST::ConstInt { value }, descriptors::FieldType::Boolean) => {
+            AST::ConstBoolean { value: value != 0 }
+        }
+        (
+            AST::Ternary {
+                condition,
+                then,
+                otherwise,
+            },
+            descriptors::FieldType::Boolean,
+        ) => {
+            let (then, otherwise) = (
+                with_type(*then, field_type),
+                with_type(*otherwise, field_type),
+            );
+            let binary = |operator, lhs, rhs| AST::Binary {
+                operator,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+            match (then, otherwise) {
+                (AST::ConstBoolean { value: true }, AST::ConstBoolean { value: false }) => {
+                    *condition
+                }
+                (AST::ConstBoolean { value: false }, AST::ConstBoolean { value: true }) => {
+                    condition.negate()
+                }
+                (AST::ConstBoolean { value: true }, otherwise) => {
+                    binary(BinaryOperator::Or, *condition, otherwise)
+                }
+                (AST::ConstBoolean { value: false }, otherwise) => {
+                    binary(BinaryOperator::And, condition.negate(), otherwise)
+                }
+                (then, AST::ConstBoolean { value: true }) => {
+                    binary(BinaryOperator::Or, condition.negate(), then)
+                }
+                (then, AST::ConstBoolean { value: false }) => {
+                    binary(BinaryOperator::And, *condition, then)
+                }
+                (then, otherwise) => AST::Ternary {
+                    condition,
+                    then: Box::new(then),
+                    otherwise: Box::new(otherwise),
+                },
+            }
+        }
+        (
+            AST::Ternary {
+                condition,
+                then,
+                otherwise,
+            },
+            field_type,
+        ) => AST::Ternary {
+            condition,
+            then: Box::new(with_type(*then, field_type)),
+            otherwise: Box::new(with_type(*otherwise, field_type)),
+        },
+        (
+            AST::Binary {
+                operator:
+                    operator @ (BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXOr),
+                lhs,
+                rhs,
+            },
+            descriptors::FieldType::Boolean,
+        ) => AST::Binary {
+            operator,
+            lhs: Box::new(with_type(*lhs, field_type)),
+            rhs: Box::new(with_type(*rhs, field_type)),
+        },
+        (AST::SwitchExpression { value, mut cases }, field_type) => {
+            for case in &mut cases {
+                apply_yield_type(&mut case.body, field_type);
+            }
+            AST::SwitchExpression { value, cases }
+        }
+        (value, _) => value,
+    }
+}
+
+/// Returns the type of an expression, as far as it is known without local variable types.
+fn get_type(
+    value: &AST,
+    local_types: &HashMap<u16, descriptors::FieldType>,
+) -> Option<descriptors::FieldType> {
+    match value {
+        AST::Variable { index, .. } => local_types.get(index).cloned(),
+        AST::Call { method_data, .. } | AST::StaticCall { method_data, .. } => {
+            descriptors::parse_method(method_data.name_and_type.descriptor.chars())
+                .ok()
+                .map(|(_, return_type)| return_type)
+        }
+        AST::Static { field_data } | AST::Field { field_data, .. } => {
+            descriptors::parse_field(field_data.name_and_type.descriptor.chars()).ok()
+        }
+        AST::Assign { target, .. } => get_type(target, local_types),
+        AST::BasicCast { cast_type, .. } => match cast_type {
+            VarType::Int => Some(descriptors::FieldType::Int),
+            VarType::Long => Some(descriptors::FieldType::Long),
+            VarType::Float => Some(descriptors::FieldType::Float),
+            VarType::Double => Some(descriptors::FieldType::Double),
+            VarType::Byte => Some(descriptors::FieldType::Byte),
+            VarType::Char => Some(descriptors::FieldType::Char),
+            VarType::Short => Some(descriptors::FieldType::Short),
+            VarType::Reference => None,
+        },
+        AST::Binary { operator, .. } if operator.precedence() <= Precedence::And => {
+            Some(descriptors::FieldType::Boolean)
+        }
+        AST::Binary { operator, .. } if operator.is_comparison() => {
+            Some(descriptors::FieldType::Boolean)
+        }
+        AST::Binary {
+            operator: BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXOr,
+            lhs,
+            rhs,
+        } => [lhs, rhs]
+            .iter()
+            .filter_map(|operand| get_type(operand, local_types))
+            .find(|operand| *operand == descriptors::FieldType::Boolean),
+        AST::Unary {
+            operator: UnaryOperator::Not,
+            ..
+        }
+        | AST::InstanceOf { .. }
+        | AST::ConstBoolean { .. } => Some(descriptors::FieldType::Boolean),
+        AST::ConstChar { .. } => Some(descriptors::FieldType::Char),
+        AST::ArrayElement { array, .. } => match get_type(array, local_types) {
+            Some(descriptors::FieldType::Array { inner }) => Some(*inner),
+            _ => None,
+        },
+        AST::ConstString { .. } | AST::Concat { .. } => Some(descriptors::FieldType::Reference {
+            name: String::from("java/lang/String"),
+        }),
+        AST::New { method_data, .. } => Some(descriptors::FieldType::Reference {
+            name: method_data.class.name.clone(),
+        }),
+        AST::NewArray { array_type, .. } => Some(array_type.clone()),
+        AST::Increment { target, .. } => get_type(target, local_types),
+        AST::ClassLiteral { .. } => Some(descriptors::FieldType::Reference {
+            name: String::from("java/lang/Class"),
+        }),
+        AST::MethodTypeConstant { .. } => Some(descriptors::FieldType::Reference {
+            name: String::from("java/lang/invoke/MethodType"),
+        }),
+        AST::MethodHandleConstant { .. } => Some(descriptors::FieldType::Reference {
+            name: String::from("java/lang/invoke/MethodHandle"),
+        }),
+        AST::DynamicConstant { name_and_type, .. } => {
+            descriptors::parse_field(name_and_type.descriptor.chars()).ok()
+        }
+        AST::Ternary {
+            then, otherwise, ..
+        } => get_type(then, local_types).or_else(|| get_type(otherwise, local_types)),
+        _ => None,
+    }
+}
+
+fn is_string_type(field_type: &descriptors::FieldType) -> bool {
+    matches!(field_type, descriptors::FieldType::Reference { name } if name == "java/lang/String")
+}
+
+/// Returns the value converted to a string by `String.valueOf` or cast to `String`, both of which
+/// are implicit in a concatenation. The contents of a `char[]` are not what concatenating it adds.
+fn get_string_conversion(value: &AST) -> Option<&AST> {
+    match value {
+        AST::StaticCall { method_data, args }
+            if method_data.class.name == "java/lang/String"
+                && method_data.name_and_type.name == "valueOf"
+                && method_data.name_and_type.descriptor != "([C)Ljava/lang/String;"
+                && args.len() == 1 =>
+        {
+            Some(&args[0])
+        }
+        AST::ClassCast { value, cast_type } if cast_type.name == "java/lang/String" => Some(value),
+        _ => None,
+    }
+}
+
+/// Builds a string concatenation, prepending an empty string if neither of the first two values
+/// is a string, as they would be added as numbers otherwise.
+fn make_concat(mut values: Vec<(AST, bool)>) -> AST {
+    // a conversion to a string is only needed if it makes one of the first two values a string,
+    // the calls to `String.valueOf` are removed before the casts, which are part of the source
+    let is_call = |value: &AST| matches!(value, AST::StaticCall { .. });
+    for remove_calls in [true, false].iter() {
+        for i in 0..values.len() {
+            let is_other_string = i >= 2 || (values.len() > 1 && values[1 - i].1);
+            let value = match get_string_conversion(&values[i].0) {
+                Some(value) if is_other_string && is_call(&values[i].0) == *remove_calls => {
+                    value.clone()
+                }
+                _ => continue,
+            };
+            values[i] = (value, false);
+        }
+    }
+    let needs_string = values.len() < 2 || !values.iter().take(2).any(|(_, is_string)| *is_string);
+    let mut concat_values = Vec::new();
+    if needs_string {
+        concat_values.push(AST::ConstString {
+            value: String::new(),
+        });
+    }
+    concat_values.extend(values.into_iter().map(|(value, _)| value));
+    if concat_values.len() == 1 {
+        concat_values.pop().unwrap()
+    } else {
+        AST::Concat {
+            values: concat_values,
+        }
+    }
+}
+
+/// Collects the values appended to a `StringBuilder` or `StringBuffer` created in the same
+/// expression, together with whether each of them is known to be a string.
+fn get_string_builder_values(
+    value: &AST,
+    local_types: &HashMap<u16, descriptors::FieldType>,
+) -> Option<Vec<(AST, bool)>> {
+    let is_builder =
+        |name: &str| name == "java/lang/StringBuilder" || name == "java/lang/StringBuffer";
+    match value {
+        AST::New { method_data, args } if is_builder(&method_data.class.name) => {
+            match (
+                method_data.name_and_type.descriptor.as_str(),
+                args.as_slice(),
+            ) {
+                ("()V", []) => Some(Vec::new()),
+                ("(Ljava/lang/String;)V", [value]) => Some(vec![(value.clone(), true)]),
+                ("(Ljava/lang/CharSequence;)V", [value]) => {
+                    let is_string = get_type(value, local_types)
+                        .is_some_and(|value_type| is_string_type(&value_type));
+                    Some(vec![(value.clone(), is_string)])
+                }
+                _ => None,
+            }
+        }
+        AST::Call {
+            method_data,
+            reference,
+            args,
+        } if is_builder(&method_data.class.name)
+            && method_data.name_and_type.name == "append"
+            && args.len() == 1 =>
+        {
+            let mut values = get_string_builder_values(reference, local_types)?;
+            let (params, _) =
+                descriptors::parse_method(method_data.name_and_type.descriptor.chars()).ok()?;
+            let is_string = is_string_type(&params[0])
+                || get_type(&args[0], local_types)
+                    .is_some_and(|value_type| is_string_type(&value_type));
+            values.push((args[0].clone(), is_string));
+            Some(values)
+        }
+        _ => None,
+    }
+}
+
+/// Applies the types known from descriptors to the values used with them, e.g. turning a
+/// comparison of a boolean with `0` into a negation.
+fn apply_types(
+    value: &mut AST,
+    return_type: &descriptors::FieldType,
+    local_types: &HashMap<u16, descriptors::FieldType>,
+) {
+    // lambda bodies have already been typed by their own method
+    if let AST::Lambda { .. } = value {
+        return;
+    }
+    for child in value.children_mut() {
+        apply_types(child, return_type, local_types);
+    }
+    let boolean = descriptors::FieldType::Boolean;
+    *value = match std::mem::replace(value, AST::VoidReturn) {
+        AST::Call {
+            method_data,
+            reference,
+            args,
+        } => {
+            let args = match descriptors::parse_method(method_data.name_and_type.descriptor.chars())
+            {
+                Ok((params, _)) if params.len() == args.len() => args
+                    .into_iter()
+                    .zip(&params)
+                    .map(|(arg, param)| with_type(arg, param))
+                    .collect(),
+                _ => args,
+            };
+            let call = AST::Call {
+                method_data,
+                reference,
+                args,
+            };
+            match &call {
+                AST::Call {
+                    method_data,
+                    reference,
+                    args,
+                } if method_data.name_and_type.name == "toString" && args.is_empty() => {
+                    match get_string_builder_values(reference, local_types) {
+                        Some(values) => make_concat(values),
+                        None => call,
+                    }
+                }
+                _ => call,
+            }
+        }
+        AST::New { method_data, args } => {
+            let args = match descriptors::parse_method(method_data.name_and_type.descriptor.chars())
+            {
+                Ok((params, _)) if params.len() == args.len() => args
+                    .into_iter()
+                    .zip(&params)
+                    .map(|(arg, param)| with_type(arg, param))
+                    .collect(),
+                _ => args,
+            };
+            AST::New { method_data, args }
+        }
+        AST::StaticCall { method_data, args } => {
+            let args = match descriptors::parse_method(method_data.name_and_type.descriptor.chars())
+            {
+                Ok((params, _)) if params.len() == args.len() => args
+                    .into_iter()
+                    .zip(&params)
+                    .map(|(arg, param)| with_type(arg, param))
+                    .collect(),
+                _ => args,
+            };
+            AST::StaticCall { method_data, args }
+        }
+        AST::SetField {
+            field_data,
+            reference,
+            value,
+        } => {
+            let value = match descriptors::parse_field(field_data.name_and_type.descriptor.chars())
+            {
+                Ok(field_type) => Box::new(with_type(*value, &field_type)),
+                Err(_) => value,
+            };
+            AST::SetField {
+                field_data,
+                reference,
+                value,
+            }
+        }
+        AST::SetStatic { field_data, value } => {
+            let value = match descriptors::parse_field(field_data.name_and_type.descriptor.chars())
+            {
+                Ok(field_type) => Box::new(with_type(*value, &field_type)),
+                Err(_) => value,
+            };
+            AST::SetStatic { field_data, value }
+        }
+        AST::Assign { target, value } => {
+            let value = match get_type(&target, local_types) {
+                Some(field_type) => Box::new(with_type(*value, &field_type)),
+                None => value,
+            };
+            AST::Assign { target, value }
+        }
+        AST::SetArrayElement {
+            array,
+            index,
+            value,
+        } => {
+            let value = match get_type(&array, local_types) {
+                Some(descriptors::FieldType::Array { inner }) => {
+                    Box::new(with_type(*value, &inner))
+                }
+                _ => value,
+            };
+            AST::SetArrayElement {
+                array,
+                index,
+                value,
+            }
+        }
+        AST::NewArray {
+            array_type,
+            dimensions,
+            elements,
+        } => {
+            let elements = match &array_type {
+                descriptors::FieldType::Array { inner } => elements
+                    .into_iter()
+                    .map(|element| with_type(element, inner))
+                    .collect(),
+                _ => elements,
+            };
+            AST::NewArray {
+                array_type,
+                dimensions,
+                elements,
+            }
+        }
+        AST::Set { index, value } => match local_types.get(&index) {
+            Some(local_type) => AST::Set {
+                index,
+                value: Box::new(with_type(*value, local_type)),
+            },
+            None => AST::Set { index, value },
+        },
+        AST::Return { value } => AST::Return {
+            value: Box::new(with_type(*value, return_type)),
+        },
+        AST::If {
+            condition,
+            then,
+            otherwise,
+        } => AST::If {
+            condition: Box::new(with_type(*condition, &boolean)),
+            then,
+            otherwise,
+        },
+        AST::Ternary {
+            condition,
+            then,
+            otherwise,
+        } => AST::Ternary {
+            condition: Box::new(with_type(*condition, &boolean)),
+            then,
+            otherwise,
+        },
+        AST::While { condition, body } => AST::While {
+            condition: Box::new(with_type(*condition, &boolean)),
+            body,
+        },
+        AST::DoWhile { body, condition } => AST::DoWhile {
+            body,
+            condition: Box::new(with_type(*condition, &boolean)),
+        },
+        AST::For {
+            condition,
+            update,
+            body,
+        } => AST::For {
+            condition: Box::new(with_type(*condition, &boolean)),
+            update,
+            body,
+        },
+        AST::Switch { value, mut cases } => {
+            apply_case_types(&value, &mut cases, local_types);
+            AST::Switch { value, cases }
+        }
+        AST::SwitchExpression { value, mut cases } => {
+            apply_case_types(&value, &mut cases, local_types);
+            AST::SwitchExpression { value, cases }
+        }
+        AST::Unary {
+            operator: UnaryOperator::Not,
+            value,
+        } => with_type(*value, &boolean).negate(),
+        AST::Binary {
+            operator: operator @ (BinaryOperator::And | BinaryOperator::Or),
+            lhs,
+            rhs,
+        } => AST::Binary {
+            operator,
+            lhs: Box::new(with_type(*lhs, &boolean)),
+            rhs: Box::new(with_type(*rhs, &boolean)),
+        },
+        // logical operators that evaluate both operands
+        AST::Binary {
+            operator:
+                operator @ (BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXOr),
+            lhs,
+            rhs,
+        } if [&lhs, &rhs]
+            .iter()
+            .any(|operand| get_type(operand, local_types).as_ref() == Some(&boolean)) =>
+        {
+            AST::Binary {
+                operator,
+                lhs: Box::new(with_type(*lhs, &boolean)),
+                rhs: Box::new(with_type(*rhs, &boolean)),
+            }
+        }
+        AST::Binary { operator, lhs, rhs } if operator.is_comparison() => {
+            let (lhs_type, rhs_type) = (get_type(&lhs, local_types), get_type(&rhs, local_types));
+            match (operator, *rhs) {
+                // javac compiles conditions on booleans to comparisons with 0
+                (BinaryOperator::Equal, AST::ConstInt { value: 0 })
+                    if lhs_type.as_ref() == Some(&boolean) =>
+                {
+                    lhs.negate()
+                }
+                (BinaryOperator::NotEqual, AST::ConstInt { value: 0 })
+                    if lhs_type.as_ref() == Some(&boolean) =>
+                {
+                    *lhs
+                }
+                (operator, rhs) => {
+                    let lhs = match rhs_type {
+                        Some(rhs_type) => with_type(*lhs, &rhs_type),
+                        None => *lhs,
+                    };
+                    let rhs = match lhs_type {
+                        Some(lhs_type) => with_type(rhs, &lhs_type),
+                        None => rhs,
+                    };
+                    AST::Binary {
+                        operator,
+                        lhs: Box::new(lhs),
+                        rhs: Box::new(rhs),
+                    }
+                }
+            }
+        }
+        value => value,
+    };
+}
+
+fn apply_case_types(
+    value: &AST,
+    cases: &mut [Case],
+    local_types: &HashMap<u16, descriptors::FieldType>,
+) {
+    let boolean = descriptors::FieldType::Boolean;
+    for case in cases.iter_mut() {
+        case.guard = case.guard.take().map(|guard| with_type(guard, &boolean));
+    }
+    if let Some(value_type) = get_type(value, local_types) {
+        for case in cases {
+            case.labels = std::mem::take(&mut case.labels)
+                .into_iter()
+                .map(|label| with_type(label, &value_type))
+                .collect();
+        }
+    }
+}
+
+/// Applies the type of a `switch` expression to the values yielded by its cases.
+fn apply_yield_type(statements: &mut [AST], field_type: &descriptors::FieldType) {
+    for statement in statements {
+        match statement {
+            AST::Yield { value } => {
+                **value = with_type(
+                    std::mem::replace(value.as_mut(), AST::ConstNull),
+                    field_type,
+                );
+            }
+            // `switch` expressions nested in other statements yield their own values
+            statement if statement.is_block_statement() => {
+                for statements in statement.statement_lists_mut() {
+                    apply_yield_type(statements, field_type);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn reads_variable(value: &mut AST, index: u16) -> bool {
+    match value {
+        AST::Variable { index: read, .. } if *read == index => true,
+        // lambda bodies use their own locals
+        AST::Lambda { captured, .. } => captured
+            .iter_mut()
+            .any(|value| reads_variable(value, index)),
+        _ => value
+            .children_mut()
+            .into_iter()
+            .any(|child| reads_variable(child, index)),
+    }
+}
+
+/// Checks if a local is declared or used anywhere in a value, including the bodies of lambdas.
+fn uses_local(value: &mut AST, index: u16) -> bool {
+    let is_declared = match value {
+        AST::Variable { index: used, .. }
+        | AST::Set { index: used, .. }
+        | AST::ForEach { index: used, .. } => *used == index,
+        AST::Lambda { params, .. } => params.contains(&index),
+        AST::Try { catches, .. } => catches.iter().any(|catch| catch.index == index),
+        AST::InstanceOf { pattern, .. } => pattern.binds(index),
+        AST::Switch { cases, .. } | AST::SwitchExpression { cases, .. } => {
+            cases.iter().any(|case| {
+                case.pattern
+                    .as_ref()
+                    .is_some_and(|pattern| pattern.binds(index))
+            })
+        }
+        _ => false,
+    };
+    is_declared
+        || value
+            .children_mut()
+            .into_iter()
+            .any(|child| uses_local(child, index))
+}
+
+/// Checks if a local is read by the statements before it is assigned a new value.
+fn is_read_before_write(statements: &mut [AST], index: u16) -> bool {
+    for statement in statements {
+        if let AST::Set { index: set, value } = statement {
+            if *set == index {
+                return reads_variable(value, index);
+            }
+        }
+        if reads_variable(statement, index) {
+            return true;
+        }
+    }
+    false
+}
+
+fn is_variable(value: &AST, index: u16) -> bool {
+    matches!(value, AST::Variable { index: read, .. } if *read == index)
+}
+
+/// The parts of a loop javac generated for an enhanced for loop.
+struct ForEachLoop {
+    init_length: usize,
+    synthetic: Vec<u16>,
+    index: u16,
+    var_type: Option<descriptors::FieldType>,
+    iterable: AST,
+    body: Vec<AST>,
+}
+
+/// Matches `T[] a = iterable; int n = a.length; for (int i = 0; i < n; i++) { T x = a[i]; ... }`.
+fn match_array_for_each(
+    init: &[AST],
+    condition: &AST,
+    update: &[AST],
+    body: &[AST],
+    local_types: &HashMap<u16, descriptors::FieldType>,
+) -> Option<ForEachLoop> {
+    let (array, iterable, length, index) = match init {
+        [AST::Set {
+            index: array,
+            value: iterable,
+        }, AST::Set {
+            index: length,
+            value: length_value,
+        }, AST::Set {
+            index,
+            value: index_value,
+        }] => match (length_value.as_ref(), index_value.as_ref()) {
+            (AST::ArrayLength { reference }, AST::ConstInt { value: 0 })
+                if is_variable(reference, *array) =>
+            {
+                (*array, iterable, *length, *index)
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    match condition {
+        AST::Binary {
+            operator: BinaryOperator::Less,
+            lhs,
+            rhs,
+        } if is_variable(lhs, index) && is_variable(rhs, length) => {}
+        _ => return None,
+    }
+    // the increment is the update of a `for` loop if the body contains a `continue`
+    let (increment, body) = match update {
+        [] => body.split_last()?,
+        [increment] => (increment, body),
+        _ => return None,
+    };
+    match increment {
+        AST::Set { index: set, value } if *set == index => match value.as_ref() {
+            AST::Binary {
+                operator: BinaryOperator::Add,
+                lhs,
+                rhs,
+            } if is_variable(lhs, index) && matches!(rhs.as_ref(), AST::ConstInt { value: 1 }) => {}
+            _ => return None,
+        },
+        _ => return None,
+    }
+    match body.split_first()? {
+        (
+            AST::Set {
+                index: variable,
+                value,
+            },
+            body,
+        ) => match value.as_ref() {
+            AST::ArrayElement {
+                array: element_array,
+                index: element_index,
+            } if is_variable(element_array, array) && is_variable(element_index, index) => {
+                Some(ForEachLoop {
+                    init_length: 3,
+                    synthetic: vec![array, length, index],
+                    index: *variable,
+                    var_type: match get_type(iterable, local_types) {
+                        Some(descriptors::FieldType::Array { inner }) => Some(*inner),
+                        _ => None,
+                    },
+                    iterable: iterable.as_ref().clone(),
+                    body: body.to_vec(),
+                })
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Matches `Iterator i = iterable.iterator(); while (i.hasNext()) { T x = (T) i.next(); ... }`.
+fn match_iterator_for_each(init: &[AST], condition: &AST, body: &[AST]) -> Option<ForEachLoop> {
+    let (iterator, iterable) = match init {
+        [AST::Set { index, value }] => match value.as_ref() {
+            AST::Call {
+                method_data,
+                reference,
+                args,
+            } if method_data.name_and_type.name == "iterator"
+                && method_data.name_and_type.descriptor == "()Ljava/util/Iterator;"
+                && args.is_empty() =>
+            {
+                (*index, reference)
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let is_iterator_call = |value: &AST, name: &str| match value {
+        AST::Call {
+            method_data,
+            reference,
+            args,
+        } => {
+            method_data.name_and_type.name == name
+                && args.is_empty()
+                && is_variable(reference, iterator)
+        }
+        _ => false,
+    };
+    // the types are not applied yet, so the boolean is still compared with `0`
+    let condition = match condition {
+        AST::Binary {
+            operator: BinaryOperator::NotEqual,
+            lhs,
+            rhs,
+        } if matches!(rhs.as_ref(), AST::ConstInt { value: 0 }) => lhs,
+        condition => condition,
+    };
+    if !is_iterator_call(condition, "hasNext") {
+        return None;
+    }
+    let (variable, value, body) = match body.split_first()? {
+        (AST::Set { index, value }, body) => (*index, value.as_ref(), body),
+        _ => return None,
+    };
+    // the cast is left out for elements of type `Object`
+    let var_type = match value {
+        // unboxing into a variable of a primitive type
+        AST::Call {
+            method_data,
+            reference,
+            args,
+        } if args.is_empty()
+            && matches!(reference.as_ref(), AST::ClassCast { value, cast_type }
+                if is_iterator_call(value, "next") && cast_type.name == method_data.class.name) =>
+        {
+            match (
+                method_data.class.name.as_str(),
+                method_data.name_and_type.name.as_str(),
+            ) {
+                ("java/lang/Integer", "intValue") => descriptors::FieldType::Int,
+                ("java/lang/Long", "longValue") => descriptors::FieldType::Long,
+                ("java/lang/Float", "floatValue") => descriptors::FieldType::Float,
+                ("java/lang/Double", "doubleValue") => descriptors::FieldType::Double,
+                ("java/lang/Boolean", "booleanValue") => descriptors::FieldType::Boolean,
+                ("java/lang/Character", "charValue") => descriptors::FieldType::Char,
+                ("java/lang/Byte", "byteValue") => descriptors::FieldType::Byte,
+                ("java/lang/Short", "shortValue") => descriptors::FieldType::Short,
+                _ => return None,
+            }
+        }
+        AST::ClassCast { value, cast_type } if is_iterator_call(value, "next") => {
+            match descriptors::parse_field(cast_type.name.chars()) {
+                Ok(array_type @ descriptors::FieldType::Array { .. }) => array_type,
+                _ => descriptors::FieldType::Reference {
+                    name: cast_type.name.clone(),
+                },
+            }
+        }
+        value if is_iterator_call(value, "next") => descriptors::FieldType::Reference {
+            name: String::from("java/lang/Object"),
+        },
+        _ => return None,
+    };
+    Some(ForEachLoop {
+        init_length: 1,
+        synthetic: vec![iterator],
+        index: variable,
+        var_type: Some(var_type),
+        iterable: iterable.as_ref().clone(),
+        body: body.to_vec(),
+    })
+}
+
+/// Turns the loops javac generates for enhanced for loops over arrays and `Iterable`s back into
+/// them, unless the synthetic locals involved are used anywhere else.
+fn sugar_for_each(statements: &mut Vec<AST>, local_types: &HashMap<u16, descriptors::FieldType>) {
+    let mut i = 0;
+    while i < statements.len() {
+        let (condition, update, body) = match &statements[i] {
+            AST::While { condition, body } => (condition, &[][..], body),
+            AST::For {
+                condition,
+                update,
+                body,
+            } => (condition, update.as_slice(), body),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let array_loop = statements[..i]
+            .get(i.saturating_sub(3)..)
+            .and_then(|init| match_array_for_each(init, condition, update, body, local_types));
+        let iterator_loop = match update {
+            [] => statements[..i]
+                .get(i.saturating_sub(1)..)
+                .and_then(|init| match_iterator_for_each(init, condition, body)),
+            _ => None,
+        };
+        if let Some(mut for_each) = array_loop.or(iterator_loop) {
+            let after = &mut statements[i + 1..];
+            let body = &mut for_each.body;
+            let is_synthetic = for_each.synthetic.iter().all(|index| {
+                !body
+                    .iter_mut()
+                    .any(|statement| reads_variable(statement, *index))
+                    && !is_read_before_write(after, *index)
+            });
+            if is_synthetic {
+                let start = i - for_each.init_length;
+                statements.splice(
+                    start..=i,
+                    [AST::ForEach {
+                        index: for_each.index,
+                        var_type: for_each.var_type,
+                        iterable: Box::new(for_each.iterable),
+                        body: for_each.body,
+                    }],
+                );
+                i = start;
+            }
+        }
+        i += 1;
+    }
+    for statement in statements.iter_mut() {
+        match statement {
+            // the type of the variable is known for loops nested in its body
+            AST::ForEach {
+                index,
+                var_type: Some(var_type),
+                body,
+                ..
+            } => {
+                let mut local_types = local_types.clone();
+                local_types.insert(*index, var_type.clone());
+                sugar_for_each(body, &local_types);
+            }
+            statement => {
+                for statements in statement.statement_lists_mut() {
+                    sugar_for_each(statements, local_types);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the class of the `close()` method if the statement closes the resource in a local.
+fn get_close_class(statement: &AST, resource: u16) -> Option<&ConstClassData> {
+    match statement {
+        AST::Call {
+            method_data,
+            reference,
+            args,
+        } if method_data.name_and_type.name == "close"
+            && method_data.name_and_type.descriptor == "()V"
+            && args.is_empty()
+            && is_variable(reference, resource) =>
+        {
+            Some(&method_data.class)
+        }
+        _ => None,
+    }
+}
+
+/// Strips the null check javac generates around the closing of a resource that might be `null`.
+fn strip_null_check(statement: &AST, resource: u16) -> &AST {
+    match statement {
+        AST::If {
+            condition,
+            then,
+            otherwise,
+        } if otherwise.is_empty() => match (condition.as_ref(), then.as_slice()) {
+            (
+                AST::Binary {
+                    operator: BinaryOperator::NotEqual,
+                    lhs,
+                    rhs,
+                },
+                [statement],
+            ) if is_variable(lhs, resource) && matches!(rhs.as_ref(), AST::ConstNull) => statement,
+            _ => statement,
+        },
+        _ => statement,
+    }
+}
+
+/// Checks if a statement closes a resource, adding an exception thrown by `close()` to the
+/// suppressed exceptions of the exception in `exception`.
+fn is_suppressing_close(statement: &AST, resource: u16, exception: u16) -> bool {
+    match strip_null_check(statement, resource) {
+        AST::Try {
+            resources,
+            body,
+            catches,
+            finally,
+        } if resources.is_empty() && finally.is_empty() => {
+            match (body.as_slice(), catches.as_slice()) {
+                ([close], [catch]) if get_close_class(close, resource).is_some() => {
+                    match catch.body.as_slice() {
+                        [AST::Call {
+                            method_data,
+                            reference,
+                            args,
+                        }] => {
+                            method_data.name_and_type.name == "addSuppressed"
+                                && is_variable(reference, exception)
+                                && matches!(args.as_slice(), [arg] if is_variable(arg, catch.index))
+                        }
+                        _ => false,
+                    }
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Returns a value javac stores in a local to return it after cleaning up, like closing a
+/// resource, directly if the statement at `i` returns the local assigned by the statement before
+/// it or at the end of the body of the try-with-resources statement before it.
+fn inline_returned_value(statements: &mut Vec<AST>, i: usize) {
+    let local = match statements.get(i) {
+        Some(AST::Return { value }) => match value.as_ref() {
+            AST::Variable { index, .. } => *index,
+            _ => return,
+        },
+        _ => return,
+    };
+    let mut previous = match i.checked_sub(1) {
+        Some(previous) => &mut statements[previous],
+        None => return,
+    };
+    while let AST::Try {
+        resources,
+        body,
+        catches,
+        finally,
+    } = previous
+    {
+        match body.last_mut() {
+            Some(last) if !resources.is_empty() && catches.is_empty() && finally.is_empty() => {
+                previous = last;
+            }
+            _ => return,
+        }
+    }
+    match previous {
+        AST::Set { index, value } if *index == local => {
+            *previous = AST::Return {
+                value: value.clone(),
+            };
+            statements.remove(i);
+        }
+        _ => {}
+    }
+}
+
+/// Removes the closing of a resource javac inlines before the jumps leaving the body of a
+/// try-with-resources statement.
+fn strip_inlined_close(statements: &mut Vec<AST>, resource: u16) {
+    let mut i = 0;
+    while i < statements.len() {
+        let is_close = get_close_class(strip_null_check(&statements[i], resource), resource)
+            .is_some()
+            && matches!(
+                statements.get(i + 1),
+                Some(AST::Return { .. } | AST::VoidReturn | AST::Break | AST::Continue)
+            );
+        if is_close {
+            statements.remove(i);
+            inline_returned_value(statements, i);
+            continue;
+        }
+        for statements in statements[i].statement_lists_mut() {
+            strip_inlined_close(statements, resource);
+        }
+        i += 1;
+    }
+}
+
+/// Turns the code javac 11 and later generates for try-with-resources statements back into
+/// them, merging nested statements into one with several resources.
+fn sugar_try_with_resources(statements: &mut Vec<AST>) {
+    for statement in statements.iter_mut() {
+        for statements in statement.statement_lists_mut() {
+            sugar_try_with_resources(statements);
+        }
+    }
+    let mut i = 1;
+    while i < statements.len() {
+        let resource = match (&statements[i - 1], &statements[i]) {
+            (
+                AST::Set { index, value },
+                AST::Try {
+                    resources, catches, ..
+                },
+            ) if resources.is_empty() => match catches.as_slice() {
+                [Catch {
+                    classes,
+                    index: exception,
+                    body,
+                }] if classes.len() == 1 && classes[0].name == "java/lang/Throwable" => {
+                    match body.as_slice() {
+                        [close, AST::Throw { value: thrown }]
+                            if is_variable(thrown, *exception)
+                                && is_suppressing_close(close, *index, *exception) =>
+                        {
+                            Some((*index, value.as_ref().clone(), close.clone()))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let (index, value, close) = match resource {
+            Some(resource) => resource,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        let class_data = match strip_null_check(&close, index) {
+            AST::Try { body, .. } => get_close_class(&body[0], index).unwrap().clone(),
+            _ => unreachable!(),
+        };
+        let mut body = match statements.remove(i) {
+            AST::Try { body, .. } => body,
+            _ => unreachable!(),
+        };
+        // the closing on the normal path is missing if the body never completes normally
+        if statements
+            .get(i)
+            .and_then(|statement| get_close_class(strip_null_check(statement, index), index))
+            .is_some()
+        {
+            statements.remove(i);
+        }
+        strip_inlined_close(&mut body, index);
+        let mut resources = vec![Resource {
+            index,
+            class_data,
+            value,
+        }];
+        // nested statements come from a single statement declaring several resources
+        if let Some((inner_resources, inner_body)) = get_nested_resources(&mut body) {
+            resources.append(inner_resources);
+            body = std::mem::take(inner_body);
+        }
+        statements[i - 1] = AST::Try {
+            resources,
+            body,
+            catches: Vec::new(),
+            finally: Vec::new(),
+        };
+        inline_returned_value(statements, i);
+    }
+    // the catch clauses of a try-with-resources statement also cover the resources
+    for statement in statements.iter_mut() {
+        merge_nested_resources(statement);
+    }
+}
+
+/// Returns the resources and the body of a try-with-resources statement if it is the only
+/// statement in a block and has neither catch clauses nor a `finally` block.
+fn get_nested_resources(body: &mut [AST]) -> Option<(&mut Vec<Resource>, &mut Vec<AST>)> {
+    match body {
+        [AST::Try {
+            resources,
+            body,
+            catches,
+            finally,
+        }] if !resources.is_empty() && catches.is_empty() && finally.is_empty() => {
+            Some((resources, body))
+        }
+        _ => None,
+    }
+}
+
+/// Moves the resources of a try-with-resources statement into a `try` statement without
+/// resources consisting only of it.
+fn merge_nested_resources(statement: &mut AST) {
+    if let AST::Try {
+        resources, body, ..
+    } = statement
+    {
+        if resources.is_empty() {
+            if let Some((inner_resources, inner_body)) = get_nested_resources(body) {
+                *resources = std::mem::take(inner_resources);
+                *body = std::mem::take(inner_body);
+            }
+        }
+    }
+}
+
+/// Checks if control can reach the end of a block of statements.
+fn completes_normally(statements: &[AST]) -> bool {
+    match statements.last() {
+        Some(AST::Return { .. } | AST::VoidReturn | AST::Throw { .. }) => false,
+        Some(AST::Break | AST::Continue) => false,
+        Some(AST::If {
+            then, otherwise, ..
+        }) if !otherwise.is_empty() => completes_normally(then) || completes_normally(otherwise),
+        Some(AST::Try { body, catches, .. }) => {
+            completes_normally(body) || catches.iter().any(|catch| completes_normally(&catch.body))
+        }
+        _ => true,
+    }
+}
+
+fn is_same_block(a: &[AST], b: &[AST]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| is_same_value(a, b))
+}
+
+/// Removes the statements of a `finally` block if they end a block of statements.
+fn strip_finally_end(statements: &mut Vec<AST>, finally: &[AST]) -> bool {
+    match statements.len().checked_sub(finally.len()) {
+        Some(start) if is_same_block(&statements[start..], finally) => {
+            statements.truncate(start);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Removes the copies of a `finally` block javac inlines before the jumps leaving the `try`
+/// statement. `breaks` and `continues` tell if a `break` or a `continue` leaves it. Returns
+/// false if a jump leaving it is not preceded by a copy.
+fn strip_inlined_finally(
+    statements: &mut Vec<AST>,
+    finally: &[AST],
+    breaks: bool,
+    continues: bool,
+) -> bool {
+    let mut i = 0;
+    while i < statements.len() {
+        let is_jump = match &statements[i] {
+            AST::Return { .. } | AST::VoidReturn => true,
+            AST::Break => breaks,
+            AST::Continue => continues,
+            _ => false,
+        };
+        if is_jump {
+            let mut block = statements[..i].to_vec();
+            if !strip_finally_end(&mut block, finally) {
+                return false;
+            }
+            let start = block.len();
+            statements.drain(start..i);
+            inline_returned_value(statements, start);
+            i = start + 1;
+            continue;
+        }
+        let (breaks, continues) = match &statements[i] {
+            AST::While { .. } | AST::DoWhile { .. } | AST::For { .. } | AST::ForEach { .. } => {
+                (false, false)
+            }
+            AST::Switch { .. } => (false, continues),
+            _ => (breaks, continues),
+        };
+        for statements in statements[i].statement_lists_mut() {
+            if !strip_inlined_finally(statements, finally, breaks, continues) {
+                return false;
+            }
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Removes the copy of a `finally` block javac inlines at the end of the code completing
+/// normally, which for catch clauses is at the end of each of them.
+fn strip_normal_finally(statements: &mut Vec<AST>, finally: &[AST]) -> bool {
+    if !completes_normally(statements) || strip_finally_end(statements, finally) {
+        return true;
+    }
+    match statements.last_mut() {
+        Some(AST::Try {
+            body,
+            catches,
+            finally: inner_finally,
+            ..
+        }) if !catches.is_empty() && inner_finally.is_empty() => {
+            strip_normal_finally(body, finally)
+                && catches
+                    .iter_mut()
+                    .all(|catch| strip_normal_finally(&mut catch.body, finally))
+        }
+        _ => false,
+    }
+}
+
+/// Builds the `try` statement with a `finally` block from the one at `i` catching the
+/// exceptions to run the block. Also returns the number of statements following it that are
+/// the copy of the block for the protected code completing normally.
+fn get_finally_statement(statements: &[AST], i: usize, finally: &[AST]) -> Option<(AST, usize)> {
+    let (resources, mut body, mut catches) = match statements[i].clone() {
+        AST::Try {
+            resources,
+            body,
+            mut catches,
+            ..
+        } => {
+            catches.pop();
+            (resources, body, catches)
+        }
+        _ => return None,
+    };
+    let is_stripped = strip_inlined_finally(&mut body, finally, true, true)
+        && catches
+            .iter_mut()
+            .all(|catch| strip_inlined_finally(&mut catch.body, finally, true, true))
+        && catches
+            .iter_mut()
+            .all(|catch| strip_normal_finally(&mut catch.body, finally));
+    if !is_stripped {
+        return None;
+    }
+    let mut following = 0;
+    let mut stripped = body.clone();
+    if strip_normal_finally(&mut stripped, finally) {
+        body = stripped;
+    } else if catches.iter().all(|catch| !completes_normally(&catch.body))
+        && statements
+            .get(i + 1..=i + finally.len())
+            .is_some_and(|statements| is_same_block(statements, finally))
+    {
+        following = finally.len();
+    } else {
+        return None;
+    }
+    // the handler also covers the catch clauses of the statement
+    if let (
+        [AST::Try {
+            resources,
+            body,
+            catches,
+            finally: inner_finally,
+        }],
+        true,
+    ) = (
+        body.as_mut_slice(),
+        resources.is_empty() && catches.is_empty(),
+    ) {
+        if !catches.is_empty() && inner_finally.is_empty() {
+            let statement = AST::Try {
+                resources: std::mem::take(resources),
+                body: std::mem::take(body),
+                catches: std::mem::take(catches),
+                finally: finally.to_vec(),
+            };
+            return Some((statement, following));
+        }
+    }
+    let statement = AST::Try {
+        resources,
+        body,
+        catches,
+        finally: finally.to_vec(),
+    };
+    Some((statement, following))
+}
+
+/// Turns the handler javac generates to run a `finally` block when an exception is thrown, and
+/// the copies of the block it inlines on all other paths leaving the `try` statement, back
+/// into the block.
+fn sugar_finally(statements: &mut Vec<AST>) {
+    let mut i = 0;
+    while i < statements.len() {
+        let handler = match &statements[i] {
+            AST::Try {
+                catches, finally, ..
+            } if finally.is_empty() => match catches.last() {
+                Some(Catch {
+                    classes,
+                    index,
+                    body,
+                }) if classes.len() == 1 && classes[0].name == "java/lang/Throwable" => {
+                    match body.split_last() {
+                        Some((AST::Throw { value }, finally))
+                            if is_variable(value, *index) && !finally.is_empty() =>
+                        {
+                            Some((*index, finally.to_vec()))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some((exception, mut finally)) = handler {
+            // the handlers of try-with-resources statements use the exception they rethrow
+            let is_finally = !finally
+                .iter_mut()
+                .any(|statement| reads_variable(statement, exception));
+            if is_finally {
+                if let Some((statement, following)) = get_finally_statement(statements, i, &finally)
+                {
+                    statements.drain(i + 1..=i + following);
+                    statements[i] = statement;
+                }
+            }
+        }
+        for statements in statements[i].statement_lists_mut() {
+            sugar_finally(statements);
+        }
+        i += 1;
+    }
+}
+
+fn is_monitor_exit(statement: &AST, lock: u16) -> bool {
+    matches!(statement, AST::MonitorExit { value } if is_variable(value, lock))
+}
+
+/// Removes the releases of a monitor javac inlines before the jumps leaving the body of a
+/// `synchronized` statement.
+fn strip_monitor_exit(statements: &mut Vec<AST>, lock: u16) {
+    statements.retain(|statement| !is_monitor_exit(statement, lock));
+    for statement in statements {
+        for statements in statement.statement_lists_mut() {
+            strip_monitor_exit(statements, lock);
+        }
+    }
+}
+
+/// Turns the monitor javac acquires and releases in a handler protecting the body of a
+/// `synchronized` statement back into it.
+fn sugar_synchronized(statements: &mut Vec<AST>) {
+    for statement in statements.iter_mut() {
+        for statements in statement.statement_lists_mut() {
+            sugar_synchronized(statements);
+        }
+    }
+    let mut i = 2;
+    while i < statements.len() {
+        let lock = match &statements[i - 2..=i] {
+            [AST::Set { index, value }, AST::MonitorEnter { .. }, AST::Try {
+                resources, catches, ..
+            }] if resources.is_empty() => match catches.as_slice() {
+                [Catch {
+                    index: exception,
+                    body,
+                    ..
+                }] => match body.as_slice() {
+                    [exit, AST::Throw { value: thrown }]
+                        if is_monitor_exit(exit, *index) && is_variable(thrown, *exception) =>
+                    {
+                        Some((*index, value.clone()))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some((index, lock)) = lock {
+            let mut body = match statements.remove(i) {
+                AST::Try { body, .. } => body,
+                _ => unreachable!(),
+            };
+            strip_monitor_exit(&mut body, index);
+            statements.remove(i - 1);
+            statements[i - 2] = AST::Synchronized { lock, body };
+        }
+        i += 1;
+    }
+}
+
+/// The synthetic field javac adds to classes with `assert` statements.
+const ASSERTIONS_DISABLED: &str = "$assertionsDisabled";
+
+fn is_assertions_disabled(value: &AST) -> bool {
+    match value {
+        AST::Static { field_data } | AST::SetStatic { field_data, .. } => {
+            field_data.name_and_type.name == ASSERTIONS_DISABLED
+        }
+        _ => false,
+    }
+}
+
+fn is_assertions_check(value: &AST) -> bool {
+    match value {
+        AST::Unary {
+            operator: UnaryOperator::Not,
+            value,
+        } => is_assertions_disabled(value),
+        AST::Binary {
+            operator: BinaryOperator::Equal,
+            lhs,
+            rhs,
+        } => {
+            is_assertions_disabled(lhs)
+                && matches!(
+                    rhs.as_ref(),
+                    AST::ConstInt { value: 0 } | AST::ConstBoolean { value: false }
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Splits a chain of `&&` into its operands.
+fn flatten_and(value: AST, operands: &mut Vec<AST>) {
+    match value {
+        AST::Binary {
+            operator: BinaryOperator::And,
+            lhs,
+            rhs,
+        } => {
+            flatten_and(*lhs, operands);
+            flatten_and(*rhs, operands);
+        }
+        value => operands.push(value),
+    }
+}
+
+fn join_and(operands: Vec<AST>) -> Option<AST> {
+    operands.into_iter().reduce(|lhs, rhs| AST::Binary {
+        operator: BinaryOperator::And,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    })
+}
+
+/// Recovers `assert` statements, which javac compiles to throwing an `AssertionError` unless
+/// `$assertionsDisabled` is set or the condition holds.
+fn sugar_assert(statements: &mut [AST]) {
+    for statement in statements.iter_mut() {
+        for statements in statement.statement_lists_mut() {
+            sugar_assert(statements);
+        }
+        let (condition, args) = match statement {
+            AST::If {
+                condition,
+                then,
+                otherwise,
+            } if otherwise.is_empty() => match then.as_slice() {
+                [AST::Throw { value }] => match value.as_ref() {
+                    AST::New { method_data, args }
+                        if method_data.class.name == "java/lang/AssertionError"
+                            && args.len() <= 1 =>
+                    {
+                        (condition, args)
+                    }
+                    _ => continue,
+                },
+                _ => continue,
+            },
+            _ => continue,
+        };
+        // the condition of an enclosing `if` may be merged into the one of the assertion
+        let mut operands = Vec::new();
+        flatten_and(condition.as_ref().clone(), &mut operands);
+        let check = match operands.iter().position(is_assertions_check) {
+            Some(check) => check,
+            None => continue,
+        };
+        let failed = operands.split_off(check + 1);
+        operands.pop();
+        let assertion = AST::Assert {
+            condition: Box::new(match join_and(failed) {
+                Some(failed) => failed.negate(),
+                None => AST::ConstBoolean { value: false },
+            }),
+            message: args.first().cloned().map(Box::new),
+        };
+        *statement = match join_and(operands) {
+            Some(condition) => AST::If {
+                condition: Box::new(condition),
+                then: vec![assertion],
+                otherwise: Vec::new(),
+            },
+            None => assertion,
+        };
+    }
+}
+
+/// Removes the initialization of `$assertionsDisabled` from `<clinit>`.
+fn strip_assertions_disabled_initializer(
+    class: &ClassFile,
+    bodies: &mut [Option<Result<Vec<AST>, DecompilerError>>],
+) -> Result<(), DecompilerError> {
+    for (method, body) in class.methods.iter().zip(bodies.iter_mut()) {
+        if class.constant_pool.get_utf8_entry(method.name_index)? == "<clinit>" {
+            if let Some(Ok(statements)) = body {
+                statements.retain(|statement| !is_assertions_disabled(statement));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Computes `String.hashCode()` of a string.
+fn get_string_hash(value: &str) -> i32 {
+    value.encode_utf16().fold(0i32, |hash, ch| {
+        hash.wrapping_mul(31).wrapping_add(ch as i32)
+    })
+}
+
+/// Collects the strings a `case` of the `hashCode()` switch of a string switch compares the
+/// string in `temp` with, together with the index each of them assigns to `index`. Strings
+/// with the same hash are compared by an `if`/`else if` chain.
+fn get_string_case_indices(
+    statements: &[AST],
+    temp: u16,
+    index: u16,
+    hash: i32,
+    indices: &mut HashMap<i32, String>,
+) -> bool {
+    let statements = match statements.split_last() {
+        Some((AST::Break, rest)) => rest,
+        _ => statements,
+    };
+    match statements {
+        [] => true,
+        [AST::If {
+            condition,
+            then,
+            otherwise,
+        }] => {
+            // the types are not applied yet, so the boolean is still compared with `0`
+            let condition = match condition.as_ref() {
+                AST::Binary {
+                    operator: BinaryOperator::NotEqual,
+                    lhs,
+                    rhs,
+                } if matches!(rhs.as_ref(), AST::ConstInt { value: 0 }) => lhs,
+                condition => condition,
+            };
+            let value = match condition {
+                AST::Call {
+                    method_data,
+                    reference,
+                    args,
+                } if method_data.name_and_type.name == "equals" && is_variable(reference, temp) => {
+                    match args.as_slice() {
+                        [AST::ConstString { value }] if get_string_hash(value) == hash => value,
+                        _ => return false,
+                    }
+                }
+                _ => return false,
+            };
+            match then.as_slice() {
+                [AST::Set {
+                    index: assigned,
+                    value: case_index,
+                }] if *assigned == index => match case_index.as_ref() {
+                    AST::ConstInt { value: case_index } => {
+                        indices.insert(*case_index, value.clone());
+                    }
+                    _ => return false,
+                },
+                _ => return false,
+            }
+            get_string_case_indices(otherwise, temp, index, hash, indices)
+        }
+        _ => false,
+    }
+}
+
+/// Turns the two `switch` statements javac generates for a `switch` on a string back into one.
+/// The first one switches on the `hashCode()` of the string and assigns the index of the
+/// matching `case` to a temporary, which the second one, possibly a `switch` expression,
+/// switches on.
+fn sugar_string_switch(statements: &mut Vec<AST>) {
+    for statement in statements.iter_mut() {
+        for statements in statement.statement_lists_mut() {
+            sugar_string_switch(statements);
+        }
+    }
+    let mut i = 0;
+    while i + 3 < statements.len() {
+        let matched = match &mut statements[i..=i + 3] {
+            [AST::Set {
+                index: temp,
+                value: string,
+            }, AST::Set {
+                index,
+                value: initial,
+            }, AST::Switch {
+                value: hash_value,
+                cases: hash_cases,
+            }, index_switch] => match index_switch.get_switch_mut() {
+                Some((index_value, cases))
+                    if matches!(initial.as_ref(), AST::ConstInt { value: -1 })
+                        && is_variable(index_value, *index) =>
+                {
+                    let is_hash_code = matches!(hash_value.as_ref(), AST::Call {
+                            method_data,
+                            reference,
+                            args,
+                        } if method_data.name_and_type.name == "hashCode"
+                            && args.is_empty()
+                            && is_variable(reference, *temp));
+                    let mut indices = HashMap::new();
+                    let all_cases_match = hash_cases.iter().all(|case| {
+                        !case.is_default
+                            && case.labels.iter().all(|label| match label {
+                                AST::ConstInt { value: hash } => get_string_case_indices(
+                                    &case.body,
+                                    *temp,
+                                    *index,
+                                    *hash,
+                                    &mut indices,
+                                ),
+                                _ => false,
+                            })
+                    });
+                    let all_labels_match = cases.iter().flat_map(|case| &case.labels).all(
+                        |label| matches!(label, AST::ConstInt { value } if indices.contains_key(value)),
+                    );
+                    let matched = is_hash_code && all_cases_match && all_labels_match;
+                    if matched {
+                        for case in cases.iter_mut() {
+                            for label in &mut case.labels {
+                                if let AST::ConstInt { value } = label {
+                                    *label = AST::ConstString {
+                                        value: indices[value].clone(),
+                                    };
+                                }
+                            }
+                        }
+                        **index_value = string.as_ref().clone();
+                    }
+                    matched
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if matched {
+            statements.drain(i..i + 3);
+        }
+        i += 1;
+    }
+}
+
+/// Matches the accessor calls javac generates for the components of a record pattern on the
+/// record in `record`. Returns the pattern and the number of statements it spans.
+fn match_record_pattern(
+    statements: &[AST],
+    record: u16,
+    class_data: &ConstClassData,
+    components: &[RecordComponent],
+) -> Option<(Pattern, usize)> {
+    let is_accessor_call = |value: &AST, component: &RecordComponent| match value {
+        AST::Call {
+            method_data,
+            reference,
+            args,
+        } => {
+            method_data.class.name == class_data.name
+                && method_data.name_and_type.name == component.name
+                && args.is_empty()
+                && is_variable(reference, record)
+        }
+        _ => false,
+    };
+    let mut patterns = Vec::with_capacity(components.len());
+    let mut len = 0;
+    for component in components {
+        // the component is read into a temporary and then copied into the binding
+        let binding = match &statements[len..] {
+            [AST::Set {
+                index: temp,
+                value: call,
+            }, AST::Set { index, value }, ..]
+                if is_accessor_call(call, component) && is_variable(value, *temp) =>
+            {
+                len += 2;
+                *index
+            }
+            [AST::Set { index, value }, ..] if is_accessor_call(value, component) => {
+                len += 1;
+                *index
+            }
+            _ => return None,
+        };
+        patterns.push(Pattern::Type {
+            pattern_type: descriptors::parse_field(component.descriptor.chars()).ok()?,
+            binding: Some(binding),
+        });
+    }
+    let pattern = Pattern::Record {
+        class_data: class_data.clone(),
+        components: patterns,
+    };
+    Some((pattern, len))
+}
+
+/// Takes the guard of a pattern `case` from the start of its body, where javac checks it and
+/// continues matching at the next label if it does not hold.
+fn take_pattern_guard(body: &mut Vec<AST>, next_label: i32) -> Option<AST> {
+    let is_restart = |statements: &[AST]| matches!(statements, [AST::PatternRestart { label }] if *label == next_label);
+    let guard = match body.first_mut()? {
+        AST::If {
+            condition,
+            then,
+            otherwise,
+        } if is_restart(then) && otherwise.is_empty() => {
+            let guard = condition.as_ref().clone().negate();
+            body.remove(0);
+            guard
+        }
+        AST::If {
+            condition,
+            then,
+            otherwise,
+        } if is_restart(otherwise) => {
+            let guard = condition.as_ref().clone();
+            let then = std::mem::take(then);
+            body.splice(0..1, then);
+            guard
+        }
+        _ => return None,
+    };
+    // conditions of nested patterns are checked one after another
+    Some(match take_pattern_guard(body, next_label) {
+        Some(next) => AST::Binary {
+            operator: BinaryOperator::And,
+            lhs: Box::new(guard),
+            rhs: Box::new(next),
+        },
+        None => guard,
+    })
+}
+
+fn contains_pattern_restart(statements: &mut [AST]) -> bool {
+    statements.iter_mut().any(|statement| {
+        matches!(statement, AST::PatternRestart { .. })
+            || statement
+                .statement_lists_mut()
+                .into_iter()
+                .any(|statements| contains_pattern_restart(statements))
+    })
+}
+
+/// Checks if a `default` case only throws the error javac adds to exhaustive switches.
+fn is_exhaustiveness_error(case: &Case) -> bool {
+    case.is_default
+        && case.labels.is_empty()
+        && matches!(case.body.as_slice(), [AST::Throw { value }] if matches!(value.as_ref(),
+            AST::New { method_data, .. } if method_data.class.name == "java/lang/MatchException"
+                || method_data.class.name == "java/lang/IncompatibleClassChangeError"))
+}
+
+/// Removes the `default` case javac adds to `switch` expressions and pattern `switch` statements
+/// that cover all values, unless other cases fall through to it.
+fn remove_exhaustiveness_error(cases: &mut Vec<Case>) {
+    let i = match cases.iter().position(is_exhaustiveness_error) {
+        Some(i) => i,
+        None => return,
+    };
+    // javac may place it before the others
+    let is_entered_by_jump = match i.checked_sub(1) {
+        Some(previous) => matches!(
+            cases[previous].body.last(),
+            Some(
+                AST::Break
+                    | AST::Continue
+                    | AST::Return { .. }
+                    | AST::VoidReturn
+                    | AST::Throw { .. }
+                    | AST::Yield { .. }
+            )
+        ),
+        None => true,
+    };
+    if is_entered_by_jump {
+        cases.remove(i);
+    }
+}
+
+/// Rebuilds the cases of a pattern `switch` from the labels passed to `typeSwitch` or
+/// `enumSwitch`, the value of which javac stored in `selector`.
+fn get_pattern_cases(
+    cases: &[Case],
+    labels: &[PatternLabel],
+    selector: u16,
+    class_path: &ClassPath,
+) -> Option<Vec<Case>> {
+    let mut pattern_cases = Vec::with_capacity(cases.len());
+    for case in cases {
+        let mut case = case.clone();
+        let mut constants = Vec::new();
+        let mut pattern_label = None;
+        for label in &case.labels {
+            match label {
+                AST::ConstInt { value: -1 } => constants.push(AST::ConstNull),
+                AST::ConstInt { value } => match labels.get(usize::try_from(*value).ok()?)? {
+                    PatternLabel::Constant(constant) => constants.push(constant.clone()),
+                    PatternLabel::Class(class_data) => pattern_label = Some((*value, class_data)),
+                },
+                _ => return None,
+            }
+        }
+        case.labels = constants;
+        if let Some((label, class_data)) = pattern_label {
+            if !case.labels.is_empty() {
+                return None;
+            }
+            // the selector is not cast if it already has the type of the pattern
+            let binding = match case.body.first() {
+                Some(AST::Set { index, value }) => match value.as_ref() {
+                    AST::ClassCast { value, cast_type }
+                        if is_variable(value, selector) && cast_type.name == class_data.name =>
+                    {
+                        Some(*index)
+                    }
+                    value if is_variable(value, selector) => Some(*index),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if binding.is_some() {
+                case.body.remove(0);
+            }
+            let components = class_path
+                .get(&class_data.name)
+                .and_then(|record| get_record_components(record).ok().flatten());
+            let record_pattern = match (binding, components) {
+                (Some(binding), Some(components)) => {
+                    match_record_pattern(&case.body, binding, class_data, &components)
+                }
+                _ => None,
+            };
+            case.pattern = Some(match record_pattern {
+                Some((pattern, len)) => {
+                    case.body.drain(..len);
+                    pattern
+                }
+                None => Pattern::from_class(class_data, binding),
+            });
+            case.guard = take_pattern_guard(&mut case.body, label + 1);
+        }
+        if contains_pattern_restart(&mut case.body) {
+            return None;
+        }
+        pattern_cases.push(case);
+    }
+    // javac jumps past the `switch` for a `default` case ending it, which is still needed for
+    // the `switch` to cover all values
+    if !pattern_cases.iter().any(|case| case.is_default) {
+        pattern_cases.push(Case {
+            labels: Vec::new(),
+            pattern: None,
+            guard: None,
+            is_default: true,
+            body: Vec::new(),
+        });
+    }
+    remove_exhaustiveness_error(&mut pattern_cases);
+    Some(pattern_cases)
+}
+
+/// Turns the `switch` on the result of `typeSwitch` or `enumSwitch` back into a pattern
+/// `switch`. javac stores the selector and the label to start matching at in locals before.
+fn sugar_pattern_switch(statements: &mut Vec<AST>, class_path: &ClassPath) {
+    for statement in statements.iter_mut() {
+        for statements in statement.statement_lists_mut() {
+            sugar_pattern_switch(statements, class_path);
+        }
+    }
+    let mut i = 0;
+    while i + 2 < statements.len() {
+        let matched = match &mut statements[i..=i + 2] {
+            [AST::Set {
+                index: selector,
+                value: selector_value,
+            }, AST::Set {
+                index: restart,
+                value: start,
+            }, switch] => match switch.get_switch_mut() {
+                Some((value, cases)) => {
+                    let pattern_cases = match value.as_ref() {
+                        AST::PatternSwitchIndex {
+                            value,
+                            restart: restart_value,
+                            labels,
+                        } if is_variable(value, *selector)
+                            && is_variable(restart_value, *restart)
+                            && matches!(start.as_ref(), AST::ConstInt { value: 0 }) =>
+                        {
+                            get_pattern_cases(cases, labels, *selector, class_path)
+                        }
+                        _ => None,
+                    };
+                    pattern_cases.map(|pattern_cases| {
+                        *cases = pattern_cases;
+                        **value = selector_value.as_ref().clone();
+                        value.as_ref().clone()
+                    })
+                }
+                None => None,
+            },
+            _ => None,
+        };
+        if let Some(selector_value) = matched {
+            statements.drain(i..i + 2);
+            // javac checks the selector for `null` unless there is a `case null`
+            if let Some(AST::StaticCall { method_data, args }) = i
+                .checked_sub(1)
+                .and_then(|previous| statements.get(previous))
+            {
+                if method_data.class.name == "java/util/Objects"
+                    && method_data.name_and_type.name == "requireNonNull"
+                    && matches!(args.as_slice(), [arg] if format!("{:?}", arg) == format!("{:?}", selector_value))
+                {
+                    statements.remove(i - 1);
+                    i -= 1;
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
+/// Reads the case values javac assigns to the constants of an enum from the static initializer
+/// of the synthetic class holding the `$SwitchMap` array `name`.
+fn get_switch_map(class: &ClassFile, name: &str) -> Option<HashMap<i32, ConstFieldData>> {
+    let constant_pool = &class.constant_pool;
+    let initializer = class.methods.iter().find(|method| {
+        constant_pool
+            .get_utf8_entry(method.name_index)
+            .is_ok_and(|name| name == "<clinit>")
+    })?;
+    let instructions = disassembler::disassemble(get_code(initializer)?.clone()).ok()?;
+    let mut switch_map = HashMap::new();
+    // javac assigns each value as `$SwitchMap$...[Constant.ordinal()] = value`
+    for window in instructions.windows(5) {
+        if let [(_, Instruction::GetStatic { index: array }), (_, Instruction::GetStatic { index: constant }), (_, Instruction::InvokeVirtual { index: ordinal }), (_, value), (_, Instruction::IAStore)] =
+            window
+        {
+            let value = match value {
+                Instruction::IConst { value } => *value,
+                Instruction::BIPush { value } => *value as i32,
+                Instruction::SIPush { value } => *value as i32,
+                _ => continue,
+            };
+            let array = constant_pool.get_field_entry(*array).ok()?;
+            let ordinal = constant_pool.get_method_or_interface_entry(*ordinal).ok()?;
+            if array.name_and_type.name == name && ordinal.name_and_type.name == "ordinal" {
+                switch_map.insert(value, constant_pool.get_field_entry(*constant).ok()?);
+            }
+        }
+    }
+    Some(switch_map)
+}
+
+/// Turns a `switch` on the ordinal of an enum back into a `switch` on its constants. javac maps
+/// the ordinals to the case values through a `$SwitchMap` array of a synthetic class, which
+/// has to be on the class path.
+fn sugar_enum_switch(statements: &mut [AST], class_path: &ClassPath) {
+    for statement in statements.iter_mut() {
+        for statements in statement.statement_lists_mut() {
+            sugar_enum_switch(statements, class_path);
+        }
+        let (value, cases) = match statement.get_switch_mut() {
+            Some(switch) => switch,
+            None => continue,
+        };
+        let (field_data, reference) = match value.as_ref() {
+            AST::ArrayElement { array, index } => match (array.as_ref(), index.as_ref()) {
+                (
+                    AST::Static { field_data },
+                    AST::Call {
+                        method_data,
+                        reference,
+                        args,
+                    },
+                ) if field_data.name_and_type.name.starts_with("$SwitchMap$")
+                    && method_data.name_and_type.name == "ordinal"
+                    && args.is_empty() =>
+                {
+                    (field_data, reference.as_ref().clone())
+                }
+                _ => continue,
+            },
+            _ => continue,
+        };
+        let switch_map = match class_path
+            .get(&field_data.class.name)
+            .and_then(|class| get_switch_map(class, &field_data.name_and_type.name))
+        {
+            Some(switch_map) => switch_map,
+            None => continue,
+        };
+        let is_mapped = |label: &AST| matches!(label, AST::ConstInt { value } if switch_map.contains_key(value));
+        if !cases.iter().flat_map(|case| &case.labels).all(is_mapped) {
+            continue;
+        }
+        for case in cases.iter_mut() {
+            for label in &mut case.labels {
+                if let AST::ConstInt { value } = label {
+                    *label = AST::Static {
+                        field_data: switch_map[value].clone(),
+                    };
+                }
+            }
+        }
+        remove_exhaustiveness_error(cases);
+        **value = reference;
+    }
+}
+
+/// The operand stack of a block. Values popped beyond its bottom were pushed by a preceding
+/// block and are represented by `AST::StackInput` placeholders.
+struct Stack {
+    values: Vec<AST>,
+    inputs: usize,
+    /// Whether each value takes up two slots, as the type of array elements isn't known yet.
+    wide: Vec<bool>,
+    /// Copied values with side effects, which are evaluated once for all their copies.
+    duplicated: Vec<AST>,
+}
+
+impl Stack {
+    fn push(&mut self, value: AST) {
+        let wide = is_wide(&value);
+        self.push_sized(value, wide);
+    }
+
+    fn push_sized(&mut self, value: AST, wide: bool) {
+        self.values.push(value);
+        self.wide.push(wide);
+    }
+
+    fn pop(&mut self) -> AST {
+        self.pop_sized().0
+    }
+
+    fn pop_sized(&mut self) -> (AST, bool) {
+        match self.values.pop() {
+            Some(value) => (value, self.wide.pop().unwrap_or(false)),
+            None => {
+                self.inputs += 1;
+                let input = AST::StackInput {
+                    index: self.inputs - 1,
+                };
+                (input, false)
+            }
+        }
+    }
+
+    /// Replaces the top value, e.g. a variable with its increment.
+    fn replace_top(&mut self, value: AST) {
+        let (_, wide) = self.pop_sized();
+        self.push_sized(value, wide);
+    }
+
+    /// Pops the values taking up the top `slots` slots of the operand stack, the topmost last.
+    fn pop_slots(&mut self, slots: usize) -> Vec<(AST, bool)> {
+        let mut values = Vec::new();
+        let mut popped = 0;
+        while popped < slots {
+            let (value, wide) = self.pop_sized();
+            popped += if wide { 2 } else { 1 };
+            values.push((value, wide));
+        }
+        values.reverse();
+        values
+    }
+
+    /// Copies the values taking up the top `slots` slots below the values taking up the next
+    /// `depth` slots, like `dup_x1` or `dup2_x2`.
+    fn duplicate(&mut self, slots: usize, depth: usize) {
+        let top = self.pop_slots(slots);
+        let below = self.pop_slots(depth);
+        for (value, _) in &top {
+            if has_side_effects(&mut value.clone()) {
+                self.duplicated.push(value.clone());
+            }
+        }
+        for (value, wide) in top.clone().into_iter().chain(below).chain(top) {
+            self.push_sized(value, wide);
+        }
+    }
+}
+
+/// Checks if a value takes up two slots of the operand stack, i.e. is a `long` or `double`.
+fn is_wide(value: &AST) -> bool {
+    match value {
+        AST::Variable { vartype, .. } => matches!(vartype, VarType::Long | VarType::Double),
+        AST::ConstLong { .. } | AST::ConstDouble { .. } => true,
+        AST::BasicCast { cast_type, .. } => matches!(cast_type, VarType::Long | VarType::Double),
+        AST::Binary { operator, lhs, .. } => {
+            !operator.is_comparison()
+                && !matches!(operator, BinaryOperator::And | BinaryOperator::Or)
+                && is_wide(lhs)
+        }
+        AST::Unary {
+            operator: UnaryOperator::Neg,
+            value,
+        } => is_wide(value),
+        AST::Increment { target, .. } => is_wide(target),
+        _ => matches!(
+            get_type(value, &HashMap::new()),
+            Some(descriptors::FieldType::Long | descriptors::FieldType::Double)
+        ),
+    }
+}
+
+/// Keeps a discarded value as a statement if evaluating it has side effects.
+fn discard(value: AST, statements: &mut Vec<AST>) {
+    match value {
+        AST::Call { .. } | AST::StaticCall { .. } | AST::New { .. } | AST::StackInput { .. } => {
+            statements.push(value)
+        }
+        AST::Assign { target, value } => statements.push(match *target {
+            AST::Variable { index, .. } => AST::Set { index, value },
+            AST::Static { field_data } => AST::SetStatic { field_data, value },
+            AST::Field {
+                field_data,
+                reference,
+            } => AST::SetField {
+                field_data,
+                reference,
+                value,
+            },
+            AST::ArrayElement { array, index } => AST::SetArrayElement {
+                array,
+                index,
+                value,
+            },
+            target => AST::Assign {
+                target: Box::new(target),
+                value,
+            },
+        }),
+        AST::Increment {
+            target, operator, ..
+        } => statements.push(AST::CompoundAssign {
+            target,
+            operator,
+            value: Box::new(AST::ConstInt { value: 1 }),
+            is_concat: false,
+        }),
+        _ => {}
+    }
+}
+
+fn is_one(value: &AST) -> bool {
+    match value {
+        AST::ConstInt { value } => *value == 1,
+        AST::ConstLong { value } => *value == 1,
+        AST::ConstFloat { value } => *value == 1.0,
+        AST::ConstDouble { value } => *value == 1.0,
+        _ => false,
+    }
+}
+
+/// Checks if two values are the same expression, e.g. a value and its copy made by `dup`.
+fn is_same_value(a: &AST, b: &AST) -> bool {
+    let names = NameResolver::new("");
+    a.to_java(false, &names) == b.to_java(false, &names)
+}
+
+/// Decompiles an assignment that is part of an expression: an increment, a compound assignment
+/// of a target with side effects, or an assignment whose value is used as well. Returns false
+/// for plain assignments, which are left to the caller.
+fn assign_in_expression(
+    target: &AST,
+    value: &AST,
+    is_copied: bool,
+    stack: &mut Stack,
+    statements: &mut Vec<AST>,
+) -> bool {
+    if let Some(increment) = get_increment(target, value, stack) {
+        stack.replace_top(increment);
+    } else if let Some(assignment) = get_compound_assignment(target, value, stack) {
+        statements.push(assignment);
+    } else if is_copied
+        && stack
+            .values
+            .last()
+            .is_some_and(|top| is_same_value(top, value))
+    {
+        stack.replace_top(AST::Assign {
+            target: Box::new(target.clone()),
+            value: Box::new(value.clone()),
+        });
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Returns the compound assignment an assignment is if its target has side effects and was
+/// copied to compute the assigned value, as for `a[f()] *= 2`.
+fn get_compound_assignment(target: &AST, value: &AST, stack: &Stack) -> Option<AST> {
+    let mut parts = target.clone();
+    let is_copied = parts.children_mut().into_iter().all(|part| {
+        !has_side_effects(part)
+            || stack
+                .duplicated
+                .iter()
+                .any(|copied| is_same_value(copied, part))
+    });
+    if !has_side_effects(&mut parts) || !is_copied {
+        return None;
+    }
+    let value = match value {
+        AST::BasicCast {
+            cast_type: VarType::Byte | VarType::Short | VarType::Char,
+            value,
+        } => value.as_ref(),
+        value => value,
+    };
+    match value {
+        AST::Binary { operator, lhs, rhs }
+            if !operator.is_comparison()
+                && !matches!(operator, BinaryOperator::And | BinaryOperator::Or)
+                && is_same_value(lhs, target) =>
+        {
+            Some(AST::CompoundAssign {
+                target: Box::new(target.clone()),
+                operator: *operator,
+                value: rhs.clone(),
+                is_concat: false,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Returns the increment an assignment is part of if the stack holds the assigned variable,
+/// field or array element from before or after the assignment, as for `a[i++]` or `++x`.
+fn get_increment(target: &AST, value: &AST, stack: &Stack) -> Option<AST> {
+    // increments of `byte`, `short` and `char` values are narrowed again
+    let increment = match value {
+        AST::BasicCast {
+            cast_type: VarType::Byte | VarType::Short | VarType::Char,
+            value,
+        } => value.as_ref(),
+        value => value,
+    };
+    let operator = match increment {
+        AST::Binary {
+            operator: operator @ (BinaryOperator::Add | BinaryOperator::Sub),
+            lhs,
+            rhs,
+        } if is_one(rhs) && is_same_value(lhs, target) => *operator,
+        _ => return None,
+    };
+    let top = stack.values.last()?;
+    let is_prefix = if is_same_value(top, target) {
+        false
+    } else if is_same_value(top, value) {
+        true
+    } else {
+        return None;
+    };
+    Some(AST::Increment {
+        target: Box::new(target.clone()),
+        operator,
+        is_prefix,
+    })
+}
+
+fn get_array_element_type(array_type: &disassembler::ArrayType) -> descriptors::FieldType {
+    match array_type {
+        disassembler::ArrayType::Boolean => descriptors::FieldType::Boolean,
+        disassembler::ArrayType::Char => descriptors::FieldType::Char,
+        disassembler::ArrayType::Float => descriptors::FieldType::Float,
+        disassembler::ArrayType::Double => descriptors::FieldType::Double,
+        disassembler::ArrayType::Byte => descriptors::FieldType::Byte,
+        disassembler::ArrayType::Short => descriptors::FieldType::Short,
+        disassembler::ArrayType::Int => descriptors::FieldType::Int,
+        disassembler::ArrayType::Long => descriptors::FieldType::Long,
+    }
+}
+
+/// Builds the condition of a conditional branch comparing `value` to zero. The result of
+/// comparing floating-point values is kept, as it depends on the instruction if either is NaN.
+fn get_condition(operator: BinaryOperator, value: AST) -> AST {
+    match value {
+        AST::Compare {
+            lhs,
+            rhs,
+            nan_result: None,
+            ..
+        } => AST::Binary { operator, lhs, rhs },
+        value => AST::Binary {
+            operator,
+            lhs: Box::new(value),
+            rhs: Box::new(AST::ConstInt { value: 0 }),
+        },
+    }
+}
+
+/// The class and method the code being decompiled belongs to.
+struct CodeContext<'a> {
+    class: &'a ClassFile,
+    class_path: &'a ClassPath,
+    max_locals: u16,
+    is_constructor: bool,
+}
+
+fn get_bootstrap_methods(class: &ClassFile) -> &[BootstrapMethodInfo] {
+    class
+        .attributes
+        .iter()
+        .find_map(|attrib| match attrib {
+            AttributeInfo::BootstrapMethods { bootstrap_methods } => {
+                Some(bootstrap_methods.as_slice())
+            }
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Finds the synthetic method javac generated for the body of a lambda expression.
+fn find_lambda_method<'a>(
+    class: &'a ClassFile,
+    method_data: &ConstMethodData,
+) -> Result<Option<&'a MethodInfo>, DecompilerError> {
+    let class_name = class.constant_pool.get_class_entry(class.this_class)?.name;
+    if method_data.class.name != class_name
+        || !method_data.name_and_type.name.starts_with("lambda$")
+    {
+        return Ok(None);
+    }
+    for method in &class.methods {
+        if class.constant_pool.get_utf8_entry(method.name_index)? == method_data.name_and_type.name
+            && class
+                .constant_pool
+                .get_utf8_entry(method.descriptor_index)?
+                == method_data.name_and_type.descriptor
+            && get_code(method).is_some()
+        {
+            return Ok(Some(method));
+        }
+    }
+    Ok(None)
+}
+
+/// Replaces the captured locals of an inlined lambda body with the captured values and moves
+/// its other locals behind the locals of the enclosing method.
+fn inline_locals(value: &mut AST, captured: &HashMap<u16, AST>, renumber: &dyn Fn(u16) -> u16) {
+    match value {
+        AST::Variable { index, .. } => match captured.get(index) {
+            // the captured value belongs to the enclosing method and is not renumbered
+            Some(captured) => {
+                *value = captured.clone();
+                return;
+            }
+            None => *index = renumber(*index),
+        },
+        AST::Set { index, .. } | AST::ForEach { index, .. } => *index = renumber(*index),
+        AST::Lambda { params, .. } => {
+            for param in params {
+                *param = renumber(*param);
+            }
+        }
+        _ => {}
+    }
+    for child in value.children_mut() {
+        inline_locals(child, captured, renumber);
+    }
+}
+
+fn decompile_lambda(
+    method: &MethodInfo,
+    captured: Vec<AST>,
+    context: &CodeContext,
+) -> Result<AST, DecompilerError> {
+    let constant_pool = &context.class.constant_pool;
+    let code = get_code(method).ok_or(DecompilerError::EndOfCode)?;
+    let mut body = decompile_code(method, code, context.class, context.class_path)?;
+    if let Some(AST::VoidReturn) = body.last() {
+        body.pop();
+    }
+
+    // the captured values are passed as the first arguments of the lambda method
+    let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+    let (param_types, _) = descriptors::parse_method(descriptor.chars())?;
+    let mut param_types = param_types.into_iter();
+    let mut captured_locals = HashMap::new();
+    let mut slot = 0;
+    for (i, value) in captured.iter().enumerate() {
+        let size = if i == 0 && !method.access_flags.acc_static {
+            1
+        } else {
+            param_types.next().map_or(1, |param| param.size())
+        };
+        captured_locals.insert(slot, value.clone());
+        slot += size;
+    }
+    let first_slot = slot;
+    let renumber = |index: u16| {
+        if index >= first_slot {
+            index - first_slot + context.max_locals
+        } else {
+            index
+        }
+    };
+    let mut params = Vec::new();
+    for param in param_types {
+        params.push(renumber(slot));
+        slot += param.size();
+    }
+    for statement in &mut body {
+        inline_locals(statement, &captured_locals, &renumber);
+    }
+    Ok(AST::Lambda {
+        params,
+        body,
+        captured,
+    })
+}
+
+/// Checks if a statement is the null check javac generates for the receiver of a bound method
+/// reference, `Objects.requireNonNull(receiver)` or `receiver.getClass()` before Java 9.
+fn is_null_check(statement: &AST, receiver: &AST) -> bool {
+    match statement {
+        AST::StaticCall { method_data, args } => {
+            method_data.class.name == "java/util/Objects"
+                && method_data.name_and_type.name == "requireNonNull"
+                && matches!(args.as_slice(), [arg] if is_same_value(arg, receiver))
+        }
+        AST::Call {
+            method_data,
+            reference,
+            args,
+        } => {
+            method_data.name_and_type.name == "getClass"
+                && args.is_empty()
+                && is_same_value(reference, receiver)
+        }
+        _ => false,
+    }
+}
+
+/// Decompiles the call sites javac generates for lambdas and method references. Returns `None`
+/// for other bootstrap methods.
+fn decompile_invoke_dynamic(
+    call_site: &ConstInvokeDynamicData,
+    args: Vec<AST>,
+    context: &CodeContext,
+) -> Result<Option<AST>, DecompilerError> {
+    let constant_pool = &context.class.constant_pool;
+    let bootstrap_method = match get_bootstrap_methods(context.class)
+        .get(call_site.bootstrap_method_attr_index as usize)
+    {
+        Some(bootstrap_method) => bootstrap_method,
+        None => return Ok(None),
+    };
+    match constant_pool
+        .get_method_handle_entry(bootstrap_method.bootstrap_method_ref)?
+        .reference
+    {
+        MethodHandleReference::Method { method_data }
+            if method_data.class.name == "java/lang/invoke/LambdaMetafactory"
+                && (method_data.name_and_type.name == "metafactory"
+                    || method_data.name_and_type.name == "altMetafactory") => {}
+        MethodHandleReference::Method { method_data }
+            if method_data.class.name == "java/lang/runtime/SwitchBootstraps" =>
+        {
+            return decompile_pattern_switch_index(
+                &method_data.name_and_type.name,
+                call_site,
+                bootstrap_method,
+                args,
+                constant_pool,
+            );
+        }
+        MethodHandleReference::Method { method_data }
+            if method_data.class.name == "java/lang/invoke/StringConcatFactory" =>
+        {
+            return decompile_string_concat(
+                &method_data.name_and_type.name,
+                call_site,
+                bootstrap_method,
+                args,
+                constant_pool,
+            );
+        }
+        _ => return Ok(None),
+    }
+    let implementation = match bootstrap_method.bootstrap_arguments.get(1) {
+        Some(index) => constant_pool.get_method_handle_entry(*index)?,
+        None => return Ok(None),
+    };
+    let method_data = match implementation.reference {
+        MethodHandleReference::Method { method_data } => method_data,
+        MethodHandleReference::Field { .. } => return Ok(None),
+    };
+    if let Some(method) = find_lambda_method(context.class, &method_data)? {
+        return decompile_lambda(method, args, context).map(Some);
+    }
+    // static methods and constructors never have a receiver, instance methods only a bound one
+    let reference = match implementation.reference_kind {
+        6 | 8 => None,
+        _ => args.into_iter().next().map(Box::new),
+    };
+    Ok(Some(AST::MethodReference {
+        method_data,
+        reference,
+    }))
+}
+
+/// Decompiles the call sites of `typeSwitch` and `enumSwitch`, whose static arguments are the
+/// labels of a pattern `switch`.
+fn decompile_pattern_switch_index(
+    name: &str,
+    call_site: &ConstInvokeDynamicData,
+    bootstrap_method: &BootstrapMethodInfo,
+    args: Vec<AST>,
+    constant_pool: &ConstantPool,
+) -> Result<Option<AST>, DecompilerError> {
+    if name != "typeSwitch" && name != "enumSwitch" {
+        return Ok(None);
+    }
+    let (params, _) = descriptors::parse_method(call_site.name_and_type.descriptor.chars())?;
+    let selector_class = match params.first() {
+        Some(descriptors::FieldType::Reference { name }) => name.clone(),
+        _ => return Ok(None),
+    };
+    let mut labels = Vec::with_capacity(bootstrap_method.bootstrap_arguments.len());
+    for index in &bootstrap_method.bootstrap_arguments {
+        let label = match constant_pool.get_entry(*index)? {
+            ConstantPoolInfo::Class { .. } => {
+                PatternLabel::Class(constant_pool.get_class_entry(*index)?)
+            }
+            // `enumSwitch` refers to the constants of the enum by their name
+            ConstantPoolInfo::String { string_index } if name == "enumSwitch" => {
+                PatternLabel::Constant(AST::Static {
+                    field_data: ConstFieldData {
+                        class: ConstClassData {
+                            name: selector_class.clone(),
+                        },
+                        name_and_type: ConstNameTypeData {
+                            name: constant_pool.get_utf8_entry(string_index)?,
+                            descriptor: format!("L{};", selector_class),
+                        },
+                    },
+                })
+            }
+            entry => match get_constant(entry, constant_pool)? {
+                Some(value) => PatternLabel::Constant(value),
+                None => return Ok(None),
+            },
+        };
+        labels.push(label);
+    }
+    let mut args = args.into_iter();
+    Ok(match (args.next(), args.next()) {
+        (Some(value), Some(restart)) => Some(AST::PatternSwitchIndex {
+            value: Box::new(value),
+            restart: Box::new(restart),
+            labels,
+        }),
+        _ => None,
+    })
+}
+
+/// Decompiles the call sites javac generates for string concatenation since Java 9. The recipe
+/// of `makeConcatWithConstants` marks arguments with `\u{1}` and constants with `\u{2}`.
+fn decompile_string_concat(
+    name: &str,
+    call_site: &ConstInvokeDynamicData,
+    bootstrap_method: &BootstrapMethodInfo,
+    args: Vec<AST>,
+    constant_pool: &ConstantPool,
+) -> Result<Option<AST>, DecompilerError> {
+    let (params, _) = descriptors::parse_method(call_site.name_and_type.descriptor.chars())?;
+    let mut args = args.into_iter().zip(params).map(|(arg, param)| {
+        let is_string = is_string_type(&param);
+        (with_type(arg, &param), is_string)
+    });
+    let values = match name {
+        "makeConcat" => args.collect(),
+        "makeConcatWithConstants" => {
+            let recipe = match bootstrap_method.bootstrap_arguments.first() {
+                Some(index) => match get_constant(constant_pool.get_entry(*index)?, constant_pool)?
+                {
+                    Some(AST::ConstString { value }) => value,
+                    _ => return Ok(None),
+                },
+                None => return Ok(None),
+            };
+            let mut constants = bootstrap_method.bootstrap_arguments[1..].iter();
+            let mut values = Vec::new();
+            let mut literal = String::new();
+            for ch in recipe.chars() {
+                let value = match ch {
+                    '\u{1}' => args.next(),
+                    '\u{2}' => match constants.next() {
+                        Some(index) => {
+                            get_constant(constant_pool.get_entry(*index)?, constant_pool)?.map(
+                                |value| {
+                                    let is_string = matches!(value, AST::ConstString { .. });
+                                    (value, is_string)
+                                },
+                            )
+                        }
+                        None => None,
+                    },
+                    _ => {
+                        literal.push(ch);
+                        continue;
+                    }
+                };
+                if !literal.is_empty() {
+                    let value = std::mem::take(&mut literal);
+                    values.push((AST::ConstString { value }, true));
+                }
+                match value {
+                    Some(value) => values.push(value),
+                    None => return Ok(None),
+                }
+            }
+            if !literal.is_empty() {
+                values.push((AST::ConstString { value: literal }, true));
+            }
+            values
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(make_concat(values)))
+}
+
+/// Checks if a constructor call initializes a new object rather than being the `super(...)` or
+/// `this(...)` call of a constructor. If the receiver was pushed by a preceding block, calls to
+/// constructors of the own or the super class within a constructor are assumed to be the latter.
+fn is_object_creation(
+    reference: &AST,
+    method_data: &ConstMethodData,
+    context: &CodeContext,
+) -> Result<bool, DecompilerError> {
+    Ok(match reference {
+        AST::Uninitialized { .. } => true,
+        AST::StackInput { .. } if context.is_constructor => {
+            let constant_pool = &context.class.constant_pool;
+            let class_name = constant_pool
+                .get_class_entry(context.class.this_class)?
+                .name;
+            let super_class_name = constant_pool
+                .get_class_entry(context.class.super_class)?
+                .name;
+            method_data.class.name != class_name && method_data.class.name != super_class_name
+        }
+        AST::StackInput { .. } => true,
+        _ => false,
+    })
+}
+
+fn decompile_block(block: &Block, context: &CodeContext) -> Result<Node, DecompilerError> {
+    let constant_pool = &context.class.constant_pool;
+    let mut statements = Vec::new();
+    let mut condition = None;
+    let mut switch = None;
+
+    let mut stack = Stack {
+        values: Vec::new(),
+        inputs: 0,
+        wide: Vec::new(),
+        duplicated: Vec::new(),
+    };
+    let mut instructions = block.instructions.iter().peekable();
+    let mut previous = None;
+    while let Some((_, code)) = instructions.next() {
+        // a value stored right after being copied is also used as the value of the assignment
+        let is_copied = matches!(
+            previous.replace(code),
+            Some(
+                Instruction::Dup
+                    | Instruction::DupX1
+                    | Instruction::DupX2
+                    | Instruction::Dup2
+                    | Instruction::Dup2X1
+                    | Instruction::Dup2X2
+            )
+        );
+        match code {
+            Instruction::ILoad { index } => {
+                stack.push(AST::Variable {
+                    index: *index,
+                    vartype: VarType::Int,
+                });
+            }
+            Instruction::LLoad { index } => {
+                stack.push(AST::Variable {
+                    index: *index,
+                    vartype: VarType::Long,
+                });
+            }
+            Instruction::FLoad { index } => {
+                stack.push(AST::Variable {
+                    index: *index,
+                    vartype: VarType::Float,
+                });
+            }
+            Instruction::DLoad { index } => {
+                stack.push(AST::Variable {
+                    index: *index,
+                    vartype: VarType::Double,
+                });
+            }
+            Instruction::ALoad { index } => {
+                stack.push(AST::Variable {
+                    index: *index,
+                    vartype: VarType::Reference,
+                });
+            }
+            Instruction::InvokeSpecial { index }
+            | Instruction::InvokeVirtual { index }
+            | Instruction::InvokeInterface { index } => {
+                let method = constant_pool.get_method_or_interface_entry(*index)?;
+                let descriptor =
+                    descriptors::parse_method(method.name_and_type.descriptor.chars())?;
+                let mut args = Vec::new();
+                for _ in &descriptor.0 {
+                    args.push(stack.pop());
+                }
+                args.reverse();
+                let reference = Box::new(stack.pop());
+                if method.name_and_type.name == "<init>"
+                    && is_object_creation(&reference, &method, context)?
+                {
+                    stack.push(AST::New {
+                        method_data: method,
+                        args,
+                    });
+                } else if descriptor.1 == descriptors::FieldType::Void {
+                    statements.push(AST::Call {
+                        method_data: method,
+                        reference,
+                        args,
+                    });
+                } else {
+                    stack.push(AST::Call {
+                        method_data: method,
+                        reference,
+                        args,
+                    });
+                }
+            }
+            Instruction::InvokeStatic { index } => {
+                let method_data = constant_pool.get_method_or_interface_entry(*index)?;
+                let descriptor =
+                    descriptors::parse_method(method_data.name_and_type.descriptor.chars())?;
+                let mut args = Vec::new();
+                for _ in &descriptor.0 {
+                    args.push(stack.pop());
+                }
+                args.reverse();
+                let class_name = constant_pool
+                    .get_class_entry(context.class.this_class)?
+                    .name;
+                let is_class_helper = method_data.class.name == class_name
+                    && (
+                        method_data.name_and_type.name.as_str(),
+                        method_data.name_and_type.descriptor.as_str(),
+                    ) == CLASS_HELPER;
+                if let (true, [AST::ConstString { value }]) = (is_class_helper, args.as_slice()) {
+                    stack.push(get_class_literal(&value.replace('.', "/"))?);
+                    continue;
+                }
+                let call = AST::StaticCall { method_data, args };
+                if descriptor.1 == descriptors::FieldType::Void {
+                    statements.push(call);
+                } else {
+                    stack.push(call);
+                }
+            }
+            Instruction::Return => {
+                statements.push(AST::VoidReturn);
+            }
+            Instruction::IReturn
+            | Instruction::LReturn
+            | Instruction::FReturn
+            | Instruction::DReturn
+            | Instruction::AReturn => {
+                let value = Box::new(stack.pop());
+                statements.push(AST::Return { value });
+            }
+            Instruction::Nop => {}
+            Instruction::Pop => discard(stack.pop(), &mut statements),
+            Instruction::Pop2 => {
+                for (value, _) in stack.pop_slots(2) {
+                    discard(value, &mut statements);
+                }
+            }
+            Instruction::DupX1 => stack.duplicate(1, 1),
+            Instruction::DupX2 => stack.duplicate(1, 2),
+            Instruction::Dup2 => stack.duplicate(2, 0),
+            Instruction::Dup2X1 => stack.duplicate(2, 1),
+            Instruction::Dup2X2 => stack.duplicate(2, 2),
+            Instruction::Swap => {
+                let top = stack.pop();
+                let below = stack.pop();
+                stack.push(top);
+                stack.push(below);
+            }
+            Instruction::AThrow => {
+                let value = Box::new(stack.pop());
+                statements.push(AST::Throw { value });
+            }
+            Instruction::MonitorEnter => {
+                // the lock is stored in a variable for the `monitorexit`s first
+                let value = match stack.pop() {
+                    AST::Assign { target, value } => {
+                        discard(
+                            AST::Assign {
+                                target: target.clone(),
+                                value,
+                            },
+                            &mut statements,
+                        );
+                        target
+                    }
+                    value => Box::new(value),
+                };
+                statements.push(AST::MonitorEnter { value });
+            }
+            Instruction::MonitorExit => {
+                let value = Box::new(stack.pop());
+                statements.push(AST::MonitorExit { value });
+            }
+            Instruction::AConstNull => stack.push(AST::ConstNull),
+            Instruction::New { index } => {
+                let class_data = constant_pool.get_class_entry(*index)?;
+                stack.push(AST::Uninitialized { class_data });
+            }
+            Instruction::Dup => {
+                // the copy of a new object consumed by its constructor call is left out
+                if !matches!(stack.values.last(), Some(AST::Uninitialized { .. })) {
+                    stack.duplicate(1, 0);
+                }
+            }
+            Instruction::InvokeDynamic { index } => {
+                let call_site = constant_pool.get_invoke_dynamic_entry(*index)?;
+                let (params, _) =
+                    descriptors::parse_method(call_site.name_and_type.descriptor.chars())?;
+                let mut args: Vec<AST> = params.iter().map(|_| stack.pop()).collect();
+                args.reverse();
+                match decompile_invoke_dynamic(&call_site, args, context)? {
+                    Some(value) => {
+                        if let AST::MethodReference {
+                            reference: Some(reference),
+                            ..
+                        } = &value
+                        {
+                            if statements
+                                .last()
+                                .is_some_and(|statement| is_null_check(statement, reference))
+                            {
+                                statements.pop();
+                            }
+                        }
+                        stack.push(value)
+                    }
+                    None => {
+                        return Err(DecompilerError::UnsupportedInstr {
+                            instruction: code.clone(),
+                        })
+                    }
+                }
+            }
+            Instruction::LCmp
+            | Instruction::FCmpL
+            | Instruction::FCmpG
+            | Instruction::DCmpL
+            | Instruction::DCmpG => {
+                let compare_type = match code {
+                    Instruction::LCmp => VarType::Long,
+                    Instruction::FCmpL | Instruction::FCmpG => VarType::Float,
+                    _ => VarType::Double,
+                };
+                let nan_result = match code {
+                    Instruction::LCmp => None,
+                    Instruction::FCmpL | Instruction::DCmpL => Some(-1),
+                    _ => Some(1),
+                };
+                let rhs = Box::new(stack.pop());
+                let lhs = Box::new(stack.pop());
+                stack.push(AST::Compare {
+                    lhs,
+                    rhs,
+                    compare_type,
+                    nan_result,
+                });
+            }
+            Instruction::IfEq { .. }
+            | Instruction::IfNe { .. }
+            | Instruction::IfLt { .. }
+            | Instruction::IfGe { .. }
+            | Instruction::IfGt { .. }
+            | Instruction::IfLe { .. } => {
+                let operator = match code {
+                    Instruction::IfEq { .. } => BinaryOperator::Equal,
+                    Instruction::IfNe { .. } => BinaryOperator::NotEqual,
+                    Instruction::IfLt { .. } => BinaryOperator::Less,
+                    Instruction::IfGe { .. } => BinaryOperator::GreaterEqual,
+                    Instruction::IfGt { .. } => BinaryOperator::Greater,
+                    _ => BinaryOperator::LessEqual,
+                };
+                condition = Some(get_condition(operator, stack.pop()));
+            }
+            Instruction::IfICmpEq { .. }
+            | Instruction::IfICmpNe { .. }
+            | Instruction::IfICmpLt { .. }
+            | Instruction::IfICmpGe { .. }
+            | Instruction::IfICmpGt { .. }
+            | Instruction::IfICmpLe { .. }
+            | Instruction::IfACmpEq { .. }
+            | Instruction::IfACmpNe { .. } => {
+                let operator = match code {
+                    Instruction::IfICmpEq { .. } | Instruction::IfACmpEq { .. } => {
+                        BinaryOperator::Equal
+                    }
+                    Instruction::IfICmpNe { .. } | Instruction::IfACmpNe { .. } => {
+                        BinaryOperator::NotEqual
+                    }
+                    Instruction::IfICmpLt { .. } => BinaryOperator::Less,
+                    Instruction::IfICmpGe { .. } => BinaryOperator::GreaterEqual,
+                    Instruction::IfICmpGt { .. } => BinaryOperator::Greater,
+                    _ => BinaryOperator::LessEqual,
+                };
+                let rhs = Box::new(stack.pop());
+                let lhs = Box::new(stack.pop());
+                condition = Some(AST::Binary { operator, lhs, rhs });
+            }
+            Instruction::IfNull { .. } | Instruction::IfNonNull { .. } => {
+                let operator = match code {
+                    Instruction::IfNull { .. } => BinaryOperator::Equal,
+                    _ => BinaryOperator::NotEqual,
+                };
+                condition = Some(AST::Binary {
+                    operator,
+                    lhs: Box::new(stack.pop()),
+                    rhs: Box::new(AST::ConstNull),
+                });
+            }
+            Instruction::Goto { .. } => {}
+            Instruction::TableSwitch {
+                default,
+                low,
+                offsets,
+                ..
+            } => {
+                let cases = offsets
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, target)| *target != default)
+                    .map(|(i, target)| ((*low as i32).wrapping_add(i as i32), *target as u64))
+                    .collect();
+                switch = Some((stack.pop(), cases, *default as u64));
+            }
+            Instruction::LookupSwitch { default, pairs } => {
+                let cases = pairs
+                    .iter()
+                    .map(|(value, target)| (*value, *target as u64))
+                    .collect();
+                switch = Some((stack.pop(), cases, *default as u64));
+            }
+            Instruction::IStore { index }
+            | Instruction::LStore { index }
+            | Instruction::FStore { index }
+            | Instruction::DStore { index }
+            | Instruction::AStore { index } => {
+                let vartype = match code {
+                    Instruction::LStore { .. } => VarType::Long,
+                    Instruction::FStore { .. } => VarType::Float,
+                    Instruction::DStore { .. } => VarType::Double,
+                    Instruction::AStore { .. } => VarType::Reference,
+                    _ => VarType::Int,
+                };
+                let value = stack.pop();
+                let target = AST::Variable {
+                    index: *index,
+                    vartype,
+                };
+                if !assign_in_expression(&target, &value, is_copied, &mut stack, &mut statements) {
+                    statements.push(AST::Set {
+                        index: *index,
+                        value: Box::new(value),
+                    });
+                }
+            }
+            Instruction::GetStatic { index } => {
+                let field = constant_pool.get_field_entry(*index)?;
+                match get_primitive_class_literal(&field) {
+                    Some(literal) => stack.push(literal),
+                    None => stack.push(AST::Static { field_data: field }),
+                }
+            }
+            Instruction::GetField { index } => {
+                let field_data = constant_pool.get_field_entry(*index)?;
+                let reference = Box::new(stack.pop());
+                stack.push(AST::Field {
+                    field_data,
+                    reference,
+                });
+            }
+            Instruction::PutField { index } => {
+                let field_data = constant_pool.get_field_entry(*index)?;
+                let value = stack.pop();
+                let reference = stack.pop();
+                let target = AST::Field {
+                    field_data: field_data.clone(),
+                    reference: Box::new(reference.clone()),
+                };
+                if !assign_in_expression(&target, &value, is_copied, &mut stack, &mut statements) {
+                    statements.push(AST::SetField {
+                        field_data,
+                        reference: Box::new(reference),
+                        value: Box::new(value),
+                    });
+                }
+            }
+            Instruction::PutStatic { index } => {
+                let field_data = constant_pool.get_field_entry(*index)?;
+                let value = stack.pop();
+                // the copy of the class literal is used instead of its cache
+                if matches!(value, AST::ClassLiteral { .. })
+                    && is_class_cache(
+                        &field_data.name_and_type.name,
+                        &field_data.name_and_type.descriptor,
+                    )
+                {
+                    continue;
+                }
+                let target = AST::Static {
+                    field_data: field_data.clone(),
+                };
+                if !assign_in_expression(&target, &value, is_copied, &mut stack, &mut statements) {
+                    statements.push(AST::SetStatic {
+                        field_data,
+                        value: Box::new(value),
+                    });
+                }
+            }
+            Instruction::ArrayLength => {
+                let reference = Box::new(stack.pop());
+                stack.push(AST::ArrayLength { reference });
+            }
+            Instruction::NewArray { array_type } => {
+                let dimension = stack.pop();
+                stack.push(AST::NewArray {
+                    array_type: descriptors::FieldType::Array {
+                        inner: Box::new(get_array_element_type(array_type)),
+                    },
+                    dimensions: vec![dimension],
+                    elements: Vec::new(),
+                });
+            }
+            Instruction::ANewArray { index } => {
+                let name = constant_pool.get_class_entry(*index)?.name;
+                let element_type = match name.starts_with('[') {
+                    true => descriptors::parse_field(name.chars())?,
+                    false => descriptors::FieldType::Reference { name },
+                };
+                let dimension = stack.pop();
+                stack.push(AST::NewArray {
+                    array_type: descriptors::FieldType::Array {
+                        inner: Box::new(element_type),
+                    },
+                    dimensions: vec![dimension],
+                    elements: Vec::new(),
+                });
+            }
+            Instruction::MultiANewArray { index, dimensions } => {
+                let name = constant_pool.get_class_entry(*index)?.name;
+                let mut dimensions: Vec<AST> = (0..*dimensions).map(|_| stack.pop()).collect();
+                dimensions.reverse();
+                stack.push(AST::NewArray {
+                    array_type: descriptors::parse_field(name.chars())?,
+                    dimensions,
+                    elements: Vec::new(),
+                });
+            }
+            Instruction::IAStore
+            | Instruction::LAStore
+            | Instruction::FAStore
+            | Instruction::DAStore
+            | Instruction::AAStore
+            | Instruction::BAStore
+            | Instruction::CAStore
+            | Instruction::SAStore => {
+                let value = stack.pop();
+                let index = stack.pop();
+                let array = stack.pop();
+                let target = AST::ArrayElement {
+                    array: Box::new(array.clone()),
+                    index: Box::new(index.clone()),
+                };
+                if assign_in_expression(&target, &value, is_copied, &mut stack, &mut statements) {
+                    continue;
+                }
+                // array initializers store their elements in order into a copy of the new array
+                match (stack.values.last_mut(), &array, &index) {
+                    (
+                        Some(AST::NewArray {
+                            dimensions,
+                            elements,
+                            ..
+                        }),
+                        AST::NewArray { .. },
+                        AST::ConstInt { value: index },
+                    ) if *index as usize == elements.len()
+                        && matches!(dimensions.as_slice(), [AST::ConstInt { value: size }]
+                            if *index < *size) =>
+                    {
+                        elements.push(value);
+                    }
+                    _ => statements.push(AST::SetArrayElement {
+                        array: Box::new(array),
+                        index: Box::new(index),
+                        value: Box::new(value),
+                    }),
+                }
+            }
+            Instruction::IALoad
+            | Instruction::LALoad
+            | Instruction::FALoad
+            | Instruction::DALoad
+            | Instruction::AALoad
+            | Instruction::BALoad
+            | Instruction::CALoad
+            | Instruction::SALoad => {
+                let index = Box::new(stack.pop());
+                let array = Box::new(stack.pop());
+                let wide = matches!(code, Instruction::LALoad | Instruction::DALoad);
+                stack.push_sized(AST::ArrayElement { array, index }, wide);
+            }
+            Instruction::IInc { index, value } => {
+                let variable = AST::Variable {
+                    index: *index,
+                    vartype: VarType::Int,
+                };
+                let (operator, amount) = match *value < 0 {
+                    true => (BinaryOperator::Sub, -(*value as i32)),
+                    false => (BinaryOperator::Add, *value as i32),
+                };
+                let value = AST::Binary {
+                    operator,
+                    lhs: Box::new(variable.clone()),
+                    rhs: Box::new(AST::ConstInt { value: amount }),
+                };
+                let is_loaded = matches!(
+                    instructions.peek(),
+                    Some((_, Instruction::ILoad { index: loaded })) if loaded == index
+                );
+                match get_increment(&variable, &value, &stack) {
+                    Some(increment) => stack.replace_top(increment),
+                    // an increment followed by a load is only a prefix increment if there are
+                    // other values on the stack it must be evaluated after
+                    None if amount == 1 && is_loaded && !stack.values.is_empty() => {
+                        instructions.next();
+                        stack.push(AST::Increment {
+                            target: Box::new(variable),
+                            operator,
+                            is_prefix: true,
+                        });
+                    }
+                    None => statements.push(AST::Set {
+                        index: *index,
+                        value: Box::new(value),
+                    }),
+                }
+            }
+            Instruction::LoadConst { index } => {
+                let constant = match constant_pool.get_entry(*index)? {
+                    ConstantPoolInfo::Dynamic { .. } => {
+                        decompile_dynamic_constant(*index, context)?
+                    }
+                    entry => get_constant(entry, constant_pool)?,
+                };
+                match constant {
+                    Some(value) => stack.push(value),
+                    None => {
+                        return Err(DecompilerError::UnsupportedInstr {
+                            instruction: code.clone(),
+                        })
+                    }
+                }
+            }
+            Instruction::IConst { value } => stack.push(AST::ConstInt { value: *value }),
+            Instruction::BIPush { value } => stack.push(AST::ConstInt {
+                value: *value as i32,
+            }),
+            Instruction::SIPush { value } => stack.push(AST::ConstInt {
+                value: *value as i32,
+            }),
+            Instruction::LConst { value } => stack.push(AST::ConstLong { value: *value }),
+            Instruction::FConst { value } => stack.push(AST::ConstFloat { value: *value }),
+            Instruction::DConst { value } => stack.push(AST::ConstDouble { value: *value }),
+            Instruction::IMul
+            | Instruction::LMul
+            | Instruction::FMul
+            | Instruction::DMul
+            | Instruction::IDiv
+            | Instruction::LDiv
+            | Instruction::FDiv
+            | Instruction::DDiv
+            | Instruction::IRem
+            | Instruction::LRem
+            | Instruction::FRem
+            | Instruction::DRem
+            | Instruction::IAdd
+            | Instruction::LAdd
+            | Instruction::FAdd
+            | Instruction::DAdd
+            | Instruction::ISub
+            | Instruction::LSub
+            | Instruction::FSub
+            | Instruction::DSub
+            | Instruction::IShL
+            | Instruction::LShL
+            | Instruction::IShR
+            | Instruction::LShR
+            | Instruction::IUShR
+            | Instruction::LUShR
+            | Instruction::IAnd
+            | Instruction::LAnd
+            | Instruction::IOr
+            | Instruction::LOr
+            | Instruction::IXOr
+            | Instruction::LXOr => {
+                let operator = match code {
+                    Instruction::IMul
+                    | Instruction::LMul
+                    | Instruction::FMul
+                    | Instruction::DMul => BinaryOperator::Mul,
+                    Instruction::IDiv
+                    | Instruction::LDiv
+                    | Instruction::FDiv
+                    | Instruction::DDiv => BinaryOperator::Div,
+                    Instruction::IRem
+                    | Instruction::LRem
+                    | Instruction::FRem
+                    | Instruction::DRem => BinaryOperator::Rem,
+                    Instruction::IAdd
+                    | Instruction::LAdd
+                    | Instruction::FAdd
+                    | Instruction::DAdd => BinaryOperator::Add,
+                    Instruction::ISub
+                    | Instruction::LSub
+                    | Instruction::FSub
+                    | Instruction::DSub => BinaryOperator::Sub,
+                    Instruction::IShL | Instruction::LShL => BinaryOperator::ShL,
+                    Instruction::IShR | Instruction::LShR => BinaryOperator::ShR,
+                    Instruction::IUShR | Instruction::LUShR => BinaryOperator::UShR,
+                    Instruction::IAnd | Instruction::LAnd => BinaryOperator::BitAnd,
+                    Instruction::IOr | Instruction::LOr => BinaryOperator::BitOr,
+                    _ => BinaryOperator::BitXOr,
+                };
+                let rhs = Box::new(stack.pop());
+                let lhs = Box::new(stack.pop());
+                // javac compiles `~x` to `x ^ -1`
+                match (operator, rhs.as_ref()) {
+                    (BinaryOperator::BitXOr, AST::ConstInt { value: -1 })
+                    | (BinaryOperator::BitXOr, AST::ConstLong { value: -1 }) => {
+                        stack.push(AST::Unary {
+                            operator: UnaryOperator::BitNot,
+                            value: lhs,
+                        })
+                    }
+                    _ => stack.push(AST::Binary { operator, lhs, rhs }),
+                }
+            }
+            Instruction::INeg | Instruction::LNeg | Instruction::FNeg | Instruction::DNeg => {
+                let value = Box::new(stack.pop());
+                stack.push(AST::Unary {
+                    operator: UnaryOperator::Neg,
+                    value,
+                });
+            }
+            Instruction::I2l
+            | Instruction::I2f
+            | Instruction::I2d
+            | Instruction::L2i
+            | Instruction::L2f
+            | Instruction::L2d
+            | Instruction::F2i
+            | Instruction::F2l
+            | Instruction::F2d
+            | Instruction::D2i
+            | Instruction::D2l
+            | Instruction::D2f
+            | Instruction::I2b
+            | Instruction::I2c
+            | Instruction::I2s => {
+                let cast_type = match code {
+                    Instruction::L2i | Instruction::F2i | Instruction::D2i => VarType::Int,
+                    Instruction::I2l | Instruction::F2l | Instruction::D2l => VarType::Long,
+                    Instruction::I2f | Instruction::L2f | Instruction::D2f => VarType::Float,
+                    Instruction::I2d | Instruction::L2d | Instruction::F2d => VarType::Double,
+                    Instruction::I2b => VarType::Byte,
+                    Instruction::I2c => VarType::Char,
+                    _ => VarType::Short,
+                };
+                let value = Box::new(stack.pop());
+                stack.push(AST::BasicCast { cast_type, value })
+            }
+            Instruction::InstanceOf { index } => {
+                let class_data = constant_pool.get_class_entry(*index)?;
+                let value = Box::new(stack.pop());
+                let pattern = Pattern::from_class(&class_data, None);
+                stack.push(AST::InstanceOf { value, pattern });
+            }
+            Instruction::CheckCast { index } => {
+                let cast_type = constant_pool.get_class_entry(*index)?;
+                let value = Box::new(stack.pop());
+                stack.push(AST::ClassCast { cast_type, value })
+            }
+            _ => {
+                return Err(DecompilerError::UnsupportedInstr {
+                    instruction: code.clone(),
+                })
+            }
+        }
+    }
+    let exit = match (condition, switch, block.branches.as_slice()) {
+        (None, Some((value, cases, default)), _) => Exit::Switch {
+            value,
+            cases,
+            default,
+        },
+        (Some(condition), None, [target, next]) => Exit::Branch {
+            condition,
+            target: *target,
+            next: *next,
+        },
+        (None, None, [target]) => Exit::Next { target: *target },
+        (None, None, []) => Exit::End,
+        _ => {
+            return Err(DecompilerError::UnstructuredControlFlow {
+                position: block.instructions[0].0,
+            })
+        }
+    };
+    Ok(Node {
+        statements,
+        stack: stack.values,
+        inputs: stack.inputs,
+        exit,
+    })
+}
+
+/// Moves the binding of a type pattern into the `instanceof` check before it. javac casts and
+/// stores the checked value right after the check, which would keep the check from being
+/// combined with the conditions following it.
+fn bind_instanceof_patterns(nodes: &mut BTreeMap<u64, Node>) {
+    let mut predecessors: HashMap<u64, usize> = HashMap::new();
+    for node in nodes.values() {
+        for successor in node.exit.successors() {
+            *predecessors.entry(successor).or_default() += 1;
+        }
+    }
+    let positions: Vec<u64> = nodes.keys().copied().collect();
+    for pos in positions {
+        let (checked, pattern_type, successor) = match &nodes[&pos].exit {
+            Exit::Branch {
+                condition: AST::Binary { operator, lhs, rhs },
+                target,
+                next,
+            } if matches!(rhs.as_ref(), AST::ConstInt { value: 0 }) => {
+                let successor = match operator {
+                    BinaryOperator::Equal => *next,
+                    BinaryOperator::NotEqual => *target,
+                    _ => continue,
+                };
+                match lhs.as_ref() {
+                    AST::InstanceOf {
+                        value,
+                        pattern:
+                            Pattern::Type {
+                                pattern_type,
+                                binding: None,
+                            },
+                    } => match value.as_ref() {
+                        AST::Variable { index, .. } => (*index, pattern_type.clone(), successor),
+                        _ => continue,
+                    },
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+        // the successor is only entered if the check succeeds
+        if successor == pos || predecessors.get(&successor) != Some(&1) {
+            continue;
+        }
+        let binding = match nodes
+            .get(&successor)
+            .and_then(|node| node.statements.first())
+        {
+            Some(AST::Set { index, value }) => match value.as_ref() {
+                AST::ClassCast { value, cast_type }
+                    if is_variable(value, checked)
+                        && Pattern::from_class(cast_type, None).has_type(&pattern_type) =>
+                {
+                    *index
+                }
+                _ => continue,
+            },
+            _ => continue,
+        };
+        if let Some(node) = nodes.get_mut(&successor) {
+            node.statements.remove(0);
+        }
+        if let Some(Node {
+            exit:
+                Exit::Branch {
+                    condition: AST::Binary { lhs, .. },
+                    ..
+                },
+            ..
+        }) = nodes.get_mut(&pos)
+        {
+            if let AST::InstanceOf {
+                pattern: Pattern::Type { binding: bound, .. },
+                ..
+            } = lhs.as_mut()
+            {
+                *bound = Some(binding);
+            }
+        }
+    }
+}
+
+/// Replaces the jumps back to the `switch` of a pattern `switch`, by which javac continues
+/// matching after a guard did not hold, so that the cases can be structured without a loop.
+fn mark_pattern_restarts(nodes: &mut BTreeMap<u64, Node>) {
+    let headers: HashMap<u64, u16> = nodes
+        .iter()
+        .filter_map(|(pos, node)| match &node.exit {
+            Exit::Switch {
+                value: AST::PatternSwitchIndex { restart, .. },
+                ..
+            } => match restart.as_ref() {
+                AST::Variable { index, .. } => Some((*pos, *index)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    for node in nodes.values_mut() {
+        let restart = match node.exit {
+            Exit::Next { target } if node.is_statement_block() => match headers.get(&target) {
+                Some(restart) => *restart,
+                None => continue,
+            },
+            _ => continue,
+        };
+        // the switch is entered with `0`, restarts continue at a later label
+        let label = match node.statements.as_slice() {
+            [AST::Set { index, value }] if *index == restart => match value.as_ref() {
+                AST::ConstInt { value } if *value > 0 => *value,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        node.statements = vec![AST::PatternRestart { label }];
+        node.exit = Exit::End;
+    }
+}
+
+/// Checks if an exception handler rethrows exceptions as a `MatchException`, which javac wraps
+/// around the accessor calls of record patterns.
+fn is_match_exception_handler(
+    instructions: &[(u64, Instruction)],
+    handler_pc: u16,
+    constant_pool: &ConstantPool,
+) -> bool {
+    let index = match get_index_for_pos(instructions, handler_pc) {
+        Some(index) => index,
+        None => return false,
+    };
+    match instructions.get(index..index + 2) {
+        Some([(_, Instruction::AStore { .. }), (_, Instruction::New { index })]) => constant_pool
+            .get_class_entry(*index)
+            .is_ok_and(|class_data| class_data.name == "java/lang/MatchException"),
+        _ => false,
+    }
+}
+
+/// Checks if evaluating an expression might have side effects, so it can't be evaluated once
+/// instead of twice.
+fn has_side_effects(value: &mut AST) -> bool {
+    matches!(
+        value,
+        AST::Call { .. } | AST::StaticCall { .. } | AST::New { .. } | AST::Increment { .. }
+    ) || value.children_mut().into_iter().any(has_side_effects)
+}
+
+/// Turns assignments of a value computed from the assigned variable, field or array element
+/// into compound assignments, e.g. `x = x * 2` into `x *= 2`.
+fn sugar_compound_assignments(value: &mut AST, local_types: &HashMap<u16, descriptors::FieldType>) {
+    for child in value.children_mut() {
+        sugar_compound_assignments(child, local_types);
+    }
+    let (target, assigned) = match value {
+        AST::Set { index, value } => (
+            AST::Variable {
+                index: *index,
+                vartype: VarType::Int,
+            },
+            value,
+        ),
+        AST::SetStatic { field_data, value } => (
+            AST::Static {
+                field_data: field_data.clone(),
+            },
+            value,
+        ),
+        AST::SetArrayElement {
+            array,
+            index,
+            value,
+        } => (
+            AST::ArrayElement {
+                array: array.clone(),
+                index: index.clone(),
+            },
+            value,
+        ),
+        AST::SetField {
+            field_data,
+            reference,
+            value,
+        } => (
+            AST::Field {
+                field_data: field_data.clone(),
+                reference: reference.clone(),
+            },
+            value,
+        ),
+        _ => return,
+    };
+    if has_side_effects(&mut target.clone()) {
+        return;
+    }
+    // the narrowing cast of a compound assignment is implicit
+    let target_type = get_type(&target, local_types);
+    let is_implicit_cast = matches!(assigned.as_ref(), AST::BasicCast { .. })
+        && target_type.is_some()
+        && get_type(assigned, local_types) == target_type;
+    let assigned = match assigned.as_mut() {
+        AST::BasicCast { value, .. } if is_implicit_cast => value,
+        assigned => assigned,
+    };
+    let is_concat = matches!(assigned, AST::Concat { .. });
+    let (operator, operand) = match assigned {
+        AST::Binary { operator, lhs, rhs }
+            if !operator.is_comparison()
+                && !matches!(operator, BinaryOperator::And | BinaryOperator::Or)
+                && is_same_value(lhs, &target) =>
+        {
+            (*operator, std::mem::replace(rhs.as_mut(), AST::ConstNull))
+        }
+        // appending to a string, as long as the rest is still a concatenation of strings
+        AST::Concat { values } if is_same_value(&values[0], &target) => match &values[1..] {
+            [value] => (BinaryOperator::Add, value.clone()),
+            [AST::ConstString { .. }, ..] => (
+                BinaryOperator::Add,
+                AST::Concat {
+                    values: values[1..].to_vec(),
+                },
+            ),
+            _ => return,
+        },
+        _ => return,
+    };
+    *value = AST::CompoundAssign {
+        target: Box::new(target),
+        operator,
+        value: Box::new(operand),
+        is_concat,
+    };
+}
+
+/// The wrapper classes of the primitive types with the method unboxing them and the descriptor
+/// of the primitive type.
+const BOXED_TYPES: [(&str, &str, &str); 8] = [
+    ("java/lang/Boolean", "booleanValue", "Z"),
+    ("java/lang/Byte", "byteValue", "B"),
+    ("java/lang/Character", "charValue", "C"),
+    ("java/lang/Short", "shortValue", "S"),
+    ("java/lang/Integer", "intValue", "I"),
+    ("java/lang/Long", "longValue", "J"),
+    ("java/lang/Float", "floatValue", "F"),
+    ("java/lang/Double", "doubleValue", "D"),
+];
+
+/// Returns the wrapper class and the primitive type of a call boxing a primitive value.
+fn get_boxing(value: &AST) -> Option<(&'static str, &'static str)> {
+    match value {
+        AST::StaticCall { method_data, args } if args.len() == 1 => {
+            BOXED_TYPES.iter().find_map(|(class_name, _, primitive)| {
+                (method_data.class.name == *class_name
+                    && method_data.name_and_type.name == "valueOf"
+                    && method_data.name_and_type.descriptor
+                        == format!("({})L{};", primitive, class_name))
+                .then_some((*class_name, *primitive))
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Returns the wrapper class and the primitive type of a call unboxing a primitive value.
+fn get_unboxing(value: &AST) -> Option<(&'static str, &'static str)> {
+    match value {
+        AST::Call { method_data, .. } => {
+            BOXED_TYPES
+                .iter()
+                .find_map(|(class_name, name, primitive)| {
+                    (method_data.class.name == *class_name
+                        && method_data.name_and_type.name == *name
+                        && method_data.name_and_type.descriptor == format!("(){}", primitive))
+                    .then_some((*class_name, *primitive))
+                })
+        }
+        _ => None,
+    }
+}
+
+/// Checks if a value is boxed to pass it as a supertype of the wrapper class, like `Object` or
+/// `Number`, which includes the erasure of type variables. Constants narrowed to `byte`, `short`
+/// or `char` would be boxed as `Integer` without the call.
+fn is_boxed_to_supertype(value: &AST, target: &descriptors::FieldType) -> bool {
+    let primitive = match get_boxing(value) {
+        Some((_, primitive)) => primitive,
+        None => return false,
+    };
+    let is_supertype = match target {
+        descriptors::FieldType::Reference { name } => match name.as_str() {
+            "java/lang/Object" | "java/lang/Comparable" | "java/io/Serializable" => true,
+            "java/lang/Number" => !matches!(primitive, "Z" | "C"),
+            _ => false,
+        },
+        _ => false,
+    };
+    let is_narrowed = matches!(primitive, "B" | "S" | "C")
+        && matches!(value, AST::StaticCall { args, .. } if matches!(args[0], AST::ConstInt { .. }));
+    is_supertype && !is_narrowed
+}
+
+/// Removes the boxing or unboxing of a value converted to `target`, or boxed to pass it as a
+/// supertype of the wrapper class. Values assigned to local variables have no known type, which
+/// is passed as `None`.
+fn strip_conversion(value: &mut AST, target: Option<&descriptors::FieldType>) {
+    loop {
+        let converted = match (get_boxing(value), get_unboxing(value)) {
+            (Some((class_name, _)), _) => descriptors::FieldType::Reference {
+                name: String::from(class_name),
+            },
+            (_, Some((_, primitive))) => match descriptors::parse_field(primitive.chars()) {
+                Ok(primitive) => primitive,
+                Err(_) => return,
+            },
+            _ => return,
+        };
+        if target
+            .is_some_and(|target| *target != converted && !is_boxed_to_supertype(value, target))
+        {
+            return;
+        }
+        *value = match value {
+            AST::StaticCall { args, .. } => args.remove(0),
+            AST::Call { reference, .. } => std::mem::replace(reference.as_mut(), AST::ConstNull),
+            _ => return,
+        };
+    }
+}
+
+/// Counts the methods with the same name and amount of parameters as the one called, looking
+/// them up in the class declaring it and its supertypes. Returns `None` if one of them is not on
+/// the class path.
+fn count_overloads(
+    method_data: &ConstMethodData,
+    class: &ClassFile,
+    class_path: &ClassPath,
+) -> Option<usize> {
+    let name = &method_data.name_and_type.name;
+    let arity = descriptors::parse_method(method_data.name_and_type.descriptor.chars())
+        .ok()?
+        .0
+        .len();
+    let mut overloads = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut class_names = vec![method_data.class.name.clone()];
+    while let Some(class_name) = class_names.pop() {
+        // the methods of `Object` take objects, which boxed values are passed as either way
+        if class_name == "java/lang/Object" || !visited.insert(class_name.clone()) {
+            continue;
+        }
+        let owner = match class.constant_pool.get_class_entry(class.this_class) {
+            Ok(own_class) if own_class.name == class_name => class,
+            _ => class_path.get(&class_name)?,
+        };
+        let constant_pool = &owner.constant_pool;
+        for method in &owner.methods {
+            let descriptor = constant_pool.get_utf8_entry(method.descriptor_index).ok()?;
+            if constant_pool.get_utf8_entry(method.name_index).ok()? == *name
+                && descriptors::parse_method(descriptor.chars()).ok()?.0.len() == arity
+            {
+                overloads.insert(descriptor);
+            }
+        }
+        // constructors are not inherited
+        if name == "<init>" {
+            break;
+        }
+        for index in owner.interfaces.iter().chain(Some(&owner.super_class)) {
+            if *index != 0 {
+                class_names.push(constant_pool.get_class_entry(*index).ok()?.name);
+            }
+        }
+    }
+    Some(overloads.len())
+}
+
+/// Methods of the JDK taking an object that are overloaded with one taking a primitive value at
+/// the same position, so boxing an argument selects the method, like `List.remove(Object)`.
+const PRIMITIVE_OVERLOADS: [(&str, &str); 6] = [
+    ("java/util/List", "remove"),
+    ("java/util/AbstractList", "remove"),
+    ("java/util/ArrayList", "remove"),
+    ("java/util/LinkedList", "remove"),
+    ("java/util/Vector", "remove"),
+    ("java/util/concurrent/CopyOnWriteArrayList", "remove"),
+];
+
+/// Removes the boxing and unboxing of primitive values javac inserts where the type they are
+/// converted to is known: values assigned, stored, returned or put in arrays, and the arguments
+/// of methods that are not known to be overloaded with the same amount of parameters. The
+/// operands of other operators than `==` and `!=` are unboxed implicitly as well.
+fn remove_boxing(
+    value: &mut AST,
+    return_type: Option<&descriptors::FieldType>,
+    local_types: &HashMap<u16, descriptors::FieldType>,
+    class: &ClassFile,
+    class_path: &ClassPath,
+) {
+    // lambdas return values of their own type
+    let inner_return_type = match value {
+        AST::Lambda { .. } => None,
+        _ => return_type,
+    };
+    for child in value.children_mut() {
+        remove_boxing(child, inner_return_type, local_types, class, class_path);
+    }
+    match value {
+        AST::Set { index, value } => strip_conversion(value, local_types.get(index)),
+        AST::SetField {
+            field_data, value, ..
+        }
+        | AST::SetStatic { field_data, value } => {
+            if let Ok(field_type) =
+                descriptors::parse_field(field_data.name_and_type.descriptor.chars())
+            {
+                strip_conversion(value, Some(&field_type));
+            }
+        }
+        AST::SetArrayElement { array, value, .. } => {
+            if let Some(descriptors::FieldType::Array { inner }) = get_type(array, local_types) {
+                strip_conversion(value, Some(&inner));
+            }
+        }
+        AST::Assign { target, value } => match target.as_ref() {
+            AST::Variable { index, .. } => strip_conversion(value, local_types.get(index)),
+            target => {
+                if let Some(target_type) = get_type(target, local_types) {
+                    strip_conversion(value, Some(&target_type));
+                }
+            }
+        },
+        AST::Return { value } => {
+            if let Some(return_type) = return_type {
+                strip_conversion(value, Some(return_type));
+            }
+        }
+        AST::Call {
+            method_data, args, ..
+        }
+        | AST::StaticCall { method_data, args }
+        | AST::New { method_data, args } => {
+            // the overload javac chose can depend on the conversion
+            let is_overloaded = match count_overloads(method_data, class, class_path) {
+                Some(count) => count > 1,
+                None => PRIMITIVE_OVERLOADS.contains(&(
+                    method_data.class.name.as_str(),
+                    method_data.name_and_type.name.as_str(),
+                )),
+            };
+            if is_overloaded {
+                return;
+            }
+            let params =
+                match descriptors::parse_method(method_data.name_and_type.descriptor.chars()) {
+                    Ok((params, _)) => params,
+                    Err(_) => return,
+                };
+            for (arg, param) in args.iter_mut().zip(&params) {
+                // constants are only narrowed to `byte`, `short` and `char` in assignments
+                let is_narrowed = matches!(get_boxing(arg), Some((_, "B" | "S" | "C")));
+                if !is_narrowed {
+                    strip_conversion(arg, Some(param));
+                }
+            }
+        }
+        AST::NewArray {
+            array_type: descriptors::FieldType::Array { inner },
+            elements,
+            ..
+        } => {
+            for element in elements {
+                strip_conversion(element, Some(inner));
+            }
+        }
+        AST::Binary { operator, lhs, rhs }
+            if !matches!(operator, BinaryOperator::Equal | BinaryOperator::NotEqual) =>
+        {
+            strip_unboxing(lhs);
+            strip_unboxing(rhs);
+        }
+        AST::CompoundAssign { value, .. } => strip_unboxing(value),
+        _ => {}
+    }
+}
+
+/// Removes the unboxing of an operand, which the operator unboxes implicitly.
+fn strip_unboxing(operand: &mut AST) {
+    if let Some((_, primitive)) = get_unboxing(operand) {
+        if let Ok(primitive) = descriptors::parse_field(primitive.chars()) {
+            strip_conversion(operand, Some(&primitive));
+        }
+    }
+}
+
+/// Checks if a method is declared with a variable number of arguments, looking it up in the
+/// class being decompiled and on the class path.
+fn is_varargs_method(
+    method_data: &ConstMethodData,
+    class: &ClassFile,
+    class_path: &ClassPath,
+) -> bool {
+    let mut class_name = method_data.class.name.clone();
+    // the method may be declared in a superclass of the class it is called on
+    loop {
+        let owner = match class.constant_pool.get_class_entry(class.this_class) {
+            Ok(own_class) if own_class.name == class_name => class,
+            _ => match class_path.get(&class_name) {
+                Some(owner) => owner,
+                None => return false,
+            },
+        };
+        let constant_pool = &owner.constant_pool;
+        let method = owner.methods.iter().find(|method| {
+            constant_pool.get_utf8_entry(method.name_index).ok()
+                == Some(method_data.name_and_type.name.clone())
+                && constant_pool.get_utf8_entry(method.descriptor_index).ok()
+                    == Some(method_data.name_and_type.descriptor.clone())
+        });
+        if let Some(method) = method {
+            return method.access_flags.acc_varargs;
+        }
+        class_name = match owner.super_class {
+            0 => return false,
+            index => match constant_pool.get_class_entry(index) {
+                Ok(super_class) => super_class.name,
+                Err(_) => return false,
+            },
+        };
+    }
+}
+
+/// Passes the elements of the array javac creates for the variable arguments of a method as
+/// separate arguments again.
+fn collapse_varargs(value: &mut AST, class: &ClassFile, class_path: &ClassPath) {
+    for child in value.children_mut() {
+        collapse_varargs(child, class, class_path);
+    }
+    let (method_data, args) = match value {
+        AST::Call {
+            method_data, args, ..
+        }
+        | AST::StaticCall { method_data, args }
+        | AST::New { method_data, args } => (method_data, args),
+        _ => return,
+    };
+    match args.last() {
+        Some(AST::NewArray {
+            dimensions,
+            elements,
+            ..
+        }) if matches!(dimensions.as_slice(), [AST::ConstInt { value }]
+            if *value as usize == elements.len()) =>
+        {
+            // a single `null` would be passed as the array itself
+            if matches!(elements.as_slice(), [AST::ConstNull]) {
+                return;
+            }
+        }
+        _ => return,
+    }
+    if !is_varargs_method(method_data, class, class_path) {
+        return;
+    }
+    if let Some(AST::NewArray { elements, .. }) = args.pop() {
+        args.extend(elements);
+    }
+}
+
+fn decompile_code(
+    method: &MethodInfo,
+    code: &[u8],
+    class: &ClassFile,
+    class_path: &ClassPath,
+) -> Result<Vec<AST>, DecompilerError> {
+    let constant_pool = &class.constant_pool;
+    let context = CodeContext {
+        class,
+        class_path,
+        max_locals: get_max_locals(method),
+        is_constructor: constant_pool.get_utf8_entry(method.name_index)? == "<init>",
+    };
+    let mut instructions: Vec<(u64, Instruction)> = disassembler::disassemble(code.to_vec())?;
+    let mut exception_table = get_exception_table(method).to_vec();
+    subroutines::inline_subroutines(&mut instructions, &mut exception_table)?;
+    exception_table.retain(|exception| {
+        !is_match_exception_handler(&instructions, exception.handler_pc, constant_pool)
+    });
+    let control_flow_graph = gen_control_flow_graph(&instructions, &exception_table);
+    let mut nodes = BTreeMap::new();
+    for (pos, block) in &control_flow_graph {
+        nodes.insert(*pos, decompile_block(block, &context)?);
+    }
+    bind_instanceof_patterns(&mut nodes);
+    mark_pattern_restarts(&mut nodes);
+    let try_regions = get_try_regions(&exception_table, constant_pool)?;
+    let mut statements = structure::structure(nodes, try_regions)?;
+
+    let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+    let (params, return_type) = descriptors::parse_method(descriptor.chars())?;
+    let mut local_types = HashMap::new();
+    let mut index = if method.access_flags.acc_static { 0 } else { 1 };
+    for param in params {
+        let size = param.size();
+        local_types.insert(index, param);
+        index += size;
+    }
+    sugar_for_each(&mut statements, &local_types);
+    sugar_synchronized(&mut statements);
+    sugar_finally(&mut statements);
+    sugar_try_with_resources(&mut statements);
+    sugar_enum_switch(&mut statements, class_path);
+    sugar_string_switch(&mut statements);
+    sugar_pattern_switch(&mut statements, class_path);
+    for statement in &mut statements {
+        apply_types(statement, &return_type, &local_types);
+        sugar_compound_assignments(statement, &local_types);
+        remove_boxing(
+            statement,
+            Some(&return_type),
+            &local_types,
+            class,
+            class_path,
+        );
+        collapse_varargs(statement, class, class_path);
+        inline_class_caches(statement);
+    }
+    sugar_assert(&mut statements);
     Ok(statements)
 }
 
-fn get_class_name(raw_name: &str) -> String {
-    String::from(raw_name)
+fn get_code(method: &MethodInfo) -> Option<&Vec<u8>> {
+    method.attributes.iter().find_map(|attrib| match attrib {
+        AttributeInfo::Code { code, .. } => Some(code),
+        _ => None,
+    })
+}
+
+fn get_exception_table(method: &MethodInfo) -> &[ExceptionTableInfo] {
+    method
+        .attributes
+        .iter()
+        .find_map(|attrib| match attrib {
+            AttributeInfo::Code {
+                exception_table, ..
+            } => Some(exception_table.as_slice()),
+            _ => None,
+        })
+        .unwrap_or(&[])
+}
+
+/// Groups the entries of an exception table into the regions of code they protect. The entries
+/// of one handler can cover several ranges, which are left out for inlined `finally` code.
+fn get_try_regions(
+    exception_table: &[ExceptionTableInfo],
+    constant_pool: &ConstantPool,
+) -> Result<Vec<TryRegion>, DecompilerError> {
+    let mut handlers: Vec<(u64, u64, Handler)> = Vec::new();
+    for exception in exception_table {
+        // javac protects the release of a monitor in a handler by the handler itself
+        if (exception.start_pc..exception.end_pc).contains(&exception.handler_pc) {
+            continue;
+        }
+        let class_data = if exception.catch_type == 0 {
+            ConstClassData {
+                name: String::from("java/lang/Throwable"),
+            }
+        } else {
+            constant_pool.get_class_entry(exception.catch_type)?
+        };
+        let (start, end) = (exception.start_pc as u64, exception.end_pc as u64);
+        let position = exception.handler_pc as u64;
+        match handlers
+            .iter_mut()
+            .find(|(_, _, handler)| handler.position == position)
+        {
+            Some((handler_start, handler_end, handler)) => {
+                *handler_start = start.min(*handler_start);
+                *handler_end = end.max(*handler_end);
+                if !handler
+                    .classes
+                    .iter()
+                    .any(|class| class.name == class_data.name)
+                {
+                    handler.classes.push(class_data);
+                }
+            }
+            None => handlers.push((
+                start,
+                end,
+                Handler {
+                    position,
+                    classes: vec![class_data],
+                },
+            )),
+        }
+    }
+    let mut try_regions: Vec<TryRegion> = Vec::new();
+    for (start, end, handler) in handlers {
+        match try_regions
+            .iter_mut()
+            .find(|try_region| try_region.start == start && try_region.end == end)
+        {
+            Some(try_region) => try_region.handlers.push(handler),
+            None => try_regions.push(TryRegion {
+                start,
+                end,
+                handlers: vec![handler],
+            }),
+        }
+    }
+    Ok(try_regions)
+}
+
+fn get_max_locals(method: &MethodInfo) -> u16 {
+    method
+        .attributes
+        .iter()
+        .find_map(|attrib| match attrib {
+            AttributeInfo::Code { max_locals, .. } => Some(*max_locals),
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+fn is_lambda_method(method: &MethodInfo, constant_pool: &ConstantPool) -> bool {
+    method.access_flags.acc_synthetic
+        && constant_pool
+            .get_utf8_entry(method.name_index)
+            .is_ok_and(|name| name.starts_with("lambda$"))
+}
+
+fn is_field_of(
+    field_data: &ConstFieldData,
+    class_name: &str,
+    field: &FieldInfo,
+    class: &ClassFile,
+) -> bool {
+    field_data.class.name == class_name
+        && class
+            .constant_pool
+            .get_utf8_entry(field.name_index)
+            .ok()
+            .as_ref()
+            == Some(&field_data.name_and_type.name)
+        && class
+            .constant_pool
+            .get_utf8_entry(field.descriptor_index)
+            .ok()
+            .as_ref()
+            == Some(&field_data.name_and_type.descriptor)
+}
+
+/// Checks if an expression may be moved from a constructor or static initializer
+/// into a field declaration, i.e. it only uses `this` (if not static) and no other locals,
+/// which includes the parameters.
+fn is_initializer_expression(value: &AST, is_static: bool) -> bool {
+    uses_only_this(&mut value.clone(), is_static)
+}
+
+fn uses_only_this(value: &mut AST, is_static: bool) -> bool {
+    match value {
+        AST::Variable { index, .. } => *index == 0 && !is_static,
+        // lambda bodies use their own locals
+        AST::Lambda { captured, .. } => captured
+            .iter_mut()
+            .all(|value| uses_only_this(value, is_static)),
+        // statements, like the cases of `switch` expressions, may declare locals
+        AST::Set { .. } | AST::SwitchExpression { .. } | AST::StackInput { .. } => false,
+        value => value
+            .children_mut()
+            .into_iter()
+            .all(|child| uses_only_this(child, is_static)),
+    }
+}
+
+/// Collects the leading field assignments of an initializer body that can be lifted into
+/// field declarations. Returns the index of the assigned field and the assigned value. The
+/// assignments following a statement that is not lifted stay in place, as they may depend on
+/// the effects of that statement.
+fn get_field_initializers(
+    statements: &[AST],
+    class: &ClassFile,
+    class_name: &str,
+    is_static: bool,
+) -> Vec<(usize, AST)> {
+    let mut initializers: Vec<(usize, AST)> = Vec::new();
+    for statement in statements {
+        let (field_data, value) = match statement {
+            AST::SetField {
+                field_data,
+                reference,
+                value,
+            } if !is_static => match reference.as_ref() {
+                AST::Variable { index: 0, .. } => (field_data, value),
+                _ => break,
+            },
+            AST::SetStatic { field_data, value } if is_static => (field_data, value),
+            _ => break,
+        };
+        let field_index = class.fields.iter().position(|field| {
+            field.access_flags.acc_static == is_static
+                && is_field_of(field_data, class_name, field, class)
+        });
+        match field_index {
+            // fields must be initialized in declaration order to keep evaluation order intact
+            Some(field_index)
+                if initializers
+                    .last()
+                    .is_none_or(|(last, _)| *last < field_index)
+                    && is_initializer_expression(value, is_static) =>
+            {
+                initializers.push((field_index, value.as_ref().clone()))
+            }
+            _ => break,
+        }
+    }
+    initializers
+}
+
+fn is_super_constructor_call(statement: &AST, class_name: &str) -> bool {
+    match statement {
+        AST::Call {
+            method_data,
+            reference,
+            ..
+        } => {
+            method_data.name_and_type.name == "<init>"
+                && method_data.class.name != class_name
+                && matches!(reference.as_ref(), AST::Variable { index: 0, .. })
+        }
+        _ => false,
+    }
+}
+
+fn get_method_modifiers(method: &MethodInfo) -> String {
+    let flags = &method.access_flags;
+    let mut modifiers = String::new();
+    for (is_set, modifier) in &[
+        (flags.acc_public, "public "),
+        (flags.acc_private, "private "),
+        (flags.acc_protected, "protected "),
+        (flags.acc_static, "static "),
+        (flags.acc_final, "final "),
+        (flags.acc_synchronized, "synchronized "),
+        (flags.acc_native, "native "),
+        (flags.acc_abstract, "abstract "),
+        (flags.acc_strict, "strictfp "),
+    ] {
+        if *is_set {
+            modifiers.push_str(modifier);
+        }
+    }
+    modifiers
+}
+
+fn get_field_modifiers(field: &FieldInfo) -> String {
+    let flags = &field.access_flags;
+    let mut modifiers = String::new();
+    for (is_set, modifier) in &[
+        (flags.acc_public, "public "),
+        (flags.acc_private, "private "),
+        (flags.acc_protected, "protected "),
+        (flags.acc_static, "static "),
+        (flags.acc_final, "final "),
+        (flags.acc_transient, "transient "),
+        (flags.acc_volatile, "volatile "),
+    ] {
+        if *is_set {
+            modifiers.push_str(modifier);
+        }
+    }
+    modifiers
+}
+
+fn get_constant_value(
+    field: &FieldInfo,
+    field_type: &descriptors::FieldType,
+    constant_pool: &ConstantPool,
+    names: &NameResolver,
+) -> Result<Option<String>, DecompilerError> {
+    for attrib in &field.attributes {
+        if let AttributeInfo::ConstantValue {
+            constant_value_index,
+        } = attrib
+        {
+            let value = get_constant(
+                constant_pool.get_entry(*constant_value_index)?,
+                constant_pool,
+            )?
+            .ok_or(ClassFileError::InvalidCPEntry)?;
+            let value = with_type(value, field_type).to_java(true, names);
+            return Ok(Some(value));
+        }
+    }
+    Ok(None)
+}
+
+fn get_signature(
+    attributes: &[AttributeInfo],
+    constant_pool: &ConstantPool,
+) -> Result<Option<String>, DecompilerError> {
+    for attrib in attributes {
+        if let AttributeInfo::Signature { signature_index } = attrib {
+            return Ok(Some(constant_pool.get_utf8_entry(*signature_index)?));
+        }
+    }
+    Ok(None)
+}
+
+fn get_exceptions(
+    attributes: &[AttributeInfo],
+    constant_pool: &ConstantPool,
+) -> Result<Vec<TypeSignature>, DecompilerError> {
+    let mut exceptions = Vec::new();
+    for attrib in attributes {
+        if let AttributeInfo::Exceptions {
+            exception_index_table,
+        } = attrib
+        {
+            for index in exception_index_table {
+                let name = constant_pool.get_class_entry(*index)?.name;
+                exceptions.push(TypeSignature::from(descriptors::FieldType::Reference {
+                    name,
+                }));
+            }
+        }
+    }
+    Ok(exceptions)
+}
+
+fn decompile_field(
+    field: &FieldInfo,
+    initializer: Option<&AST>,
+    constant_pool: &ConstantPool,
+    names: &NameResolver,
+) -> Result<String, DecompilerError> {
+    let name = constant_pool.get_utf8_entry(field.name_index)?;
+    let descriptor = constant_pool.get_utf8_entry(field.descriptor_index)?;
+    let field_type = descriptors::parse_field(descriptor.chars())?;
+    let type_signature = match get_signature(&field.attributes, constant_pool)? {
+        Some(signature) => signatures::parse_field(signature.chars())?,
+        None => TypeSignature::from(field_type.clone()),
+    };
+    let value = match initializer {
+        Some(initializer) => Some(initializer.to_java(field.access_flags.acc_static, names)),
+        None => get_constant_value(field, &field_type, constant_pool, names)?,
+    };
+    Ok(format!(
+        "{}{} {}{};",
+        get_field_modifiers(field),
+        type_signature.to_java(names),
+        name,
+        value
+            .map(|value| format!(" = {}", value))
+            .unwrap_or_default()
+    ))
+}
+
+fn write_statements(
+    output: &mut String,
+    statements: &[AST],
+    is_static: bool,
+    indent: usize,
+    names: &NameResolver,
+) {
+    for statement in statements {
+        let mut java = statement.to_java(is_static, names);
+        if statement.is_expression_statement() {
+            java.push(';');
+        }
+        for line in java.lines() {
+            if !line.is_empty() {
+                output.push_str(&" ".repeat(indent));
+                output.push_str(line);
+            }
+            output.push('\n');
+        }
+    }
+}
+
+/// Writes the elements of an array initializer, leaving out the types of nested initializers.
+fn array_initializer_to_java(elements: &[AST], is_static: bool, names: &NameResolver) -> String {
+    let elements: Vec<String> = elements
+        .iter()
+        .map(|element| match element {
+            AST::NewArray { elements, .. } if !elements.is_empty() => {
+                array_initializer_to_java(elements, is_static, names)
+            }
+            element => element.to_java(is_static, names),
+        })
+        .collect();
+    format!("{{{}}}", elements.join(", "))
+}
+
+fn block_to_java(statements: &[AST], is_static: bool, names: &NameResolver) -> String {
+    let mut output = String::new();
+    write_statements(&mut output, statements, is_static, 4, names);
+    output
+}
+
+fn decompile_method(
+    output: &mut String,
+    decompiled: &DecompiledClass,
+    method_index: usize,
+    body: Option<&Result<Vec<AST>, DecompilerError>>,
+    synthetic_params: &[usize],
+    names: &NameResolver,
+) -> Result<(), DecompilerError> {
+    let method = &decompiled.class.methods[method_index];
+    let constant_pool = &decompiled.class.constant_pool;
+    let class_name = &decompiled.name;
+    let local_offset = decompiled
+        .local_offsets
+        .get(&method_index)
+        .copied()
+        .unwrap_or_default();
+    let name = constant_pool.get_utf8_entry(method.name_index)?;
+    let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+    let (params, return_type) = descriptors::parse_method(descriptor.chars())?;
+    let is_static = method.access_flags.acc_static;
+
+    if name == "<clinit>" {
+        output.push_str("    static {\n");
+    } else {
+        let signature = match get_signature(&method.attributes, constant_pool)? {
+            Some(signature) => signatures::parse_method(signature.chars())?,
+            None => MethodSignature {
+                type_parameters: Vec::new(),
+                params: params.iter().cloned().map(TypeSignature::from).collect(),
+                return_type: TypeSignature::from(return_type),
+                throws: Vec::new(),
+            },
+        };
+        // synthetic parameters (e.g. of inner class constructors) are missing in the signature
+        let skipped_params = params.len().saturating_sub(signature.params.len());
+        let mut index = if is_static { 0 } else { 1 };
+        let mut param_strings = Vec::with_capacity(params.len());
+        for (i, param) in params.iter().enumerate() {
+            // e.g. the name and ordinal of enum constants, which are passed implicitly
+            if synthetic_params.contains(&i) {
+                index += param.size();
+                continue;
+            }
+            let mut param_type = match i.checked_sub(skipped_params) {
+                Some(i) => signature.params[i].to_java(names),
+                None => param.to_java(names),
+            };
+            if method.access_flags.acc_varargs && i + 1 == params.len() {
+                if let Some(element_type) = param_type.strip_suffix("[]") {
+                    param_type = format!("{}...", element_type);
+                }
+            }
+            param_strings.push(format!("{} var{}", param_type, index + local_offset));
+            index += param.size();
+        }
+        let name = if name == "<init>" {
+            names.get_simple_name(class_name)
+        } else {
+            format!("{} {}", signature.return_type.to_java(names), name)
+        };
+        let throws = if signature.throws.is_empty() {
+            get_exceptions(&method.attributes, constant_pool)?
+        } else {
+            signature.throws
+        };
+        let type_parameters =
+            signatures::type_parameters_to_java(&signature.type_parameters, names);
+        output.push_str(&format!(
+            "    {}{}{}({})",
+            get_method_modifiers(method),
+            if type_parameters.is_empty() {
+                type_parameters
+            } else {
+                type_parameters + " "
+            },
+            name,
+            param_strings.join(", ")
+        ));
+        if !throws.is_empty() {
+            let throws: Vec<String> = throws
+                .iter()
+                .map(|exception| exception.to_java(names))
+                .collect();
+            output.push_str(&format!(" throws {}", throws.join(", ")));
+        }
+        if body.is_none() {
+            output.push_str(";\n");
+            return Ok(());
+        }
+        output.push_str(" {\n");
+    }
+    match body {
+        Some(Ok(statements)) => {
+            let statements = match statements.split_last() {
+                Some((AST::VoidReturn, rest)) => rest,
+                _ => statements,
+            };
+            // the call of the constructor of the superclass without arguments is implicit
+            let statements = match statements.split_first() {
+                Some((AST::Call { args, .. }, rest))
+                    if name == "<init>"
+                        && args.is_empty()
+                        && is_super_constructor_call(&statements[0], class_name) =>
+                {
+                    rest
+                }
+                _ => statements,
+            };
+            write_statements(output, statements, is_static, 8, names);
+        }
+        Some(Err(error)) => {
+            output.push_str(&format!("        // could not decompile: {}\n", error))
+        }
+        None => {}
+    }
+    output.push_str("    }\n");
+    Ok(())
+}
+
+/// Writes the canonical constructor declared in a record, either as a compact constructor or
+/// with its parameters named after the components.
+fn write_canonical_constructor(
+    output: &mut String,
+    method: &MethodInfo,
+    statements: &[AST],
+    components: &[RecordComponent],
+    is_compact: bool,
+    class_name: &str,
+    names: &NameResolver,
+) {
+    let name = names.get_simple_name(class_name);
+    if is_compact {
+        output.push_str(&format!(
+            "    {}{} {{\n",
+            get_method_modifiers(method),
+            name
+        ));
+    } else {
+        let params: Vec<String> = components
+            .iter()
+            .enumerate()
+            .map(|(i, component)| {
+                let mut param_type = component.type_signature.to_java(names);
+                if method.access_flags.acc_varargs && i + 1 == components.len() {
+                    if let Some(element_type) = param_type.strip_suffix("[]") {
+                        param_type = format!("{}...", element_type);
+                    }
+                }
+                format!("{} {}", param_type, component.name)
+            })
+            .collect();
+        output.push_str(&format!(
+            "    {}{}({}) {{\n",
+            get_method_modifiers(method),
+            name,
+            params.join(", ")
+        ));
+    }
+    write_statements(output, statements, false, 8, names);
+    output.push_str("    }\n");
+}
+
+/// Checks if a class is an enum declaration. The class bodies of enum constants are marked as
+/// enums as well, but extend the enum instead of `Enum`.
+fn is_enum_class(class: &ClassFile) -> Result<bool, DecompilerError> {
+    Ok(class.access_flags.acc_enum
+        && class.super_class != 0
+        && class.constant_pool.get_class_entry(class.super_class)?.name == "java/lang/Enum")
+}
+
+/// Checks if any direct supertype of a class on the class path is `sealed`, which a subclass
+/// that is neither `final` nor `sealed` has to be declared `non-sealed` for.
+fn has_sealed_supertype(
+    class: &ClassFile,
+    class_path: &ClassPath,
+) -> Result<bool, DecompilerError> {
+    let mut supertypes = class.interfaces.clone();
+    if class.super_class != 0 {
+        supertypes.push(class.super_class);
+    }
+    for index in supertypes {
+        let name = class.constant_pool.get_class_entry(index)?.name;
+        if let Some(supertype) = class_path.get(&name) {
+            if get_permitted_subclasses(supertype).is_some() {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+fn get_class_signature(class: &ClassFile) -> Result<ClassSignature, DecompilerError> {
+    if let Some(signature) = get_signature(&class.attributes, &class.constant_pool)? {
+        return Ok(signatures::parse_class(signature.chars())?);
+    }
+    let super_name = if class.super_class != 0 {
+        class.constant_pool.get_class_entry(class.super_class)?.name
+    } else {
+        String::from("java/lang/Object")
+    };
+    let interfaces = class
+        .interfaces
+        .iter()
+        .map(|index| {
+            let name = class.constant_pool.get_class_entry(*index)?.name;
+            Ok(TypeSignature::from(descriptors::FieldType::Reference {
+                name,
+            }))
+        })
+        .collect::<Result<Vec<TypeSignature>, DecompilerError>>()?;
+    Ok(ClassSignature {
+        type_parameters: Vec::new(),
+        super_class: TypeSignature::from(descriptors::FieldType::Reference { name: super_name }),
+        interfaces,
+    })
+}
+
+fn get_class_header(
+    decompiled: &DecompiledClass,
+    names: &NameResolver,
+) -> Result<String, DecompilerError> {
+    let DecompiledClass {
+        class,
+        name: class_name,
+        ..
+    } = decompiled;
+    let flags = &class.access_flags;
+    let is_enum = is_enum_class(class)?;
+    let components = decompiled.components.as_ref();
+    // enums and records can not be extended other than by the class bodies of enum constants
+    let permitted_subclasses = match is_enum || components.is_some() {
+        true => None,
+        false => get_permitted_subclasses(class),
+    };
+    let mut header = String::new();
+    // the access of nested classes is only recorded in the `InnerClasses` attribute
+    let inner_flags = get_own_inner_class_info(class)?.map(|info| &info.inner_class_access_flags);
+    match inner_flags {
+        Some(inner_flags) if inner_flags.acc_public => header.push_str("public "),
+        Some(inner_flags) if inner_flags.acc_protected => header.push_str("protected "),
+        Some(inner_flags) if inner_flags.acc_private => header.push_str("private "),
+        Some(_) => {}
+        None if flags.acc_public => header.push_str("public "),
+        None => {}
+    }
+    // enums are implicitly final, or abstract if their constants have bodies
+    if flags.acc_abstract && !flags.acc_interface && !is_enum {
+        header.push_str("abstract ");
+    }
+    // nested enums, records and interfaces and all local classes are implicitly static
+    let inner_info = get_own_inner_class_info(class)?;
+    if inner_info.is_some_and(|info| {
+        info.inner_class_access_flags.acc_static && info.outer_class_info_index != 0
+    }) && !is_enum
+        && components.is_none()
+        && !flags.acc_interface
+    {
+        header.push_str("static ");
+    }
+    if flags.acc_final && !is_enum && components.is_none() {
+        header.push_str("final ");
+    }
+    if permitted_subclasses.is_some() {
+        header.push_str("sealed ");
+    } else if !flags.acc_final
+        && !is_enum
+        && !flags.acc_annotation
+        && has_sealed_supertype(class, decompiled.class_path)?
+    {
+        header.push_str("non-sealed ");
+    }
+    header.push_str(if flags.acc_annotation {
+        "@interface "
+    } else if is_enum {
+        "enum "
+    } else if components.is_some() {
+        "record "
+    } else if flags.acc_interface {
+        "interface "
+    } else {
+        "class "
+    });
+    header.push_str(&names.get_simple_name(class_name));
+
+    let signature = get_class_signature(class)?;
+    header.push_str(&signatures::type_parameters_to_java(
+        &signature.type_parameters,
+        names,
+    ));
+    if let Some(components) = components {
+        let components: Vec<String> = components
+            .iter()
+            .map(|component| {
+                format!(
+                    "{} {}",
+                    component.type_signature.to_java(names),
+                    component.name
+                )
+            })
+            .collect();
+        header.push_str(&format!("({})", components.join(", ")));
+    }
+    if !signature.super_class.is_object() && !is_enum && components.is_none() {
+        header.push_str(&format!(
+            " extends {}",
+            signature.super_class.to_java(names)
+        ));
+    }
+    let interfaces: Vec<String> = signature
+        .interfaces
+        .iter()
+        .map(|interface| interface.to_java(names))
+        .collect();
+    if !interfaces.is_empty() && !flags.acc_annotation {
+        header.push_str(if flags.acc_interface {
+            " extends "
+        } else {
+            " implements "
+        });
+        header.push_str(&interfaces.join(", "));
+    }
+    if let Some(permitted_subclasses) = permitted_subclasses {
+        let permitted_subclasses = permitted_subclasses
+            .iter()
+            .map(|index| {
+                let name = class.constant_pool.get_class_entry(*index)?.name;
+                Ok(TypeSignature::from(descriptors::FieldType::Reference { name }).to_java(names))
+            })
+            .collect::<Result<Vec<String>, DecompilerError>>()?;
+        header.push_str(&format!(" permits {}", permitted_subclasses.join(", ")));
+    }
+    Ok(header)
+}
+
+/// Moves the field initializers javac placed into `<clinit>` and the constructors back into
+/// the field declarations. Instance initializers are only lifted if every constructor calling
+/// `super(...)` starts with the same assignments.
+fn lift_field_initializers(
+    class: &ClassFile,
+    class_name: &str,
+    bodies: &mut [Option<Result<Vec<AST>, DecompilerError>>],
+) -> Result<HashMap<usize, AST>, DecompilerError> {
+    let mut initializers = HashMap::new();
+    let mut constructors: Vec<(usize, Vec<(usize, AST)>)> = Vec::new();
+    let mut can_lift_instance = true;
+    for (method_index, method) in class.methods.iter().enumerate() {
+        let name = class.constant_pool.get_utf8_entry(method.name_index)?;
+        match (name.as_str(), &mut bodies[method_index]) {
+            ("<clinit>", Some(Ok(statements))) => {
+                let lifted = get_field_initializers(statements, class, class_name, true);
+                statements.drain(..lifted.len());
+                initializers.extend(lifted);
+            }
+            ("<init>", Some(Ok(statements))) => match statements.first() {
+                Some(statement) if is_super_constructor_call(statement, class_name) => {
+                    let lifted = get_field_initializers(&statements[1..], class, class_name, false);
+                    constructors.push((method_index, lifted));
+                }
+                Some(AST::Call { method_data, .. })
+                    if method_data.name_and_type.name == "<init>"
+                        && method_data.class.name == class_name => {}
+                _ => can_lift_instance = false,
+            },
+            ("<init>", _) => can_lift_instance = false,
+            _ => {}
+        }
+    }
+    if !can_lift_instance || constructors.is_empty() {
+        return Ok(initializers);
+    }
+
+    // javac copies the initializers into every constructor, so only the common prefix is lifted
+    let names = NameResolver::new(class_name);
+    let to_java = |(index, value): &(usize, AST)| (*index, value.to_java(false, &names));
+    let (_, first) = &constructors[0];
+    let common = constructors
+        .iter()
+        .map(|(_, lifted)| {
+            lifted
+                .iter()
+                .zip(first)
+                .take_while(|(a, b)| to_java(a) == to_java(b))
+                .count()
+        })
+        .min()
+        .unwrap_or(0);
+    initializers.extend(first.iter().take(common).cloned());
+    for (method_index, _) in &constructors {
+        if let Some(Ok(statements)) = &mut bodies[*method_index] {
+            statements.drain(1..=common);
+        }
+    }
+    Ok(initializers)
+}
+
+/// A constant of an enum declaration.
+struct EnumConstant<'a> {
+    name: String,
+    args: Vec<AST>,
+    /// The anonymous class javac generates for a constant with a class body.
+    body: Option<DecompiledClass<'a>>,
+}
+
+/// A component of a record, `descriptor` is the descriptor of its field and accessor.
+struct RecordComponent {
+    name: String,
+    descriptor: String,
+    type_signature: TypeSignature,
+}
+
+/// A class with the bodies of its methods decompiled, before it is written as source code.
+struct DecompiledClass<'a> {
+    class: &'a ClassFile,
+    class_path: &'a ClassPath,
+    name: String,
+    bodies: Vec<Option<Result<Vec<AST>, DecompilerError>>>,
+    initializers: HashMap<usize, AST>,
+    constants: Vec<EnumConstant<'a>>,
+    components: Option<Vec<RecordComponent>>,
+    /// The index of the canonical constructor of a record if it is the one javac generates.
+    canonical_constructor: Option<usize>,
+    /// The index of the canonical constructor of a record if it is declared, and whether it is a
+    /// compact constructor.
+    declared_canonical_constructor: Option<(usize, bool)>,
+    /// The positions of the parameters passing the enclosing instance and the captured
+    /// variables, by the index of the constructor.
+    synthetic_params: HashMap<usize, Vec<usize>>,
+    /// The positions of the parameters passing captured variables and the names of the fields
+    /// they are stored in, by the index of the constructor.
+    captured_params: HashMap<usize, Vec<(usize, String)>>,
+    /// The amount the locals of a method are renumbered by, so that they do not hide the
+    /// captured variables, by the index of the method.
+    local_offsets: HashMap<usize, u16>,
+    member_classes: Vec<DecompiledClass<'a>>,
+    /// The local and anonymous classes declared in the methods of the class.
+    local_classes: Vec<DecompiledClass<'a>>,
+}
+
+fn get_permitted_subclasses(class: &ClassFile) -> Option<&[u16]> {
+    class.attributes.iter().find_map(|attrib| match attrib {
+        AttributeInfo::PermittedSubclasses { classes } => Some(classes.as_slice()),
+        _ => None,
+    })
+}
+
+fn get_inner_classes(class: &ClassFile) -> &[InnerClassInfo] {
+    class
+        .attributes
+        .iter()
+        .find_map(|attrib| match attrib {
+            AttributeInfo::InnerClasses { classes } => Some(classes.as_slice()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the entry of the `InnerClasses` attribute describing the class itself.
+fn get_own_inner_class_info(class: &ClassFile) -> Result<Option<&InnerClassInfo>, DecompilerError> {
+    let name = class.constant_pool.get_class_entry(class.this_class)?.name;
+    for info in get_inner_classes(class) {
+        if class
+            .constant_pool
+            .get_class_entry(info.inner_class_info_index)?
+            .name
+            == name
+        {
+            return Ok(Some(info));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the class and the name and type of the method a local or anonymous class is
+/// declared in. The method is missing if the class is declared in an initializer.
+fn get_enclosing_method(
+    class: &ClassFile,
+) -> Result<Option<(String, Option<ConstNameTypeData>)>, DecompilerError> {
+    let constant_pool = &class.constant_pool;
+    for attrib in &class.attributes {
+        if let AttributeInfo::EnclosingMethod {
+            class_index,
+            method_index,
+        } = attrib
+        {
+            let method = match *method_index {
+                0 => None,
+                index => Some(constant_pool.get_name_type_entry(index)?),
+            };
+            return Ok(Some((
+                constant_pool.get_class_entry(*class_index)?.name,
+                method,
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the name of the class a class is declared in, if it is a nested class.
+fn get_enclosing_class(class: &ClassFile) -> Result<Option<String>, DecompilerError> {
+    let constant_pool = &class.constant_pool;
+    if let Some(info) = get_own_inner_class_info(class)? {
+        if info.outer_class_info_index != 0 {
+            return Ok(Some(
+                constant_pool
+                    .get_class_entry(info.outer_class_info_index)?
+                    .name,
+            ));
+        }
+    }
+    if let Some((enclosing_class, _)) = get_enclosing_method(class)? {
+        return Ok(Some(enclosing_class));
+    }
+    for attrib in &class.attributes {
+        if let AttributeInfo::NestHost { host_class_index } = attrib {
+            return Ok(Some(constant_pool.get_class_entry(*host_class_index)?.name));
+        }
+    }
+    Ok(None)
+}
+
+/// Checks if a class is written as part of the class it is declared in, which is the case if
+/// that class is on the class path as well.
+pub fn is_nested_class(class: &ClassFile, class_path: &ClassPath) -> Result<bool, DecompilerError> {
+    Ok(get_enclosing_class(class)?.is_some_and(|name| class_path.get(&name).is_some()))
+}
+
+fn is_anonymous_class(class: &ClassFile) -> Result<bool, DecompilerError> {
+    Ok(get_own_inner_class_info(class)?
+        .is_some_and(|info| info.outer_class_info_index == 0 && info.inner_name_index == 0))
+}
+
+/// Checks if a field holds the enclosing instance or a captured variable of a nested class.
+fn is_capture_field(field: &FieldInfo, constant_pool: &ConstantPool) -> bool {
+    field.access_flags.acc_synthetic
+        && constant_pool
+            .get_utf8_entry(field.name_index)
+            .is_ok_and(|name| name.starts_with("this$") || name.starts_with("val$"))
+}
+
+/// The positions of the synthetic parameters of each constructor, and of those passing captured
+/// variables together with the fields they are stored in.
+type CaptureParams = (
+    HashMap<usize, Vec<usize>>,
+    HashMap<usize, Vec<(usize, String)>>,
+);
+
+/// Removes the assignments of the enclosing instance and the captured variables javac
+/// generates in the constructors of nested classes, and returns the positions of the
+/// parameters passing them for each constructor.
+fn strip_capture_field_assignments(
+    class: &ClassFile,
+    class_name: &str,
+    bodies: &mut [Option<Result<Vec<AST>, DecompilerError>>],
+) -> Result<CaptureParams, DecompilerError> {
+    let constant_pool = &class.constant_pool;
+    let mut synthetic_params = HashMap::new();
+    let mut captured_params = HashMap::new();
+    for (method_index, method) in class.methods.iter().enumerate() {
+        let statements = match &mut bodies[method_index] {
+            Some(Ok(statements))
+                if constant_pool.get_utf8_entry(method.name_index)? == "<init>" =>
+            {
+                statements
+            }
+            _ => continue,
+        };
+        let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+        let (params, _) = descriptors::parse_method(descriptor.chars())?;
+        let mut positions = HashMap::new();
+        let mut slot = 1;
+        for (position, param) in params.iter().enumerate() {
+            positions.insert(slot, position);
+            slot += param.size();
+        }
+        let mut synthetic = Vec::new();
+        let mut captured = Vec::new();
+        statements.retain(|statement| {
+            let position = match statement {
+                AST::SetField {
+                    field_data,
+                    reference,
+                    value,
+                } if is_variable(reference, 0)
+                    && class.fields.iter().any(|field| {
+                        is_capture_field(field, constant_pool)
+                            && is_field_of(field_data, class_name, field, class)
+                    }) =>
+                {
+                    let position = match value.as_ref() {
+                        AST::Variable { index, .. } => positions.get(index),
+                        _ => None,
+                    };
+                    let name = &field_data.name_and_type.name;
+                    if let (Some(position), true) = (position, name.starts_with("val$")) {
+                        captured.push((*position, name.clone()));
+                    }
+                    position
+                }
+                _ => None,
+            };
+            synthetic.extend(position);
+            position.is_none()
+        });
+        if !synthetic.is_empty() {
+            synthetic.sort_unstable();
+            synthetic_params.insert(method_index, synthetic);
+        }
+        if !captured.is_empty() {
+            captured_params.insert(method_index, captured);
+        }
+    }
+    Ok((synthetic_params, captured_params))
+}
+
+/// Finds the member classes and the local and anonymous classes declared in a class. Local
+/// classes are declared at the start of the method declaring them, or as a member class if
+/// that method is not written.
+fn get_nested_classes<'a>(
+    class: &'a ClassFile,
+    class_name: &str,
+    bodies: &mut [Option<Result<Vec<AST>, DecompilerError>>],
+    class_path: &'a ClassPath,
+) -> Result<(Vec<DecompiledClass<'a>>, Vec<DecompiledClass<'a>>), DecompilerError> {
+    let constant_pool = &class.constant_pool;
+    let mut member_classes = Vec::new();
+    let mut local_classes = Vec::new();
+    let mut declarations: HashMap<usize, Vec<AST>> = HashMap::new();
+    for info in get_inner_classes(class) {
+        let name = constant_pool
+            .get_class_entry(info.inner_class_info_index)?
+            .name;
+        let nested_class = match class_path.get(&name) {
+            Some(nested_class) if name != class_name => nested_class,
+            _ => continue,
+        };
+        // e.g. the classes holding the tables for `switch` on enums
+        if info.inner_class_access_flags.acc_synthetic || nested_class.access_flags.acc_synthetic {
+            continue;
+        }
+        if info.outer_class_info_index != 0 {
+            if constant_pool
+                .get_class_entry(info.outer_class_info_index)?
+                .name
+                == class_name
+            {
+                member_classes.push(decompile_class(nested_class, class_path)?);
+            }
+            continue;
+        }
+        let method = match get_enclosing_method(nested_class)? {
+            Some((enclosing_class, method)) if enclosing_class == class_name => method,
+            _ => continue,
+        };
+        // the class bodies of enum constants are written with the constants
+        if nested_class.access_flags.acc_enum && !is_enum_class(nested_class)? {
+            continue;
+        }
+        let decompiled = decompile_class(nested_class, class_path)?;
+        if info.inner_name_index == 0 {
+            local_classes.push(decompiled);
+            continue;
+        }
+        let mut method_index = None;
+        if let Some(method) = method {
+            for (index, candidate) in class.methods.iter().enumerate() {
+                if constant_pool.get_utf8_entry(candidate.name_index)? == method.name
+                    && constant_pool.get_utf8_entry(candidate.descriptor_index)?
+                        == method.descriptor
+                    && !is_lambda_method(candidate, constant_pool)
+                    && matches!(bodies[index], Some(Ok(_)))
+                {
+                    method_index = Some(index);
+                }
+            }
+        }
+        match method_index {
+            Some(index) => {
+                declarations
+                    .entry(index)
+                    .or_default()
+                    .push(AST::LocalClass {
+                        class_name: decompiled.name.clone(),
+                    });
+                local_classes.push(decompiled);
+            }
+            None => member_classes.push(decompiled),
+        }
+    }
+    // javac lists the members of a nest in the reverse order of their declaration
+    let nest_members = class.attributes.iter().find_map(|attrib| match attrib {
+        AttributeInfo::NestMembers { classes } => Some(classes),
+        _ => None,
+    });
+    if let Some(nest_members) = nest_members {
+        let nest_members = nest_members
+            .iter()
+            .map(|index| Ok(constant_pool.get_class_entry(*index)?.name))
+            .collect::<Result<Vec<String>, DecompilerError>>()?;
+        member_classes.sort_by_key(|member_class: &DecompiledClass| {
+            std::cmp::Reverse(
+                nest_members
+                    .iter()
+                    .position(|name| *name == member_class.name),
+            )
+        });
+    }
+    for (index, declarations) in declarations {
+        if let Some(Ok(statements)) = &mut bodies[index] {
+            // declarations can not precede the call of another constructor
+            let position = match statements.first() {
+                Some(AST::Call { method_data, .. })
+                    if method_data.name_and_type.name == "<init>" =>
+                {
+                    1
+                }
+                _ => 0,
+            };
+            statements.splice(position..position, declarations);
+        }
+    }
+    Ok((member_classes, local_classes))
+}
+
+fn get_record_components(
+    class: &ClassFile,
+) -> Result<Option<Vec<RecordComponent>>, DecompilerError> {
+    let constant_pool = &class.constant_pool;
+    let components = match class.attributes.iter().find_map(|attrib| match attrib {
+        AttributeInfo::Record { components } => Some(components),
+        _ => None,
+    }) {
+        Some(components) => components,
+        None => return Ok(None),
+    };
+    components
+        .iter()
+        .map(|component| {
+            let descriptor = constant_pool.get_utf8_entry(component.descriptor_index)?;
+            let type_signature = match get_signature(&component.attributes, constant_pool)? {
+                Some(signature) => signatures::parse_field(signature.chars())?,
+                None => TypeSignature::from(descriptors::parse_field(descriptor.chars())?),
+            };
+            Ok(RecordComponent {
+                name: constant_pool.get_utf8_entry(component.name_index)?,
+                descriptor,
+                type_signature,
+            })
+        })
+        .collect::<Result<Vec<RecordComponent>, DecompilerError>>()
+        .map(Some)
+}
+
+/// Checks if a canonical constructor only assigns its parameters to the fields of the
+/// components, like the one javac generates if a record does not declare it.
+fn is_generated_canonical_constructor(statements: &[AST], components: &[RecordComponent]) -> bool {
+    let statements = match statements.split_last() {
+        Some((AST::VoidReturn, rest)) => rest,
+        _ => statements,
+    };
+    statements.len() == components.len() && is_component_assignments(statements, components)
+}
+
+/// Checks if statements assign the parameters of the canonical constructor to the fields of the
+/// components in order.
+fn is_component_assignments(statements: &[AST], components: &[RecordComponent]) -> bool {
+    if statements.len() != components.len() {
+        return false;
+    }
+    let mut slot = 1;
+    for (statement, component) in statements.iter().zip(components) {
+        match statement {
+            AST::SetField {
+                field_data,
+                reference,
+                value,
+            } if is_variable(reference, 0)
+                && is_variable(value, slot)
+                && field_data.name_and_type.name == component.name => {}
+            _ => return false,
+        }
+        slot += match descriptors::parse_field(component.descriptor.chars()) {
+            Ok(field_type) => field_type.size(),
+            Err(_) => return false,
+        };
+    }
+    true
+}
+
+fn name_record_parameter(value: &mut AST, slots: &HashMap<u16, String>) {
+    match value {
+        AST::Variable { index, .. } => {
+            if let Some(name) = slots.get(index) {
+                *value = AST::NamedVariable { name: name.clone() };
+            }
+        }
+        AST::Set {
+            index,
+            value: assigned,
+        } if slots.contains_key(index) => {
+            let name = slots[index].clone();
+            let mut assigned = std::mem::replace(assigned.as_mut(), AST::ConstNull);
+            name_record_parameter(&mut assigned, slots);
+            *value = AST::Assign {
+                target: Box::new(AST::NamedVariable { name }),
+                value: Box::new(assigned),
+            };
+        }
+        // lambda bodies use their own locals
+        AST::Lambda { captured, .. } => {
+            for value in captured {
+                name_record_parameter(value, slots);
+            }
+        }
+        _ => {
+            for child in value.children_mut() {
+                name_record_parameter(child, slots);
+            }
+        }
+    }
+}
+
+/// Names the parameters of a canonical constructor declared in a record after its components,
+/// as javac requires. The assignments of the fields javac appends to a compact constructor are
+/// removed, returns whether the constructor is compact.
+fn name_record_parameters(statements: &mut Vec<AST>, components: &[RecordComponent]) -> bool {
+    if let Some(AST::VoidReturn) = statements.last() {
+        statements.pop();
+    }
+    let assignments = statements.len().saturating_sub(components.len());
+    let is_compact = is_component_assignments(&statements[assignments..], components);
+    if is_compact {
+        statements.truncate(assignments);
+    }
+    let mut slots = HashMap::new();
+    let mut slot = 1;
+    for component in components {
+        slots.insert(slot, component.name.clone());
+        slot += descriptors::parse_field(component.descriptor.chars())
+            .map_or(1, |field_type| field_type.size());
+    }
+    for statement in statements.iter_mut() {
+        name_record_parameter(statement, &slots);
+    }
+    is_compact
+}
+
+fn is_enum_constant(field_data: &ConstFieldData, class: &ClassFile, class_name: &str) -> bool {
+    class.fields.iter().any(|field| {
+        field.access_flags.acc_enum && is_field_of(field_data, class_name, field, class)
+    })
+}
+
+/// Recovers the constants of an enum from the assignments of the constant fields in
+/// `<clinit>`, which are removed together with the assignment of the synthetic `$VALUES` array.
+fn get_enum_constants<'a>(
+    class: &ClassFile,
+    class_name: &str,
+    bodies: &mut [Option<Result<Vec<AST>, DecompilerError>>],
+    class_path: &'a ClassPath,
+) -> Result<Vec<EnumConstant<'a>>, DecompilerError> {
+    let constant_pool = &class.constant_pool;
+    let mut initializer = None;
+    for (method, body) in class.methods.iter().zip(bodies.iter_mut()) {
+        if constant_pool.get_utf8_entry(method.name_index)? == "<clinit>" {
+            initializer = body.as_mut().and_then(|body| body.as_mut().ok());
+        }
+    }
+    let statements = match initializer {
+        Some(statements) => statements,
+        // without the initializer only the names of the constants are known
+        None => {
+            return class
+                .fields
+                .iter()
+                .filter(|field| field.access_flags.acc_enum)
+                .map(|field| {
+                    Ok(EnumConstant {
+                        name: constant_pool.get_utf8_entry(field.name_index)?,
+                        args: Vec::new(),
+                        body: None,
+                    })
+                })
+                .collect();
+        }
+    };
+    let mut constants = Vec::new();
+    for statement in statements.iter() {
+        let (field_data, method_data, args) = match statement {
+            AST::SetStatic { field_data, value } => match value.as_ref() {
+                AST::New { method_data, args }
+                    if is_enum_constant(field_data, class, class_name) && args.len() >= 2 =>
+                {
+                    (field_data, method_data, args)
+                }
+                _ => break,
+            },
+            _ => break,
+        };
+        // constants with a class body are instances of an anonymous subclass
+        let body = match method_data.class.name != class_name {
+            true => class_path
+                .get(&method_data.class.name)
+                .map(|body| decompile_class(body, class_path))
+                .transpose()?,
+            false => None,
+        };
+        constants.push(EnumConstant {
+            name: field_data.name_and_type.name.clone(),
+            args: args[2..].to_vec(),
+            body,
+        });
+    }
+    statements.drain(..constants.len());
+    statements.retain(|statement| {
+        !matches!(statement, AST::SetStatic { field_data, .. }
+            if field_data.class.name == class_name && field_data.name_and_type.name == "$VALUES")
+    });
+    Ok(constants)
+}
+
+fn decompile_class<'a>(
+    class: &'a ClassFile,
+    class_path: &'a ClassPath,
+) -> Result<DecompiledClass<'a>, DecompilerError> {
+    let constant_pool = &class.constant_pool;
+    let name = class.constant_pool.get_class_entry(class.this_class)?.name;
+    let mut bodies: Vec<Option<Result<Vec<AST>, DecompilerError>>> = class
+        .methods
+        .iter()
+        .map(|method| get_code(method).map(|code| decompile_code(method, code, class, class_path)))
+        .collect();
+    strip_assertions_disabled_initializer(class, &mut bodies)?;
+    let is_enum = is_enum_class(class)?;
+    let constants = if is_enum {
+        get_enum_constants(class, &name, &mut bodies, class_path)?
+    } else {
+        Vec::new()
+    };
+    let components = get_record_components(class)?;
+    let (synthetic_params, captured_params) =
+        strip_capture_field_assignments(class, &name, &mut bodies)?;
+    let initializers = lift_field_initializers(class, &name, &mut bodies)?;
+    if is_enum || components.is_some() {
+        // the constructors of enums and records implicitly call `Enum(String, int)` or `Record()`
+        for body in bodies.iter_mut().flatten().flatten() {
+            if let Some(AST::Call { method_data, .. }) = body.first() {
+                if (method_data.class.name == "java/lang/Enum"
+                    || method_data.class.name == "java/lang/Record")
+                    && method_data.name_and_type.name == "<init>"
+                {
+                    body.remove(0);
+                }
+            }
+        }
+    }
+    let mut canonical_constructor = None;
+    let mut declared_canonical_constructor = None;
+    if let Some(components) = &components {
+        let descriptors: String = components
+            .iter()
+            .map(|component| component.descriptor.as_str())
+            .collect();
+        let canonical_descriptor = format!("({})V", descriptors);
+        for (index, method) in class.methods.iter().enumerate() {
+            if constant_pool.get_utf8_entry(method.name_index)? != "<init>"
+                || constant_pool.get_utf8_entry(method.descriptor_index)? != canonical_descriptor
+            {
+                continue;
+            }
+            if let Some(Ok(statements)) = &mut bodies[index] {
+                if is_generated_canonical_constructor(statements, components) {
+                    canonical_constructor = Some(index);
+                } else {
+                    let is_compact = name_record_parameters(statements, components);
+                    declared_canonical_constructor = Some((index, is_compact));
+                }
+            }
+        }
+    }
+    let (member_classes, local_classes) =
+        get_nested_classes(class, &name, &mut bodies, class_path)?;
+    Ok(DecompiledClass {
+        class,
+        class_path,
+        name,
+        bodies,
+        initializers,
+        constants,
+        components,
+        canonical_constructor,
+        declared_canonical_constructor,
+        synthetic_params,
+        captured_params,
+        local_offsets: HashMap::new(),
+        member_classes,
+        local_classes,
+    })
+}
+
+/// How the creations of a nested class are written.
+struct NestedClassCreation {
+    /// The positions of the synthetic parameters of each constructor, by its descriptor.
+    synthetic_params: HashMap<String, Vec<usize>>,
+    /// The positions of the parameters passing captured variables and the fields they are
+    /// stored in, by the descriptor of the constructor.
+    captured_params: HashMap<String, Vec<(usize, String)>>,
+    /// The type an anonymous class is created as.
+    anonymous_supertype: Option<TypeSignature>,
+}
+
+fn get_nested_class_creations(
+    decompiled: &DecompiledClass,
+    creations: &mut HashMap<String, NestedClassCreation>,
+) -> Result<(), DecompilerError> {
+    for nested in decompiled
+        .member_classes
+        .iter()
+        .chain(&decompiled.local_classes)
+    {
+        let constant_pool = &nested.class.constant_pool;
+        let mut synthetic_params = HashMap::new();
+        for (index, params) in &nested.synthetic_params {
+            let method = &nested.class.methods[*index];
+            let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+            synthetic_params.insert(descriptor, params.clone());
+        }
+        let mut captured_params = HashMap::new();
+        for (index, params) in &nested.captured_params {
+            let method = &nested.class.methods[*index];
+            let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+            captured_params.insert(descriptor, params.clone());
+        }
+        // an anonymous class either implements an interface or extends a class
+        let anonymous_supertype = match is_anonymous_class(nested.class)? {
+            true => {
+                let signature = get_class_signature(nested.class)?;
+                match signature.interfaces.into_iter().next() {
+                    Some(interface) if signature.super_class.is_object() => Some(interface),
+                    _ => Some(signature.super_class),
+                }
+            }
+            false => None,
+        };
+        creations.insert(
+            nested.name.clone(),
+            NestedClassCreation {
+                synthetic_params,
+                captured_params,
+                anonymous_supertype,
+            },
+        );
+        get_nested_class_creations(nested, creations)?;
+    }
+    Ok(())
+}
+
+/// The variables captured by each nested class, by the name of the field holding them. A field
+/// holds `None` if the class is created with different values for it.
+type Captures = HashMap<String, HashMap<String, Option<AST>>>;
+
+/// Removes the synthetic arguments from the creations of nested classes and declares
+/// anonymous classes where they are created. The variables passed to be captured are collected
+/// in `captures`.
+fn sugar_nested_class_creation(
+    value: &mut AST,
+    creations: &HashMap<String, NestedClassCreation>,
+    captures: &mut Captures,
+) {
+    for child in value.children_mut() {
+        sugar_nested_class_creation(child, creations, captures);
+    }
+    let (method_data, args) = match value {
+        AST::New { method_data, args } => (method_data, args),
+        _ => return,
+    };
+    let creation = match creations.get(&method_data.class.name) {
+        Some(creation) => creation,
+        None => return,
+    };
+    if let Some(captured_params) = creation
+        .captured_params
+        .get(&method_data.name_and_type.descriptor)
+    {
+        let captured = captures.entry(method_data.class.name.clone()).or_default();
+        for (position, name) in captured_params {
+            let arg = match args.get(*position) {
+                Some(arg) => arg,
+                None => continue,
+            };
+            captured
+                .entry(name.clone())
+                .and_modify(|value| {
+                    if !value
+                        .as_ref()
+                        .is_some_and(|value| is_same_value(value, arg))
+                    {
+                        *value = None;
+                    }
+                })
+                .or_insert_with(|| Some(arg.clone()));
+        }
+    }
+    if let Some(synthetic_params) = creation
+        .synthetic_params
+        .get(&method_data.name_and_type.descriptor)
+    {
+        let mut position = 0;
+        args.retain(|_| {
+            position += 1;
+            !synthetic_params.contains(&(position - 1))
+        });
+    }
+    if let Some(supertype) = &creation.anonymous_supertype {
+        *value = AST::AnonymousClass {
+            class_name: method_data.class.name.clone(),
+            supertype: supertype.clone(),
+            args: std::mem::take(args),
+        };
+    }
+}
+
+/// Resolves the variable captured in a field of a local or anonymous class. A class created in
+/// another one may capture a variable the other class captured as well, which it is passed as the
+/// field of that class.
+fn resolve_captured(class_name: &str, name: &str, captures: &Captures) -> Option<AST> {
+    let mut value = captures.get(class_name)?.get(name)?.clone()?;
+    for _ in 0..captures.len() {
+        let field_data = match &value {
+            AST::Field {
+                field_data,
+                reference,
+            } if is_variable(reference, 0) && field_data.name_and_type.name.starts_with("val$") => {
+                field_data
+            }
+            _ => break,
+        };
+        value = captures
+            .get(&field_data.class.name)?
+            .get(&field_data.name_and_type.name)?
+            .clone()?;
+    }
+    Some(value)
+}
+
+/// Checks if a value is the instance of the class itself or an enclosing instance of it.
+fn is_enclosing_instance(value: &AST) -> bool {
+    match value {
+        AST::Field {
+            field_data,
+            reference,
+        } => field_data.name_and_type.name.starts_with("this$") && is_enclosing_instance(reference),
+        value => is_variable(value, 0),
+    }
+}
+
+/// Replaces the reads of fields holding captured variables, by the class and the name of the
+/// field.
+fn replace_captured_field(value: &mut AST, captured: &HashMap<(String, String), AST>) {
+    if let AST::Field {
+        field_data,
+        reference,
+    } = value
+    {
+        let key = (
+            field_data.class.name.clone(),
+            field_data.name_and_type.name.clone(),
+        );
+        if let (Some(variable), true) = (captured.get(&key), is_enclosing_instance(reference)) {
+            *value = variable.clone();
+            return;
+        }
+    }
+    for child in value.children_mut() {
+        replace_captured_field(child, captured);
+    }
+}
+
+/// Checks if a method of a class has a parameter or a local of the given index, which would hide
+/// a captured variable of the same name.
+fn has_local(decompiled: &mut DecompiledClass, method_index: usize, index: u16) -> bool {
+    let method = &decompiled.class.methods[method_index];
+    let synthetic_params = decompiled.synthetic_params.get(&method_index);
+    let params = decompiled
+        .class
+        .constant_pool
+        .get_utf8_entry(method.descriptor_index)
+        .ok()
+        .and_then(|descriptor| descriptors::parse_method(descriptor.chars()).ok())
+        .map(|(params, _)| params)
+        .unwrap_or_default();
+    let mut slot = if method.access_flags.acc_static { 0 } else { 1 };
+    for (position, param) in params.iter().enumerate() {
+        if slot == index && !synthetic_params.is_some_and(|params| params.contains(&position)) {
+            return true;
+        }
+        slot += param.size();
+    }
+    match &mut decompiled.bodies[method_index] {
+        Some(Ok(statements)) => statements
+            .iter_mut()
+            .any(|statement| uses_local(statement, index)),
+        _ => false,
+    }
+}
+
+/// Renumbers the locals declared or used in a value.
+fn renumber_locals(value: &mut AST, renumber: &dyn Fn(u16) -> u16) {
+    match value {
+        AST::Variable { index, .. } | AST::Set { index, .. } | AST::ForEach { index, .. } => {
+            *index = renumber(*index)
+        }
+        AST::Lambda { params, .. } => {
+            for param in params {
+                *param = renumber(*param);
+            }
+        }
+        AST::Try { catches, .. } => {
+            for catch in catches {
+                catch.index = renumber(catch.index);
+            }
+        }
+        AST::InstanceOf { pattern, .. } => pattern.renumber(renumber),
+        AST::Switch { cases, .. } | AST::SwitchExpression { cases, .. } => {
+            for pattern in cases.iter_mut().filter_map(|case| case.pattern.as_mut()) {
+                pattern.renumber(renumber);
+            }
+        }
+        _ => {}
+    }
+    for child in value.children_mut() {
+        renumber_locals(child, renumber);
+    }
+}
+
+/// Replaces the reads of the fields holding captured variables in local and anonymous classes
+/// with the variables. The locals of a method that would hide a captured variable are renumbered
+/// past it. A field is read as the variable it is named after if it is not known where the class
+/// is created. Classes nested in them may read the variables captured by the classes `enclosing`
+/// them as well, which are the locals in `enclosing_locals`.
+fn replace_captured_fields(
+    decompiled: &mut DecompiledClass,
+    captures: &Captures,
+    enclosing: &HashMap<(String, String), AST>,
+    enclosing_locals: &BTreeSet<u16>,
+) {
+    let constant_pool = &decompiled.class.constant_pool;
+    let mut captured = enclosing.clone();
+    let mut locals = enclosing_locals.clone();
+    for field in &decompiled.class.fields {
+        let name = match constant_pool.get_utf8_entry(field.name_index) {
+            Ok(name) if is_capture_field(field, constant_pool) && name.starts_with("val$") => name,
+            _ => continue,
+        };
+        let variable = match resolve_captured(&decompiled.name, &name, captures) {
+            Some(AST::Variable { index, .. }) => {
+                locals.insert(index);
+                format!("var{}", index)
+            }
+            _ => String::from(&name["val$".len()..]),
+        };
+        captured.insert(
+            (decompiled.name.clone(), name),
+            AST::NamedVariable { name: variable },
+        );
+    }
+    for method_index in 0..decompiled.class.methods.len() {
+        let is_static = decompiled.class.methods[method_index]
+            .access_flags
+            .acc_static;
+        // the first local of a method that is not static is `this`
+        let first = if is_static { 0 } else { 1 };
+        let hidden = locals
+            .range(first..)
+            .any(|index| has_local(decompiled, method_index, *index));
+        let offset = match locals.last() {
+            Some(last) if hidden => last + 1,
+            _ => continue,
+        };
+        decompiled.local_offsets.insert(method_index, offset);
+        if let Some(Ok(statements)) = &mut decompiled.bodies[method_index] {
+            let renumber = |index: u16| {
+                if index >= first {
+                    index + offset
+                } else {
+                    index
+                }
+            };
+            for statement in statements {
+                renumber_locals(statement, &renumber);
+            }
+        }
+    }
+    if !captured.is_empty() {
+        let values = decompiled
+            .bodies
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .flatten()
+            .chain(decompiled.initializers.values_mut());
+        for value in values {
+            replace_captured_field(value, &captured);
+        }
+    }
+    for nested in decompiled
+        .member_classes
+        .iter_mut()
+        .chain(&mut decompiled.local_classes)
+    {
+        replace_captured_fields(nested, captures, &captured, &locals);
+    }
+}
+
+/// Applies `f` to the statements and the initializers of a class and of the classes nested in
+/// it.
+fn for_each_value_mut(decompiled: &mut DecompiledClass, f: &mut dyn FnMut(&mut AST)) {
+    let values = decompiled
+        .bodies
+        .iter_mut()
+        .flatten()
+        .flatten()
+        .flatten()
+        .chain(decompiled.initializers.values_mut())
+        .chain(
+            decompiled
+                .constants
+                .iter_mut()
+                .flat_map(|constant| constant.args.iter_mut()),
+        );
+    for value in values {
+        f(value);
+    }
+    for nested in decompiled
+        .member_classes
+        .iter_mut()
+        .chain(&mut decompiled.local_classes)
+        .chain(
+            decompiled
+                .constants
+                .iter_mut()
+                .flat_map(|constant| constant.body.as_mut()),
+        )
+    {
+        for_each_value_mut(nested, f);
+    }
+}
+
+/// A synthetic method javac generates to access a private member of another class of a nest,
+/// `value` is the expression it evaluates.
+struct Accessor {
+    /// The positions of the parameters, by their local.
+    params: HashMap<u16, usize>,
+    value: AST,
 }
 
-pub fn decompile(class: ClassFile) -> Result<(), DecompilerError> {
-    for method in class.methods {
-        println!(
-            "{}",
-            class
-                .constant_pool
-                .get_utf8_entry(method.name_index)
-                .unwrap()
-        ); //TODO
-        for attrib in method.attributes {
-            if let AttributeInfo::Code {
-                max_stack: _,
-                max_locals: _,
-                code,
-                exception_table: _,
-                attributes: _,
-            } = attrib
+/// Matches the synthetic `access$000` methods wrapping an access of a private member, and the
+/// synthetic constructors javac adds to call a private constructor.
+fn get_accessor(
+    method: &MethodInfo,
+    body: Option<&Result<Vec<AST>, DecompilerError>>,
+    class_name: &str,
+    constant_pool: &ConstantPool,
+) -> Result<Option<Accessor>, DecompilerError> {
+    if !method.access_flags.acc_synthetic {
+        return Ok(None);
+    }
+    let statements = match body {
+        Some(Ok(statements)) => statements.as_slice(),
+        _ => return Ok(None),
+    };
+    let name = constant_pool.get_utf8_entry(method.name_index)?;
+    let value = match (name.as_str(), statements) {
+        (
+            "<init>",
+            [call @ AST::Call {
+                method_data,
+                reference,
+                ..
+            }, AST::VoidReturn],
+        ) if is_variable(reference, 0)
+            && method_data.name_and_type.name == "<init>"
+            && method_data.class.name == class_name =>
+        {
+            call
+        }
+        (name, [AST::Return { value }]) if name.starts_with("access$") => value.as_ref(),
+        (name, [statement, AST::VoidReturn]) if name.starts_with("access$") => statement,
+        _ => return Ok(None),
+    };
+    let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+    let (param_types, _) = descriptors::parse_method(descriptor.chars())?;
+    let mut params = HashMap::new();
+    let mut slot = if method.access_flags.acc_static { 0 } else { 1 };
+    for (position, param_type) in param_types.iter().enumerate() {
+        params.insert(slot, position);
+        slot += param_type.size();
+    }
+    let mut value = value.clone();
+    if !uses_only_params(&mut value, &params, method.access_flags.acc_static) {
+        return Ok(None);
+    }
+    Ok(Some(Accessor { params, value }))
+}
+
+/// Checks if an expression only depends on the parameters of a method, and `this` if the
+/// method is not static.
+fn uses_only_params(value: &mut AST, params: &HashMap<u16, usize>, is_static: bool) -> bool {
+    let is_valid = match value {
+        AST::Variable { index, .. } => params.contains_key(index) || *index == 0 && !is_static,
+        AST::Set { .. } | AST::Lambda { .. } => false,
+        _ => true,
+    };
+    is_valid
+        && value
+            .children_mut()
+            .into_iter()
+            .all(|child| uses_only_params(child, params, is_static))
+}
+
+/// Records the nested classes named in the `InnerClasses` attributes of a class and of the
+/// classes written as part of it.
+fn add_inner_classes(
+    decompiled: &DecompiledClass,
+    names: &mut NameResolver,
+) -> Result<(), DecompilerError> {
+    let constant_pool = &decompiled.class.constant_pool;
+    for info in get_inner_classes(decompiled.class) {
+        let name = constant_pool
+            .get_class_entry(info.inner_class_info_index)?
+            .name;
+        let outer_class = match info.outer_class_info_index {
+            0 => None,
+            index => Some(constant_pool.get_class_entry(index)?.name),
+        };
+        let simple_name = match info.inner_name_index {
+            0 => None,
+            index => Some(constant_pool.get_utf8_entry(index)?),
+        };
+        names.add_inner_class(&name, outer_class.as_deref(), simple_name.as_deref());
+    }
+    for nested in decompiled
+        .member_classes
+        .iter()
+        .chain(&decompiled.local_classes)
+        .chain(
+            decompiled
+                .constants
+                .iter()
+                .flat_map(|constant| &constant.body),
+        )
+    {
+        add_inner_classes(nested, names)?;
+    }
+    Ok(())
+}
+
+fn get_accessors(
+    decompiled: &DecompiledClass,
+    accessors: &mut HashMap<(String, String, String), Accessor>,
+) -> Result<(), DecompilerError> {
+    let constant_pool = &decompiled.class.constant_pool;
+    for (method, body) in decompiled.class.methods.iter().zip(&decompiled.bodies) {
+        if let Some(accessor) =
+            get_accessor(method, body.as_ref(), &decompiled.name, constant_pool)?
+        {
+            let key = (
+                decompiled.name.clone(),
+                constant_pool.get_utf8_entry(method.name_index)?,
+                constant_pool.get_utf8_entry(method.descriptor_index)?,
+            );
+            accessors.insert(key, accessor);
+        }
+    }
+    for nested in decompiled
+        .member_classes
+        .iter()
+        .chain(&decompiled.local_classes)
+        .chain(
+            decompiled
+                .constants
+                .iter()
+                .flat_map(|constant| &constant.body),
+        )
+    {
+        get_accessors(nested, accessors)?;
+    }
+    Ok(())
+}
+
+/// Replaces the parameters of an accessor with the arguments it is called with.
+fn bind_accessor_params(value: &mut AST, params: &HashMap<u16, usize>, args: &[AST]) {
+    if let AST::Variable { index, .. } = value {
+        if let Some(arg) = params.get(index).and_then(|position| args.get(*position)) {
+            *value = arg.clone();
+        }
+        return;
+    }
+    for child in value.children_mut() {
+        bind_accessor_params(child, params, args);
+    }
+}
+
+/// Replaces the calls of synthetic accessors with the access they wrap.
+fn inline_accessor(value: &mut AST, accessors: &HashMap<(String, String, String), Accessor>) {
+    for child in value.children_mut() {
+        inline_accessor(child, accessors);
+    }
+    let (method_data, args) = match value {
+        AST::StaticCall { method_data, args } | AST::New { method_data, args } => {
+            (method_data, args)
+        }
+        _ => return,
+    };
+    let key = (
+        method_data.class.name.clone(),
+        method_data.name_and_type.name.clone(),
+        method_data.name_and_type.descriptor.clone(),
+    );
+    let accessor = match accessors.get(&key) {
+        Some(accessor) => accessor,
+        None => return,
+    };
+    let mut inlined = accessor.value.clone();
+    bind_accessor_params(&mut inlined, &accessor.params, args);
+    *value = match (&value, inlined) {
+        // a synthetic constructor creates the object with the private constructor it calls
+        (
+            AST::New { .. },
+            AST::Call {
+                method_data, args, ..
+            },
+        ) => AST::New { method_data, args },
+        (_, inlined) => inlined,
+    };
+}
+
+/// Checks if a method of an enum is generated by javac, i.e. `values()`, `valueOf(String)`
+/// and the helpers for them, or an empty constructor.
+fn is_generated_enum_method(
+    method: &MethodInfo,
+    body: Option<&Result<Vec<AST>, DecompilerError>>,
+    class_name: &str,
+    constant_pool: &ConstantPool,
+) -> Result<bool, DecompilerError> {
+    let name = constant_pool.get_utf8_entry(method.name_index)?;
+    let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+    if method.access_flags.acc_synthetic {
+        return Ok(true);
+    }
+    Ok(match name.as_str() {
+        "values" => descriptor == format!("()[L{};", class_name),
+        "valueOf" => descriptor == format!("(Ljava/lang/String;)L{};", class_name),
+        "<init>" => {
+            descriptor == "(Ljava/lang/String;I)V"
+                && matches!(body, Some(Ok(statements))
+                    if statements.iter().all(|statement| matches!(statement, AST::VoidReturn)))
+        }
+        _ => false,
+    })
+}
+
+/// Checks if a method of a record is generated by javac, i.e. the accessor of a component or
+/// `toString()`, `hashCode()` and `equals(Object)` implemented by `ObjectMethods`.
+fn is_generated_record_method(
+    method: &MethodInfo,
+    class: &ClassFile,
+    class_name: &str,
+    components: &[RecordComponent],
+) -> Result<bool, DecompilerError> {
+    let constant_pool = &class.constant_pool;
+    let code = match get_code(method) {
+        Some(code) if !method.access_flags.acc_static => code,
+        _ => return Ok(false),
+    };
+    let name = constant_pool.get_utf8_entry(method.name_index)?;
+    let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+    let instructions = disassembler::disassemble(code.clone())?;
+    for (_, instruction) in &instructions {
+        if let Instruction::InvokeDynamic { index } = instruction {
+            let call_site = constant_pool.get_invoke_dynamic_entry(*index)?;
+            let bootstrap_method = get_bootstrap_methods(class)
+                .get(call_site.bootstrap_method_attr_index as usize)
+                .map(|bootstrap_method| {
+                    constant_pool.get_method_handle_entry(bootstrap_method.bootstrap_method_ref)
+                })
+                .transpose()?;
+            if let Some(MethodHandleReference::Method { method_data }) =
+                bootstrap_method.map(|bootstrap_method| bootstrap_method.reference)
             {
-                let instructions: Vec<(u64, Instruction)> = disassembler::disassemble(code)?;
-                for (p, i) in &instructions {
-                    println!("{}: {:?}", p, i);
-                }
-                let control_flow_graph = gen_control_flow_graph(&instructions);
-                let paths = find_paths(&control_flow_graph, 0, Vec::new());
-                println!("{:?}", paths);
-                for block in control_flow_graph.values() {
-                    for statement in decompile_block(block, &class.constant_pool)? {
-                        println!(
-                            "{}",
-                            statement.to_java(method.access_flags.acc_static, get_class_name)
-                        );
-                    }
+                if method_data.class.name == "java/lang/runtime/ObjectMethods" {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    let component = match components.iter().find(|component| {
+        component.name == name && descriptor == format!("(){}", component.descriptor)
+    }) {
+        Some(component) => component,
+        None => return Ok(false),
+    };
+    Ok(match instructions.as_slice() {
+        [(_, Instruction::ALoad { index: 0 }), (_, Instruction::GetField { index }), (
+            _,
+            Instruction::IReturn
+            | Instruction::LReturn
+            | Instruction::FReturn
+            | Instruction::DReturn
+            | Instruction::AReturn,
+        )] => {
+            let field_data = constant_pool.get_field_entry(*index)?;
+            field_data.class.name == class_name && field_data.name_and_type.name == component.name
+        }
+        _ => false,
+    })
+}
+
+/// Writes the fields and methods of a class.
+fn write_members(
+    output: &mut String,
+    decompiled: &DecompiledClass,
+    is_anonymous: bool,
+    names: &NameResolver,
+) -> Result<(), DecompilerError> {
+    let DecompiledClass {
+        class,
+        name: class_name,
+        ..
+    } = decompiled;
+    let constant_pool = &class.constant_pool;
+    let is_enum = is_enum_class(class)?;
+    for (index, field) in class.fields.iter().enumerate() {
+        // the fields of a record are declared by its components
+        if decompiled.components.is_some() && !field.access_flags.acc_static {
+            continue;
+        }
+        if is_enum
+            && (field.access_flags.acc_enum
+                || field.access_flags.acc_synthetic
+                    && constant_pool.get_utf8_entry(field.name_index)? == "$VALUES")
+        {
+            continue;
+        }
+        let name = constant_pool.get_utf8_entry(field.name_index)?;
+        let descriptor = constant_pool.get_utf8_entry(field.descriptor_index)?;
+        if is_capture_field(field, constant_pool)
+            || field.access_flags.acc_synthetic && name == ASSERTIONS_DISABLED
+            || field.access_flags.acc_static && is_class_cache(&name, &descriptor)
+        {
+            continue;
+        }
+        let initializer = decompiled.initializers.get(&index);
+        let field = decompile_field(field, initializer, constant_pool, names)?;
+        // initializers may span multiple lines, e.g. lambdas with a block body
+        for line in field.lines() {
+            output.push_str("    ");
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    for (method_index, (method, body)) in class.methods.iter().zip(&decompiled.bodies).enumerate() {
+        if is_lambda_method(method, constant_pool) {
+            continue;
+        }
+        let name = constant_pool.get_utf8_entry(method.name_index)?;
+        let descriptor = constant_pool.get_utf8_entry(method.descriptor_index)?;
+        if method.access_flags.acc_static && (name.as_str(), descriptor.as_str()) == CLASS_HELPER {
+            continue;
+        }
+        // bridge methods and accessors are generated by javac, the calls of accessors are inlined
+        if method.access_flags.acc_bridge
+            || get_accessor(method, body.as_ref(), class_name, constant_pool)?.is_some()
+        {
+            continue;
+        }
+        if let Some(components) = &decompiled.components {
+            if is_generated_record_method(method, class, class_name, components)? {
+                continue;
+            }
+        }
+        if is_enum && is_generated_enum_method(method, body.as_ref(), class_name, constant_pool)? {
+            continue;
+        }
+        // anonymous classes can not declare constructors, only instance initializers
+        if is_anonymous && name == "<init>" {
+            if let Some(Ok(statements)) = body {
+                let statements = match statements.split_first() {
+                    Some((first, rest)) if is_super_constructor_call(first, class_name) => rest,
+                    _ => statements,
+                };
+                let statements = match statements.split_last() {
+                    Some((AST::VoidReturn, rest)) => rest,
+                    _ => statements,
+                };
+                if !statements.is_empty() {
+                    output.push_str("\n    {\n");
+                    write_statements(output, statements, false, 8, names);
+                    output.push_str("    }\n");
+                }
+            }
+            continue;
+        }
+        if decompiled.canonical_constructor == Some(method_index) {
+            continue;
+        }
+        if let (Some((index, is_compact)), Some(components), Some(Ok(statements))) = (
+            decompiled.declared_canonical_constructor,
+            &decompiled.components,
+            body,
+        ) {
+            if index == method_index {
+                output.push('\n');
+                write_canonical_constructor(
+                    output, method, statements, components, is_compact, class_name, names,
+                );
+                continue;
+            }
+        }
+        if name == "<clinit>" {
+            if let Some(Ok(statements)) = body {
+                if statements
+                    .iter()
+                    .all(|statement| matches!(statement, AST::VoidReturn))
+                {
+                    continue;
+                }
+            }
+        }
+        output.push('\n');
+        let mut synthetic_params = decompiled
+            .synthetic_params
+            .get(&method_index)
+            .cloned()
+            .unwrap_or_default();
+        if is_enum && name == "<init>" {
+            synthetic_params.extend([0, 1]);
+        }
+        decompile_method(
+            output,
+            decompiled,
+            method_index,
+            body.as_ref(),
+            &synthetic_params,
+            names,
+        )?;
+    }
+    for member_class in &decompiled.member_classes {
+        output.push('\n');
+        for line in write_class(member_class, names)?.lines() {
+            if !line.is_empty() {
+                output.push_str("    ");
+                output.push_str(line);
+            }
+            output.push('\n');
+        }
+    }
+    Ok(())
+}
+
+/// Writes the body of an anonymous class, which is declared where it is created.
+fn write_anonymous_body(
+    decompiled: &DecompiledClass,
+    names: &NameResolver,
+) -> Result<String, DecompilerError> {
+    declare_local_classes(decompiled, names)?;
+    let mut members = String::new();
+    let previous = names.enter_class(&decompiled.name);
+    let written = write_members(&mut members, decompiled, true, names);
+    names.exit_class(previous);
+    written?;
+    Ok(String::from(members.trim_start_matches('\n')))
+}
+
+/// Renders the local and anonymous classes of a class, so they are written where they are
+/// declared.
+fn declare_local_classes(
+    decompiled: &DecompiledClass,
+    names: &NameResolver,
+) -> Result<(), DecompilerError> {
+    for local_class in &decompiled.local_classes {
+        let source = match is_anonymous_class(local_class.class)? {
+            true => write_anonymous_body(local_class, names)?,
+            false => write_class(local_class, names)?,
+        };
+        names.declare_class(&local_class.name, source);
+    }
+    Ok(())
+}
+
+/// Writes the constants of an enum declaration, each with its arguments and class body.
+fn write_enum_constants(
+    output: &mut String,
+    constants: &[EnumConstant],
+    names: &NameResolver,
+) -> Result<(), DecompilerError> {
+    let mut declarations = Vec::with_capacity(constants.len());
+    for constant in constants {
+        let mut declaration = format!("    {}", constant.name);
+        if !constant.args.is_empty() {
+            let args: Vec<String> = constant
+                .args
+                .iter()
+                .map(|arg| arg.to_java(true, names))
+                .collect();
+            declaration.push_str(&format!("({})", args.join(", ")));
+        }
+        if let Some(body) = &constant.body {
+            declaration.push_str(" {\n");
+            for line in write_anonymous_body(body, names)?.lines() {
+                if !line.is_empty() {
+                    declaration.push_str("    ");
+                    declaration.push_str(line);
                 }
+                declaration.push('\n');
             }
+            declaration.push_str("    }");
         }
+        declarations.push(declaration);
     }
+    output.push_str(&declarations.join(",\n"));
+    output.push_str(";\n");
     Ok(())
 }
+
+fn write_class(
+    decompiled: &DecompiledClass,
+    names: &NameResolver,
+) -> Result<String, DecompilerError> {
+    declare_local_classes(decompiled, names)?;
+    let mut output = format!("{} {{\n", get_class_header(decompiled, names)?);
+    let is_enum = is_enum_class(decompiled.class)?;
+    if is_enum {
+        write_enum_constants(&mut output, &decompiled.constants, names)?;
+    }
+    let mut members = String::new();
+    let previous = names.enter_class(&decompiled.name);
+    let written = write_members(&mut members, decompiled, false, names);
+    names.exit_class(previous);
+    written?;
+    if is_enum && !members.is_empty() && !members.starts_with('\n') {
+        output.push('\n');
+    }
+    output.push_str(&members);
+    output.push_str("}\n");
+    Ok(output)
+}
+
+/// The classes a decompiled class may refer to, e.g. the synthetic classes javac generates
+/// for `switch` on enums or the class bodies of enum constants.
+#[derive(Default)]
+pub struct ClassPath {
+    classes: HashMap<String, ClassFile>,
+}
+
+impl ClassPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a class to the class path and returns its name.
+    pub fn insert(&mut self, class: ClassFile) -> Result<String, DecompilerError> {
+        let name = class.constant_pool.get_class_entry(class.this_class)?.name;
+        self.classes.insert(name.clone(), class);
+        Ok(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ClassFile> {
+        self.classes.get(name)
+    }
+}
+
+pub fn decompile(class: ClassFile) -> Result<String, DecompilerError> {
+    decompile_with(&class, &ClassPath::new())
+}
+
+/// Decompiles a class, looking up the classes it refers to in `class_path`.
+pub fn decompile_with(
+    class: &ClassFile,
+    class_path: &ClassPath,
+) -> Result<String, DecompilerError> {
+    let mut decompiled = decompile_class(class, class_path)?;
+    let mut accessors = HashMap::new();
+    get_accessors(&decompiled, &mut accessors)?;
+    for_each_value_mut(&mut decompiled, &mut |value| {
+        inline_accessor(value, &accessors)
+    });
+    let mut creations = HashMap::new();
+    get_nested_class_creations(&decompiled, &mut creations)?;
+    let mut captures = HashMap::new();
+    for_each_value_mut(&mut decompiled, &mut |value| {
+        sugar_nested_class_creation(value, &creations, &mut captures)
+    });
+    replace_captured_fields(
+        &mut decompiled,
+        &captures,
+        &HashMap::new(),
+        &BTreeSet::new(),
+    );
+
+    // the first pass only collects the referenced classes to decide on the imports
+    let mut names = NameResolver::new(&decompiled.name);
+    add_inner_classes(&decompiled, &mut names)?;
+    write_class(&decompiled, &names)?;
+    names.resolve();
+    let class_output = write_class(&decompiled, &names)?;
+
+    let mut output = String::new();
+    if let Some(package) = names.get_package() {
+        output.push_str(&format!("package {};\n\n", package));
+    }
+    let imports = names.get_imports();
+    for import in &imports {
+        output.push_str(&format!("import {};\n", import));
+    }
+    if !imports.is_empty() {
+        output.push('\n');
+    }
+    output.push_str(&class_output);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Decompiles a class of `tests/classes` together with its nested classes. The classes are
+    /// compiled from the sources next to them with `javac -d tests/classes`.
+    fn decompile_test_class(name: &str) -> String {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/classes");
+        let mut class_path = ClassPath::new();
+        for entry in std::fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            let is_related = file_name == format!("{}.class", name)
+                || file_name.starts_with(&format!("{}$", name)) && file_name.ends_with(".class");
+            if is_related {
+                let mut file = std::fs::File::open(&path).unwrap();
+                class_path
+                    .insert(javaclass::read_classfile(&mut file).unwrap())
+                    .unwrap();
+            }
+        }
+        decompile_with(class_path.get(name).unwrap(), &class_path).unwrap()
+    }
+
+    fn variable(index: u16) -> AST {
+        AST::Variable {
+            index,
+            vartype: VarType::Int,
+        }
+    }
+
+    fn component(name: &str) -> RecordComponent {
+        RecordComponent {
+            name: String::from(name),
+            descriptor: String::from("I"),
+            type_signature: TypeSignature::from(descriptors::FieldType::Int),
+        }
+    }
+
+    fn field_data(class: &str, name: &str, descriptor: &str) -> ConstFieldData {
+        ConstFieldData {
+            class: ConstClassData {
+                name: String::from(class),
+            },
+            name_and_type: ConstNameTypeData {
+                name: String::from(name),
+                descriptor: String::from(descriptor),
+            },
+        }
+    }
+
+    fn set_component(name: &str, value: AST) -> AST {
+        AST::SetField {
+            field_data: field_data("Point", name, "I"),
+            reference: Box::new(variable(0)),
+            value: Box::new(value),
+        }
+    }
+
+    #[test]
+    fn compact_canonical_constructor() {
+        let components = [component("x"), component("y")];
+        let mut statements = vec![
+            AST::Set {
+                index: 2,
+                value: Box::new(AST::Binary {
+                    operator: BinaryOperator::Add,
+                    lhs: Box::new(variable(1)),
+                    rhs: Box::new(variable(2)),
+                }),
+            },
+            set_component("x", variable(1)),
+            set_component("y", variable(2)),
+            AST::VoidReturn,
+        ];
+        assert!(name_record_parameters(&mut statements, &components));
+        let names = NameResolver::new("Point");
+        assert_eq!(
+            block_to_java(&statements, false, &names),
+            "    y = x + y;\n"
+        );
+    }
+
+    #[test]
+    fn canonical_constructor_with_named_parameters() {
+        let components = [component("x"), component("y")];
+        let mut statements = vec![
+            set_component("x", variable(2)),
+            set_component("y", variable(1)),
+            AST::VoidReturn,
+        ];
+        assert!(!name_record_parameters(&mut statements, &components));
+        let names = NameResolver::new("Point");
+        assert_eq!(
+            block_to_java(&statements, false, &names),
+            "    this.x = y;\n    this.y = x;\n"
+        );
+    }
+
+    fn double_comparison(operator: BinaryOperator, nan_result: i32) -> AST {
+        let double = |index| AST::Variable {
+            index,
+            vartype: VarType::Double,
+        };
+        get_condition(
+            operator,
+            AST::Compare {
+                lhs: Box::new(double(0)),
+                rhs: Box::new(double(2)),
+                compare_type: VarType::Double,
+                nan_result: Some(nan_result),
+            },
+        )
+    }
+
+    #[test]
+    fn negated_float_comparison_keeps_nan_result() {
+        let names = NameResolver::new("");
+        // `dcmpl; iflt` jumps past the body of `if (a >= b)`, which is false for NaN
+        let condition = double_comparison(BinaryOperator::Less, -1).negate();
+        assert_eq!(condition.to_java(true, &names), "var0 >= var2");
+        // `dcmpl; ifge` jumps past the body of `if (!(a >= b))`, which is true for NaN
+        let condition = double_comparison(BinaryOperator::GreaterEqual, -1).negate();
+        assert_eq!(condition.to_java(true, &names), "!(var0 >= var2)");
+        let condition = double_comparison(BinaryOperator::NotEqual, 1).negate();
+        assert_eq!(condition.to_java(true, &names), "var0 == var2");
+    }
+
+    #[test]
+    fn boolean_operands_of_bitwise_operators() {
+        let local_types = HashMap::from([
+            (1, descriptors::FieldType::Boolean),
+            (2, descriptors::FieldType::Boolean),
+            (3, descriptors::FieldType::Int),
+        ]);
+        let ternary = |condition: AST| AST::Ternary {
+            condition: Box::new(condition),
+            then: Box::new(AST::ConstInt { value: 1 }),
+            otherwise: Box::new(AST::ConstInt { value: 0 }),
+        };
+        let names = NameResolver::new("");
+        // `var1 & !var2` selects between 1 and 0 to evaluate the negation
+        for operator in [
+            BinaryOperator::BitAnd,
+            BinaryOperator::BitOr,
+            BinaryOperator::BitXOr,
+        ] {
+            let mut value = binary(operator, variable(1), ternary(variable(2).negate()));
+            apply_types(&mut value, &descriptors::FieldType::Boolean, &local_types);
+            let expected = format!("var1 {} !var2", operator.symbol());
+            assert_eq!(value.to_java(true, &names), expected);
+        }
+        // the operands of operators on ints are left alone
+        let mut value = binary(
+            BinaryOperator::BitAnd,
+            variable(3),
+            ternary(variable(2).negate()),
+        );
+        apply_types(&mut value, &descriptors::FieldType::Int, &local_types);
+        assert_eq!(value.to_java(true, &names), "var3 & (!var2 ? 1 : 0)");
+    }
+
+    fn literal(value: AST) -> String {
+        value.to_java(true, &NameResolver::new(""))
+    }
+
+    #[test]
+    fn escaped_literals() {
+        assert_eq!(
+            literal(AST::ConstString {
+                value: String::from("a\"b'\\\n\t\r\u{8}\u{c}\u{0}\u{e9}\u{1f600}"),
+            }),
+            "\"a\\\"b'\\\\\\n\\t\\r\\b\\f\\u0000\\u00e9\\ud83d\\ude00\""
+        );
+        assert_eq!(literal(AST::ConstChar { value: '\'' as u16 }), "'\\''");
+        assert_eq!(literal(AST::ConstChar { value: '"' as u16 }), "'\"'");
+        assert_eq!(literal(AST::ConstChar { value: 0x7f }), "'\\u007f'");
+        assert_eq!(literal(AST::ConstChar { value: 0xd800 }), "'\\ud800'");
+    }
+
+    #[test]
+    fn float_literals() {
+        assert_eq!(literal(AST::ConstFloat { value: 1.0 }), "1.0F");
+        assert_eq!(literal(AST::ConstFloat { value: 0.1 }), "0.1F");
+        assert_eq!(literal(AST::ConstFloat { value: 1e10 }), "1e10F");
+        assert_eq!(literal(AST::ConstFloat { value: 1.5e-5 }), "1.5e-5F");
+        assert_eq!(literal(AST::ConstFloat { value: -0.0 }), "-0.0F");
+        assert_eq!(
+            literal(AST::ConstFloat { value: f32::NAN }),
+            "java.lang.Float.NaN"
+        );
+        assert_eq!(
+            literal(AST::ConstFloat { value: f32::MAX }),
+            "java.lang.Float.MAX_VALUE"
+        );
+        assert_eq!(
+            literal(AST::ConstFloat {
+                value: f32::from_bits(1),
+            }),
+            "java.lang.Float.MIN_VALUE"
+        );
+        assert_eq!(
+            literal(AST::ConstFloat {
+                value: f32::NEG_INFINITY,
+            }),
+            "java.lang.Float.NEGATIVE_INFINITY"
+        );
+    }
+
+    #[test]
+    fn double_literals() {
+        assert_eq!(literal(AST::ConstDouble { value: 2.0 }), "2.0");
+        assert_eq!(literal(AST::ConstDouble { value: 0.1 }), "0.1");
+        assert_eq!(literal(AST::ConstDouble { value: 1234567.5 }), "1234567.5");
+        assert_eq!(literal(AST::ConstDouble { value: 1e7 }), "1e7");
+        assert_eq!(literal(AST::ConstDouble { value: 0.0001 }), "1e-4");
+        assert_eq!(
+            literal(AST::ConstDouble {
+                value: f64::MIN_POSITIVE,
+            }),
+            "java.lang.Double.MIN_NORMAL"
+        );
+        assert_eq!(
+            literal(AST::ConstDouble {
+                value: f64::INFINITY,
+            }),
+            "java.lang.Double.POSITIVE_INFINITY"
+        );
+        assert_eq!(
+            literal(AST::ConstLong { value: i64::MIN }),
+            "java.lang.Long.MIN_VALUE"
+        );
+        assert_eq!(literal(AST::ConstLong { value: -1 }), "-1L");
+    }
+
+    fn binary(operator: BinaryOperator, lhs: AST, rhs: AST) -> AST {
+        AST::Binary {
+            operator,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    fn negative(value: AST) -> AST {
+        AST::Unary {
+            operator: UnaryOperator::Neg,
+            value: Box::new(value),
+        }
+    }
+
+    #[test]
+    fn binary_operands_are_parenthesized_by_precedence() {
+        let sub = |lhs, rhs| binary(BinaryOperator::Sub, lhs, rhs);
+        assert_eq!(
+            literal(sub(sub(variable(0), variable(1)), variable(2))),
+            "var0 - var1 - var2"
+        );
+        assert_eq!(
+            literal(sub(variable(0), sub(variable(1), variable(2)))),
+            "var0 - (var1 - var2)"
+        );
+        assert_eq!(
+            literal(binary(
+                BinaryOperator::Mul,
+                binary(BinaryOperator::Add, variable(0), variable(1)),
+                variable(2),
+            )),
+            "(var0 + var1) * var2"
+        );
+        assert_eq!(
+            literal(binary(
+                BinaryOperator::Add,
+                binary(BinaryOperator::Mul, variable(0), variable(1)),
+                binary(BinaryOperator::Rem, variable(2), variable(3)),
+            )),
+            "var0 * var1 + var2 % var3"
+        );
+        assert_eq!(
+            literal(binary(
+                BinaryOperator::ShL,
+                variable(0),
+                binary(BinaryOperator::Add, variable(1), variable(2)),
+            )),
+            "var0 << var1 + var2"
+        );
+        assert_eq!(
+            literal(binary(
+                BinaryOperator::BitAnd,
+                binary(BinaryOperator::BitOr, variable(0), variable(1)),
+                variable(2),
+            )),
+            "(var0 | var1) & var2"
+        );
+    }
+
+    #[test]
+    fn unary_operands_are_parenthesized() {
+        assert_eq!(
+            literal(negative(binary(
+                BinaryOperator::Add,
+                variable(0),
+                variable(1)
+            ))),
+            "-(var0 + var1)"
+        );
+        assert_eq!(literal(negative(negative(variable(0)))), "-(-var0)");
+        assert_eq!(literal(negative(AST::ConstInt { value: -1 })), "-(-1)");
+        assert_eq!(
+            literal(binary(
+                BinaryOperator::Sub,
+                variable(0),
+                AST::ConstInt { value: -1 },
+            )),
+            "var0 - -1"
+        );
+        assert_eq!(
+            literal(AST::BasicCast {
+                value: Box::new(binary(BinaryOperator::Add, variable(0), variable(1))),
+                cast_type: VarType::Byte,
+            }),
+            "(byte) (var0 + var1)"
+        );
+    }
+
+    #[test]
+    fn ternary_operands_are_parenthesized() {
+        let ternary = |condition, then, otherwise| AST::Ternary {
+            condition: Box::new(condition),
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+        };
+        assert_eq!(
+            literal(ternary(
+                variable(0),
+                variable(1),
+                ternary(variable(2), variable(3), variable(4)),
+            )),
+            "var0 ? var1 : var2 ? var3 : var4"
+        );
+        assert_eq!(
+            literal(ternary(
+                ternary(variable(0), variable(1), variable(2)),
+                variable(3),
+                variable(4),
+            )),
+            "(var0 ? var1 : var2) ? var3 : var4"
+        );
+        assert_eq!(
+            literal(binary(
+                BinaryOperator::Add,
+                ternary(variable(0), variable(1), variable(2)),
+                variable(3),
+            )),
+            "(var0 ? var1 : var2) + var3"
+        );
+    }
+
+    #[test]
+    fn enhanced_for_loops() {
+        let output = decompile_test_class("ForEach");
+        assert!(output.contains("for (int var6 : var1) {\n            var2 += var6;"));
+        assert!(output.contains("for (String var3 : var1) {"));
+        // the index and the iterator are used in the body
+        assert!(output.contains("while (var3 < var1.length) {"));
+        assert!(output.contains("while (var2.hasNext()) {"));
+        assert!(output.contains("var2.remove();"));
+    }
+
+    #[test]
+    fn synchronized_blocks() {
+        let output = decompile_test_class("Synchronized");
+        assert!(output.contains("    synchronized void method() {"));
+        assert!(output
+            .contains("synchronized (this.lock) {\n            return this.count;\n        }"));
+        assert!(output.contains("    void unlocked() {"));
+        assert!(!output.contains("monitor"));
+    }
+
+    #[test]
+    fn string_switches() {
+        let output = decompile_test_class("StringSwitch");
+        // "Aa" and "BB" have the same hash code
+        assert!(output.contains(
+            "case \"foo\":\n                return 1;\n            case \"Aa\":\n            case \"BB\":\n                return 2;"
+        ));
+        assert!(output.contains(
+            "case \"Aa\":\n                return 1;\n            case \"BB\":\n                return 2;"
+        ));
+        assert!(output.contains(
+            "return switch (var1) {\n            case \"a\" -> \"x\";\n            case \"b\" -> \"y\";\n            default -> \"z\";\n        };"
+        ));
+        // a switch on a hash code written in the source
+        assert!(output.contains("switch (var1.hashCode()) {\n            case 1:"));
+        assert!(!output.contains("equals"));
+    }
+
+    #[test]
+    fn patterns() {
+        // the fixture uses pattern switches, which are a preview feature of Java 17
+        let output = decompile_test_class("Patterns");
+        assert!(output
+            .contains("if (var1 instanceof String var2) {\n            return var2.length();"));
+        // the cast is written in the source
+        assert!(output.contains(
+            "if (var1 instanceof String) {\n            return ((String) var1).length();"
+        ));
+        assert!(output.contains("case null:\n                return -1;"));
+        assert!(
+            output.contains("case String var4 when var4.length() > 2:\n                return 1;")
+        );
+        assert!(output.contains("case Integer var5:\n                return var5;"));
+        // javac jumps past the switch for the default case
+        assert!(output.contains("default:\n        }\n        return 0;"));
+        assert!(!output.contains("typeSwitch"));
+    }
+
+    #[test]
+    fn switch_expressions() {
+        let output = decompile_test_class("SwitchExpressions");
+        assert!(output.contains(
+            "var2 = switch (var1) {\n            case 1 -> \"a\";\n            case 2, 3 -> \"b\";\n            default -> {\n                System.out.println(var1);\n                yield \"c\";\n            }\n        };"
+        ));
+        assert!(output.contains(
+            "return Math.abs(switch (var1) {\n            case 1 -> -1;\n            default -> var1;\n        });"
+        ));
+        // the cases assign a local instead of yielding a value
+        assert!(output.contains("switch (var1) {\n            case 1:\n                var2 = 10;\n                break;"));
+    }
+
+    #[test]
+    fn synthetic_accessors_and_bridge_methods() {
+        // the fixture is compiled for Java 8, which accesses private members through `access$`
+        let output = decompile_test_class("Accessors");
+        assert!(output.contains("Accessors.this.value = 3;"));
+        assert!(output.contains("return Accessors.this.twice() + Accessors.read(Accessors.this);"));
+        assert!(!output.contains("access$"));
+        assert!(output.contains("public int compareTo(Accessors var1) {"));
+        assert!(!output.contains("compareTo(Object"));
+    }
+
+    #[test]
+    fn compound_assignments_and_increments() {
+        let output = decompile_test_class("Increments");
+        assert!(output.contains("var1 += 5;\n        var1++;"));
+        assert!(output.contains("var2[var3] *= 2;"));
+        assert!(output.contains("var2[var3++] = var4;"));
+        assert!(output.contains("this.count--;"));
+        assert!(output.contains("System.out.println(++var1 + var1);"));
+        // the assigned value is computed from other values
+        assert!(output.contains("var3 = var1 + 5;"));
+        assert!(output.contains("var1[0] = var1[1] + 2;"));
+    }
+
+    #[test]
+    fn assert_statements() {
+        let output = decompile_test_class("Asserts");
+        assert!(output
+            .contains("assert var1 > 0 : \"negative \" + var1;\n        assert var1 < limit;"));
+        assert!(!output.contains("$assertionsDisabled"));
+        assert!(!output.contains("desiredAssertionStatus"));
+        // the error is thrown regardless of whether assertions are enabled
+        assert!(
+            output.contains("if (var1 < 0) {\n            throw new AssertionError(\"negative\");")
+        );
+        assert!(output.contains("static int limit = 3;"));
+    }
+
+    #[test]
+    fn class_literals() {
+        let output = decompile_test_class("ClassLiterals");
+        assert!(output.contains(
+            "{String.class, int[].class, int.class, void.class, ClassLiterals[][].class}"
+        ));
+        assert!(output.contains("static final Class<?> TYPE = ClassLiterals.class;"));
+        // a field named like the one holding a primitive class
+        assert!(output.contains("{TYPE, Integer.MAX_VALUE, \"java.lang.String\"}"));
+    }
+
+    #[test]
+    fn class_literals_cached_by_class_helper() {
+        let cached = |name: &str| AST::Ternary {
+            condition: Box::new(AST::ConstBoolean { value: true }),
+            then: Box::new(AST::ClassLiteral {
+                class_type: descriptors::FieldType::Reference {
+                    name: String::from("java/lang/String"),
+                },
+            }),
+            otherwise: Box::new(AST::Static {
+                field_data: field_data("Old", name, "Ljava/lang/Class;"),
+            }),
+        };
+        let mut value = cached("class$java$lang$String");
+        inline_class_caches(&mut value);
+        assert_eq!(literal(value), "java.lang.String.class");
+        let mut value = cached("strings");
+        inline_class_caches(&mut value);
+        assert!(matches!(value, AST::Ternary { .. }));
+    }
+
+    #[test]
+    fn method_handle_and_method_type_constants() {
+        assert_eq!(
+            literal(AST::MethodTypeConstant {
+                descriptor: String::from("(I[Ljava/lang/String;)V"),
+            }),
+            "java.lang.invoke.MethodType.methodType(void.class, int.class, java.lang.String[].class)"
+        );
+        assert_eq!(
+            literal(AST::MethodTypeConstant {
+                descriptor: String::from("(I"),
+            }),
+            "java.lang.invoke.MethodType.fromMethodDescriptorString(\"(I\", null)"
+        );
+        assert_eq!(
+            literal(AST::MethodHandleConstant {
+                handle: ConstMethodHandleData {
+                    reference_kind: 2,
+                    reference: MethodHandleReference::Field {
+                        field_data: field_data("a/Point", "ORIGIN", "La/Point;"),
+                    },
+                },
+            }),
+            "java.lang.invoke.MethodHandles.lookup().findStaticGetter(a.Point.class, \"ORIGIN\", a.Point.class)"
+        );
+    }
+
+    #[test]
+    fn field_accesses() {
+        let output = decompile_test_class("Fields");
+        assert!(output.contains("this.a = this.b = 0;"));
+        assert!(output.contains("var1.a = Integer.MAX_VALUE - var1.b;"));
+        // static fields of the class itself are not qualified
+        assert!(output.contains("shared = Math.max(shared, System.out.hashCode());"));
+        assert!(output.contains("return var1 + this.a;"));
+    }
+
+    #[test]
+    fn static_and_interface_calls() {
+        let output = decompile_test_class("Calls");
+        assert!(output.contains("var1.add(\"x\");\n        var2.run();\n        Math.abs(-1);"));
+        // static methods of the class itself are not qualified
+        assert!(output.contains("return twice(var1.size()) + Integer.parseInt(\"3\");"));
+        assert!(output.contains("this.calls(null, null);"));
+        assert!(!output.contains(";)"));
+        assert!(!output.contains(";;"));
+    }
+
+    #[test]
+    fn lambda_captures_field_of_anonymous_class() {
+        // `() -> a + 1` inside an anonymous class captures `this.val$a` as its first argument
+        let captured_field = AST::Field {
+            field_data: field_data("Outer$1", "val$a", "I"),
+            reference: Box::new(variable(0)),
+        };
+        let captured = HashMap::from([(0, captured_field)]);
+        let mut body = binary(BinaryOperator::Add, variable(0), AST::ConstInt { value: 1 });
+        inline_locals(&mut body, &captured, &|index| index + 1);
+        let names = NameResolver::new("");
+        assert_eq!(body.to_java(false, &names), "this.val$a + 1");
+        let mut local = variable(1);
+        inline_locals(&mut local, &captured, &|index| index + 1);
+        assert_eq!(literal(local), "var2");
+    }
+
+    #[test]
+    fn captured_variables_of_anonymous_classes() {
+        let output = decompile_test_class("Captures");
+        assert!(!output.contains("val$"), "{}", output);
+        // the parameters of the method would hide the captured variables
+        assert!(output.contains("public int apply(int var4, int var5) {"));
+        assert!(output.contains("return var6 + var1 + var2;"));
+        // a parameter of a static method is not written as `this`
+        assert!(output.contains("return var0 + var1;"));
+        assert!(output.contains("var3 = () -> var1 + 1;"));
+    }
+
+    #[test]
+    fn field_initializers() {
+        let output = decompile_test_class("Initializers");
+        assert!(output.contains("static final int CONSTANT = 5;"));
+        assert!(output.contains("static long created = System.nanoTime();"));
+        assert!(output.contains("int count = 3;"));
+        assert!(output.contains("String name = \"n\" + this.count;"));
+        assert!(output.contains("List<String> names = new ArrayList();"));
+        // the initializer following the initializer block is not moved in front of it
+        assert!(output.contains("    int last;\n"));
+        assert!(output.contains(
+            "        System.out.println(this.count);
+        this.last = 7;
+        this.fromParam = var1;
+"
+        ));
+    }
+
+    #[test]
+    fn initializer_expressions_only_use_this() {
+        let this_field = AST::Field {
+            field_data: field_data("Outer", "count", "I"),
+            reference: Box::new(variable(0)),
+        };
+        let sum = binary(
+            BinaryOperator::Add,
+            this_field.clone(),
+            AST::ConstInt { value: 1 },
+        );
+        assert!(is_initializer_expression(&sum, false));
+        // static initializers have no `this`
+        assert!(!is_initializer_expression(&sum, true));
+        // parameters are locals as well
+        let sum = binary(BinaryOperator::Add, this_field, variable(1));
+        assert!(!is_initializer_expression(&sum, false));
+    }
+
+    #[test]
+    fn bound_method_references() {
+        let output = decompile_test_class("MethodReferences");
+        assert!(output.contains(
+            "        var1.forEach(System.out::println);
+        var2 = this.name::length;
+"
+        ));
+        // only the check javac generates right before the method reference is removed
+        assert!(output.contains(
+            "        Objects.requireNonNull(this.name);
+        var1.forEach(this.name::concat);
+"
+        ));
+    }
+
+    #[test]
+    fn boxing_of_arguments() {
+        let output = decompile_test_class("Boxing");
+        assert!(output.contains("var1.add(5);"));
+        // the parameter is a type variable
+        assert!(output.contains("var2.generic(3L);"));
+        assert!(output.contains("this.object(true);"));
+        assert!(output.contains("this.varargs(1, 'c', \"s\");"));
+        // the boxing selects the overload
+        assert!(output.contains("var1.remove(Integer.valueOf(5));"));
+        assert!(output.contains("this.overloaded(Integer.valueOf(5));"));
+        // the constant would be boxed as an `Integer`
+        assert!(output.contains("this.object(Byte.valueOf("));
+    }
+
+    #[test]
+    fn string_conversions_in_concatenation() {
+        let output = decompile_test_class("Concat");
+        assert!(output.contains("return var1.getKey() + \"=\" + var1.getValue();"));
+        assert!(output.contains("return (String) var1.getKey() + var1.getValue();"));
+        assert!(output.contains("var1 += var2;"));
+        assert!(output.contains("return var1.get(0) + (String) var1.get(1);"));
+        // the contents of the array are added rather than the array
+        assert!(output.contains("return var2 + String.valueOf(var1);"));
+        // the numbers would be added otherwise
+        assert!(output.contains("return String.valueOf(var1) + var2;"));
+    }
+
+    #[test]
+    fn unboxing_of_operands() {
+        let output = decompile_test_class("Boxing");
+        assert!(output.contains("var2 += var4;"), "{}", output);
+        assert!(!output.contains("intValue()"));
+    }
+
+    #[test]
+    fn try_with_resources() {
+        let output = decompile_test_class("Resources");
+        // a statement with several resources returning a value
+        assert!(output.contains(
+            "        try (InputStream var3 = new FileInputStream(var1); \
+             OutputStream var4 = new FileOutputStream(var2)) {
+            var4.write(var3.read());
+            return var3.available();
+        }
+"
+        ));
+        assert!(output.contains(
+            "        try (InputStream var2 = new FileInputStream(var1)) {
+            return var2.read();
+        } catch (IOException var2) {
+            return -1;
+        } finally {
+            System.out.println(\"done\");
+        }
+"
+        ));
+        // a resource closed in a `finally` block is not turned into one
+        assert!(output.contains(
+            "        try {
+            var1.read();
+        } finally {
+            var1.close();
+        }
+"
+        ));
+    }
+
+    #[test]
+    fn finally_blocks() {
+        let output = decompile_test_class("Resources");
+        assert!(output.contains(
+            "        try {
+            var1.read();
+        } catch (IOException var2) {
+            var2.printStackTrace();
+        } finally {
+            System.out.println(\"done\");
+        }
+"
+        ));
+        // a handler rethrowing the exception is only a `finally` block if it is also run on
+        // the other paths
+        assert!(output.contains(
+            "        } catch (Throwable var2) {
+            System.out.println(\"failed\");
+            throw var2;
+        }
+"
+        ));
+    }
+}
@@ -1,10 +1,14 @@
 extern crate javadec;
 
 use clap::{App, Arg, ArgMatches};
+use javaclass::ClassFile;
+use javadec::ClassPath;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Display;
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
 
 #[derive(Debug)]
 struct ContextError {
@@ -41,28 +45,124 @@ impl<T, E> ToContextError<T, E> for Result<T, E> {
     }
 }
 
+/// Loads the classes related to the class at `path` that javac writes next to it, i.e. its
+/// nested classes and its direct supertypes in the same package.
+fn load_related_classes(
+    path: &Path,
+    classfile: &ClassFile,
+    class_path: &mut ClassPath,
+) -> Result<(), Box<dyn Error>> {
+    let stem = match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some(stem) => format!("{}$", stem),
+        None => return Ok(()),
+    };
+    let directory = match path.parent() {
+        Some(directory) if directory.as_os_str().is_empty() => Path::new("."),
+        Some(directory) => directory,
+        None => return Ok(()),
+    };
+    let mut related = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let entry_path = entry?.path();
+        let is_nested = entry_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(&stem) && name.ends_with(".class"));
+        if is_nested {
+            related.push(entry_path);
+        }
+    }
+    let constant_pool = &classfile.constant_pool;
+    let name = constant_pool.get_class_entry(classfile.this_class)?.name;
+    let package = name.rsplit_once('/').map(|(package, _)| package);
+    let mut supertypes = classfile.interfaces.clone();
+    if classfile.super_class != 0 {
+        supertypes.push(classfile.super_class);
+    }
+    for index in supertypes {
+        let supertype = constant_pool.get_class_entry(index)?.name;
+        let (supertype_package, simple_name) = match supertype.rsplit_once('/') {
+            Some((package, simple_name)) => (Some(package), simple_name),
+            None => (None, supertype.as_str()),
+        };
+        let supertype_path = directory.join(format!("{}.class", simple_name));
+        if supertype_package == package && supertype_path.is_file() {
+            related.push(supertype_path);
+        }
+    }
+    for related_path in related {
+        let mut file = File::open(&related_path)?;
+        class_path.insert(javaclass::read_classfile(&mut file)?)?;
+    }
+    Ok(())
+}
+
 fn run(matches: ArgMatches) -> Result<(), ContextError> {
-    for val in matches
+    let inputs: Vec<&str> = matches
         .values_of("INPUT")
         .expect("missing required argument")
-    {
+        .collect();
+    // like the nested classes of a jar, nested classes passed together with the class declaring
+    // them are written as part of that class
+    let mut input_classes = ClassPath::new();
+    let mut input_names = HashMap::new();
+    for val in inputs.iter().filter(|val| !val.ends_with(".jar")) {
         let mut file = File::open(val).context_err(val)?;
+        let classfile = javaclass::read_classfile(&mut file).context_err(val)?;
+        input_names.insert(*val, input_classes.insert(classfile).context_err(val)?);
+    }
+    for val in inputs {
+        let mut class_path = ClassPath::new();
         if val.ends_with(".jar") {
+            let file = File::open(val).context_err(val)?;
             let mut archive = zip::ZipArchive::new(file).context_err(val)?;
+            // all classes are read first, so that classes can refer to each other
+            let mut entries = Vec::new();
             for i in 0..archive.len() {
                 let mut zfile = archive.by_index(i).context_err(val)?;
-                println!("{}", zfile.name());
+                let mut class_name = None;
                 if zfile.name().ends_with(".class") {
                     let mut full = Vec::new();
                     zfile.read_to_end(&mut full).context_err(val)?;
                     let mut data = std::io::Cursor::new(full);
                     let classfile = javaclass::read_classfile(&mut data).context_err(val)?;
-                    javadec::decompile(classfile).context_err(val)?;
+                    class_name = Some(class_path.insert(classfile).context_err(val)?);
+                }
+                entries.push((String::from(zfile.name()), class_name));
+            }
+            for (entry_name, class_name) in entries {
+                let classfile = class_name.and_then(|name| class_path.get(&name));
+                // nested classes are written as part of the class declaring them
+                if let Some(classfile) = classfile {
+                    if javadec::is_nested_class(classfile, &class_path).context_err(val)? {
+                        continue;
+                    }
+                }
+                println!("{}", entry_name);
+                if let Some(classfile) = classfile {
+                    let output =
+                        javadec::decompile_with(classfile, &class_path).context_err(val)?;
+                    println!("{}", output);
                 }
             }
         } else {
-            let classfile = javaclass::read_classfile(&mut file).context_err(val)?;
-            javadec::decompile(classfile).context_err(val)?;
+            let classfile = input_names
+                .get(val)
+                .and_then(|name| input_classes.get(name))
+                .expect("class inputs are read first");
+            if javadec::is_nested_class(classfile, &input_classes).context_err(val)? {
+                continue;
+            }
+            if let Err(error) = load_related_classes(Path::new(val), classfile, &mut class_path) {
+                return Err(ContextError {
+                    error,
+                    context: String::from(val),
+                });
+            }
+            println!(
+                "{}",
+                javadec::decompile_with(classfile, &class_path).context_err(val)?
+            );
         }
     }
     Ok(())
@@ -82,8 +182,7 @@ fn main() {
         )
         .get_matches();
 
-    match run(matches) {
-        Err(e) => eprintln!("{}: {}", name, e),
-        Ok(_) => {}
+    if let Err(e) = run(matches) {
+        eprintln!("{}: {}", name, e);
     }
 }
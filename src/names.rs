@@ -0,0 +1,301 @@
+use super::descriptors;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Converts internal class names into the names used in the java output. All top level classes
+/// passed to the resolver are recorded, so that after a first rendering pass `resolve` can decide
+/// which of them can be imported and referred to by their simple name.
+pub struct NameResolver {
+    class_name: String,
+    package: String,
+    referenced: RefCell<BTreeSet<String>>,
+    simple_names: HashSet<String>,
+    /// The source of the local and anonymous classes, which is written where they are declared.
+    declarations: RefCell<HashMap<String, String>>,
+    /// The class whose members are being written, its own static members are referred to
+    /// without naming the class.
+    current_class: RefCell<String>,
+    /// The nested classes named in the `InnerClasses` attributes, by their internal name.
+    inner_classes: HashMap<String, InnerClass>,
+}
+
+/// An entry of an `InnerClasses` attribute. Local and anonymous classes have no outer class and
+/// anonymous classes have no simple name.
+struct InnerClass {
+    outer_class: Option<String>,
+    simple_name: Option<String>,
+}
+
+fn get_package(name: &str) -> &str {
+    match name.rfind('/') {
+        Some(index) => &name[..index],
+        None => "",
+    }
+}
+
+fn get_simple_name(name: &str) -> &str {
+    match name.rfind('/') {
+        Some(index) => &name[index + 1..],
+        None => name,
+    }
+}
+
+impl NameResolver {
+    pub fn new(class_name: &str) -> Self {
+        NameResolver {
+            class_name: String::from(class_name),
+            package: String::from(get_package(class_name)),
+            referenced: RefCell::new(BTreeSet::new()),
+            simple_names: HashSet::new(),
+            declarations: RefCell::new(HashMap::new()),
+            current_class: RefCell::new(String::from(class_name)),
+            inner_classes: HashMap::new(),
+        }
+    }
+
+    /// Records a nested class, only these are written as members of their outer class since
+    /// top level classes may contain a '$' in their name as well.
+    pub fn add_inner_class(
+        &mut self,
+        name: &str,
+        outer_class: Option<&str>,
+        simple_name: Option<&str>,
+    ) {
+        self.inner_classes.insert(
+            String::from(name),
+            InnerClass {
+                outer_class: outer_class.map(String::from),
+                simple_name: simple_name.map(String::from),
+            },
+        );
+    }
+
+    /// Splits an internal name into the top level class and the names of its inner classes,
+    /// starting with the outermost one.
+    fn split_inner_classes<'a>(&'a self, name: &'a str) -> (&'a str, Vec<&'a str>) {
+        let mut top_level = name;
+        let mut inner_classes = Vec::new();
+        while let Some(InnerClass {
+            outer_class: Some(outer_class),
+            simple_name: Some(simple_name),
+        }) = self.inner_classes.get(top_level)
+        {
+            inner_classes.push(simple_name.as_str());
+            top_level = outer_class;
+        }
+        inner_classes.reverse();
+        (top_level, inner_classes)
+    }
+
+    /// Returns the name a class is declared with, anonymous classes keep their binary name.
+    pub fn get_simple_name(&self, raw_name: &str) -> String {
+        match self.inner_classes.get(raw_name) {
+            Some(InnerClass {
+                simple_name: Some(simple_name),
+                ..
+            }) => simple_name.clone(),
+            _ => String::from(get_simple_name(raw_name)),
+        }
+    }
+
+    pub fn get_class_name(&self, raw_name: &str) -> String {
+        if raw_name.starts_with('[') {
+            if let Ok(field_type) = descriptors::parse_field(raw_name.chars()) {
+                return field_type.to_java(self);
+            }
+        }
+        let (top_level, inner_classes) = self.split_inner_classes(raw_name);
+        // local classes are only referred to inside of the class declaring them and by their
+        // simple name
+        if let Some(InnerClass {
+            outer_class: None,
+            simple_name: Some(simple_name),
+        }) = self.inner_classes.get(top_level)
+        {
+            let mut java_name = simple_name.clone();
+            for inner_class in inner_classes {
+                java_name.push('.');
+                java_name.push_str(inner_class);
+            }
+            return java_name;
+        }
+        self.referenced.borrow_mut().insert(String::from(top_level));
+        let mut java_name = if self.simple_names.contains(top_level) {
+            String::from(get_simple_name(top_level))
+        } else {
+            top_level.replace('/', ".")
+        };
+        for inner_class in inner_classes {
+            java_name.push('.');
+            java_name.push_str(inner_class);
+        }
+        java_name
+    }
+
+    /// Decides which of the referenced classes are referred to by their simple name. On a
+    /// clash the class itself wins, then classes of the same package, then `java.lang`, then the
+    /// first name in alphabetical order.
+    pub fn resolve(&mut self) {
+        let (own_top_level, _) = self.split_inner_classes(&self.class_name);
+        let own_top_level = String::from(own_top_level);
+        let mut referenced = self.referenced.replace(BTreeSet::new());
+        referenced.insert(own_top_level.clone());
+        let mut by_simple_name: BTreeMap<&str, Vec<&String>> = BTreeMap::new();
+        for name in &referenced {
+            by_simple_name
+                .entry(get_simple_name(name))
+                .or_default()
+                .push(name);
+        }
+        let rank = |name: &String| {
+            if **name == own_top_level {
+                0
+            } else if get_package(name) == self.package {
+                1
+            } else if get_package(name) == "java/lang" {
+                2
+            } else {
+                3
+            }
+        };
+        let simple_names = by_simple_name
+            .values()
+            .filter_map(|names| names.iter().min_by_key(|name| rank(name)))
+            .map(|name| (*name).clone())
+            .collect();
+        self.simple_names = simple_names;
+    }
+
+    /// Records the source of a local or anonymous class for the current rendering pass.
+    pub fn declare_class(&self, name: &str, source: String) {
+        self.declarations
+            .borrow_mut()
+            .insert(String::from(name), source);
+    }
+
+    pub fn get_declaration(&self, name: &str) -> Option<String> {
+        self.declarations.borrow().get(name).cloned()
+    }
+
+    /// Sets the class whose members are being written and returns the previous one, which is
+    /// restored by passing it to `exit_class`.
+    pub fn enter_class(&self, name: &str) -> String {
+        self.current_class.replace(String::from(name))
+    }
+
+    pub fn exit_class(&self, previous: String) {
+        self.current_class.replace(previous);
+    }
+
+    pub fn is_current_class(&self, name: &str) -> bool {
+        *self.current_class.borrow() == name
+    }
+
+    pub fn get_package(&self) -> Option<String> {
+        if self.package.is_empty() {
+            None
+        } else {
+            Some(self.package.replace('/', "."))
+        }
+    }
+
+    /// Returns the imports needed by the names resolved since the last call to `resolve`. Classes
+    /// of the default package cannot be imported.
+    pub fn get_imports(&self) -> Vec<String> {
+        self.referenced
+            .borrow()
+            .iter()
+            .filter(|name| self.simple_names.contains(*name))
+            .filter(|name| {
+                let package = get_package(name);
+                !package.is_empty() && package != self.package && package != "java/lang"
+            })
+            .map(|name| name.replace('/', "."))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Resolves the names once to record the referenced classes and returns them as written in
+    /// the second pass.
+    fn resolve(names: &mut NameResolver, raw_names: &[&str]) -> Vec<String> {
+        for raw_name in raw_names {
+            names.get_class_name(raw_name);
+        }
+        names.resolve();
+        raw_names
+            .iter()
+            .map(|raw_name| names.get_class_name(raw_name))
+            .collect()
+    }
+
+    #[test]
+    fn clashing_simple_names() {
+        let mut names = NameResolver::new("a/List");
+        assert_eq!(
+            resolve(&mut names, &["java/util/List", "a/List"]),
+            ["java.util.List", "List"]
+        );
+
+        let mut names = NameResolver::new("a/Main");
+        assert_eq!(
+            resolve(&mut names, &["java/lang/Object", "a/Object", "b/Object"]),
+            ["java.lang.Object", "Object", "b.Object"]
+        );
+
+        let mut names = NameResolver::new("a/Main");
+        assert_eq!(
+            resolve(&mut names, &["b/String", "java/lang/String"]),
+            ["b.String", "String"]
+        );
+
+        // otherwise the first name in alphabetical order wins
+        let mut names = NameResolver::new("a/Main");
+        assert_eq!(
+            resolve(&mut names, &["java/util/Map", "b/Map", "c/Set"]),
+            ["java.util.Map", "Map", "Set"]
+        );
+    }
+
+    #[test]
+    fn imports() {
+        let mut names = NameResolver::new("a/Main");
+        resolve(
+            &mut names,
+            &[
+                "java/util/List",
+                "java/lang/String",
+                "a/Helper",
+                "Default",
+                "b/Set",
+                "java/util/Set",
+            ],
+        );
+        assert_eq!(names.get_imports(), ["b.Set", "java.util.List"]);
+
+        let mut names = NameResolver::new("Main");
+        resolve(&mut names, &["Default", "java/lang/String"]);
+        assert!(names.get_imports().is_empty());
+    }
+
+    #[test]
+    fn inner_classes() {
+        let mut names = NameResolver::new("a/Main");
+        names.add_inner_class("b/Outer$Inner", Some("b/Outer"), Some("Inner"));
+        names.add_inner_class("a/Main$1Local", None, Some("Local"));
+        names.add_inner_class("a/Main$1", None, None);
+        assert_eq!(
+            resolve(
+                &mut names,
+                &["b/Outer$Inner", "a/Main$1Local", "b/Top$Level", "a/Main$1"]
+            ),
+            ["Outer.Inner", "Local", "Top$Level", "Main$1"]
+        );
+        assert_eq!(names.get_imports(), ["b.Outer", "b.Top$Level"]);
+        assert_eq!(names.get_simple_name("b/Outer$Inner"), "Inner");
+        assert_eq!(names.get_simple_name("b/Top$Level"), "Top$Level");
+    }
+}
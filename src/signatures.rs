@@ -0,0 +1,501 @@
+use super::descriptors::{
+    accept, consume, expect, parse_return_desc, peek, DescriptorParseError, FieldType,
+};
+use super::names::NameResolver;
+use std::iter::Peekable;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeSignature {
+    Base { base_type: FieldType },
+    Class { classes: Vec<SimpleClassType> },
+    TypeVariable { name: String },
+    Array { inner: Box<TypeSignature> },
+}
+
+/// One segment of a class type signature. The first segment carries the full internal name,
+/// following segments carry the simple names of the inner classes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimpleClassType {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeArgument {
+    Wildcard,
+    Exact { argument: TypeSignature },
+    Extends { bound: TypeSignature },
+    Super { bound: TypeSignature },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeParameter {
+    pub name: String,
+    pub class_bound: Option<TypeSignature>,
+    pub interface_bounds: Vec<TypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub super_class: TypeSignature,
+    pub interfaces: Vec<TypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub params: Vec<TypeSignature>,
+    pub return_type: TypeSignature,
+    pub throws: Vec<TypeSignature>,
+}
+
+impl From<FieldType> for TypeSignature {
+    fn from(field_type: FieldType) -> Self {
+        match field_type {
+            FieldType::Reference { name } => TypeSignature::Class {
+                classes: vec![SimpleClassType {
+                    name,
+                    type_arguments: Vec::new(),
+                }],
+            },
+            FieldType::Array { inner } => TypeSignature::Array {
+                inner: Box::new(TypeSignature::from(*inner)),
+            },
+            base_type => TypeSignature::Base { base_type },
+        }
+    }
+}
+
+impl TypeSignature {
+    pub fn to_java(&self, names: &NameResolver) -> String {
+        match self {
+            TypeSignature::Base { base_type } => base_type.to_java(names),
+            TypeSignature::TypeVariable { name } => name.clone(),
+            TypeSignature::Array { inner } => format!("{}[]", inner.to_java(names)),
+            TypeSignature::Class { classes } => {
+                // only qualify inner classes by their generic outer class if it has arguments
+                let mut java = String::new();
+                let mut name = String::new();
+                for (i, class) in classes.iter().enumerate() {
+                    if i == 0 {
+                        name.push_str(&class.name);
+                    } else {
+                        name.push('$');
+                        name.push_str(&class.name);
+                    }
+                    if !java.is_empty() {
+                        java.push('.');
+                        java.push_str(&class.name);
+                    } else if !class.type_arguments.is_empty() || i + 1 == classes.len() {
+                        java.push_str(&names.get_class_name(&name));
+                    }
+                    java.push_str(&type_arguments_to_java(&class.type_arguments, names));
+                }
+                java
+            }
+        }
+    }
+
+    pub fn is_object(&self) -> bool {
+        match self {
+            TypeSignature::Class { classes } => {
+                classes.len() == 1 && classes[0].name == "java/lang/Object"
+            }
+            _ => false,
+        }
+    }
+}
+
+fn type_arguments_to_java(type_arguments: &[TypeArgument], names: &NameResolver) -> String {
+    if type_arguments.is_empty() {
+        return String::new();
+    }
+    let arguments: Vec<String> = type_arguments
+        .iter()
+        .map(|argument| match argument {
+            TypeArgument::Wildcard => String::from("?"),
+            TypeArgument::Exact { argument } => argument.to_java(names),
+            TypeArgument::Extends { bound } => {
+                format!("? extends {}", bound.to_java(names))
+            }
+            TypeArgument::Super { bound } => format!("? super {}", bound.to_java(names)),
+        })
+        .collect();
+    format!("<{}>", arguments.join(", "))
+}
+
+pub fn type_parameters_to_java(type_parameters: &[TypeParameter], names: &NameResolver) -> String {
+    if type_parameters.is_empty() {
+        return String::new();
+    }
+    let parameters: Vec<String> = type_parameters
+        .iter()
+        .map(|parameter| {
+            let bounds: Vec<String> = parameter
+                .class_bound
+                .iter()
+                .filter(|bound| !bound.is_object())
+                .chain(parameter.interface_bounds.iter())
+                .map(|bound| bound.to_java(names))
+                .collect();
+            if bounds.is_empty() {
+                parameter.name.clone()
+            } else {
+                format!("{} extends {}", parameter.name, bounds.join(" & "))
+            }
+        })
+        .collect();
+    format!("<{}>", parameters.join(", "))
+}
+
+fn parse_identifier<T: Iterator<Item = char>>(
+    iter: &mut Peekable<T>,
+) -> Result<String, DescriptorParseError> {
+    let mut identifier = String::new();
+    loop {
+        let ch = peek(iter)?;
+        match ch {
+            '.' | ';' | '[' | '<' | '>' | ':' => break,
+            _ => {
+                identifier.push(ch);
+                consume(iter)?;
+            }
+        }
+    }
+    Ok(identifier)
+}
+
+fn parse_type_arguments<T: Iterator<Item = char>>(
+    iter: &mut Peekable<T>,
+) -> Result<Vec<TypeArgument>, DescriptorParseError> {
+    let mut type_arguments = Vec::new();
+    if !accept(iter, '<')? {
+        return Ok(type_arguments);
+    }
+    consume(iter)?;
+    while !accept(iter, '>')? {
+        let type_argument = match peek(iter)? {
+            '*' => {
+                consume(iter)?;
+                TypeArgument::Wildcard
+            }
+            '+' => {
+                consume(iter)?;
+                TypeArgument::Extends {
+                    bound: parse_reference_type(iter)?,
+                }
+            }
+            '-' => {
+                consume(iter)?;
+                TypeArgument::Super {
+                    bound: parse_reference_type(iter)?,
+                }
+            }
+            _ => TypeArgument::Exact {
+                argument: parse_reference_type(iter)?,
+            },
+        };
+        type_arguments.push(type_argument);
+    }
+    consume(iter)?;
+    Ok(type_arguments)
+}
+
+fn parse_class_type<T: Iterator<Item = char>>(
+    iter: &mut Peekable<T>,
+) -> Result<TypeSignature, DescriptorParseError> {
+    expect(iter, 'L')?;
+    consume(iter)?;
+    let mut classes = Vec::new();
+    loop {
+        let name = parse_identifier(iter)?;
+        let type_arguments = parse_type_arguments(iter)?;
+        classes.push(SimpleClassType {
+            name,
+            type_arguments,
+        });
+        if accept(iter, '.')? {
+            consume(iter)?;
+        } else {
+            break;
+        }
+    }
+    expect(iter, ';')?;
+    consume(iter)?;
+    Ok(TypeSignature::Class { classes })
+}
+
+fn parse_reference_type<T: Iterator<Item = char>>(
+    iter: &mut Peekable<T>,
+) -> Result<TypeSignature, DescriptorParseError> {
+    match peek(iter)? {
+        'L' => parse_class_type(iter),
+        'T' => {
+            consume(iter)?;
+            let name = parse_identifier(iter)?;
+            expect(iter, ';')?;
+            consume(iter)?;
+            Ok(TypeSignature::TypeVariable { name })
+        }
+        '[' => {
+            consume(iter)?;
+            Ok(TypeSignature::Array {
+                inner: Box::new(parse_java_type(iter)?),
+            })
+        }
+        ch => Err(DescriptorParseError::Expect {
+            expected: String::from("reference type signature"),
+            got: ch,
+        }),
+    }
+}
+
+fn parse_java_type<T: Iterator<Item = char>>(
+    iter: &mut Peekable<T>,
+) -> Result<TypeSignature, DescriptorParseError> {
+    match peek(iter)? {
+        'L' | 'T' | '[' => parse_reference_type(iter),
+        _ => Ok(TypeSignature::from(parse_return_desc(iter)?)),
+    }
+}
+
+fn parse_type_parameters<T: Iterator<Item = char>>(
+    iter: &mut Peekable<T>,
+) -> Result<Vec<TypeParameter>, DescriptorParseError> {
+    let mut type_parameters = Vec::new();
+    if !accept(iter, '<')? {
+        return Ok(type_parameters);
+    }
+    consume(iter)?;
+    while !accept(iter, '>')? {
+        let name = parse_identifier(iter)?;
+        expect(iter, ':')?;
+        consume(iter)?;
+        let class_bound = if accept(iter, ':')? {
+            None
+        } else {
+            Some(parse_reference_type(iter)?)
+        };
+        let mut interface_bounds = Vec::new();
+        while accept(iter, ':')? {
+            consume(iter)?;
+            interface_bounds.push(parse_reference_type(iter)?);
+        }
+        type_parameters.push(TypeParameter {
+            name,
+            class_bound,
+            interface_bounds,
+        });
+    }
+    consume(iter)?;
+    Ok(type_parameters)
+}
+
+pub fn parse_class<T: IntoIterator<Item = char>>(
+    into: T,
+) -> Result<ClassSignature, DescriptorParseError> {
+    let mut iter = into.into_iter().peekable();
+    let type_parameters = parse_type_parameters(&mut iter)?;
+    let super_class = parse_class_type(&mut iter)?;
+    let mut interfaces = Vec::new();
+    while iter.peek().is_some() {
+        interfaces.push(parse_class_type(&mut iter)?);
+    }
+    Ok(ClassSignature {
+        type_parameters,
+        super_class,
+        interfaces,
+    })
+}
+
+pub fn parse_method<T: IntoIterator<Item = char>>(
+    into: T,
+) -> Result<MethodSignature, DescriptorParseError> {
+    let mut iter = into.into_iter().peekable();
+    let type_parameters = parse_type_parameters(&mut iter)?;
+    expect(&mut iter, '(')?;
+    consume(&mut iter)?;
+    let mut params = Vec::new();
+    while !accept(&mut iter, ')')? {
+        params.push(parse_java_type(&mut iter)?);
+    }
+    consume(&mut iter)?;
+    let return_type = parse_java_type(&mut iter)?;
+    let mut throws = Vec::new();
+    while iter.peek().is_some() {
+        expect(&mut iter, '^')?;
+        consume(&mut iter)?;
+        throws.push(parse_reference_type(&mut iter)?);
+    }
+    Ok(MethodSignature {
+        type_parameters,
+        params,
+        return_type,
+        throws,
+    })
+}
+
+pub fn parse_field<T: IntoIterator<Item = char>>(
+    into: T,
+) -> Result<TypeSignature, DescriptorParseError> {
+    let mut iter = into.into_iter().peekable();
+    parse_reference_type(&mut iter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class(name: &str, type_arguments: Vec<TypeArgument>) -> SimpleClassType {
+        SimpleClassType {
+            name: String::from(name),
+            type_arguments,
+        }
+    }
+
+    fn type_variable(name: &str) -> TypeSignature {
+        TypeSignature::TypeVariable {
+            name: String::from(name),
+        }
+    }
+
+    #[test]
+    fn type_variables() {
+        let signature = parse_method("<T:Ljava/lang/Object;>([TT;)TT;".chars()).unwrap();
+        assert_eq!(signature.type_parameters.len(), 1);
+        assert_eq!(signature.type_parameters[0].name, "T");
+        assert!(signature.type_parameters[0]
+            .class_bound
+            .as_ref()
+            .unwrap()
+            .is_object());
+        assert_eq!(
+            signature.params,
+            vec![TypeSignature::Array {
+                inner: Box::new(type_variable("T")),
+            }]
+        );
+        assert_eq!(signature.return_type, type_variable("T"));
+    }
+
+    #[test]
+    fn interface_bounds() {
+        let signature = parse_class(
+            "<K::Ljava/lang/Comparable<TK;>;V:Ljava/lang/Number;:Ljava/io/Serializable;>Ljava/lang/Object;"
+                .chars(),
+        )
+        .unwrap();
+        let names = NameResolver::new("");
+        assert_eq!(
+            type_parameters_to_java(&signature.type_parameters, &names),
+            "<K extends java.lang.Comparable<K>, V extends java.lang.Number & java.io.Serializable>"
+        );
+        assert!(signature.interfaces.is_empty());
+    }
+
+    #[test]
+    fn wildcards() {
+        let signature = parse_field("Ljava/util/Map<*+Ljava/lang/Number;-TT;>;".chars()).unwrap();
+        assert_eq!(
+            signature,
+            TypeSignature::Class {
+                classes: vec![class(
+                    "java/util/Map",
+                    vec![
+                        TypeArgument::Wildcard,
+                        TypeArgument::Extends {
+                            bound: TypeSignature::from(FieldType::Reference {
+                                name: String::from("java/lang/Number"),
+                            }),
+                        },
+                        TypeArgument::Super {
+                            bound: type_variable("T"),
+                        },
+                    ],
+                )],
+            }
+        );
+        let names = NameResolver::new("");
+        assert_eq!(
+            signature.to_java(&names),
+            "java.util.Map<?, ? extends java.lang.Number, ? super T>"
+        );
+    }
+
+    #[test]
+    fn nested_generic_inner_classes() {
+        let signature =
+            parse_field("La/Outer<TT;>.Inner<Ljava/lang/String;>.Leaf;".chars()).unwrap();
+        assert_eq!(
+            signature,
+            TypeSignature::Class {
+                classes: vec![
+                    class(
+                        "a/Outer",
+                        vec![TypeArgument::Exact {
+                            argument: type_variable("T"),
+                        }],
+                    ),
+                    class(
+                        "Inner",
+                        vec![TypeArgument::Exact {
+                            argument: TypeSignature::from(FieldType::Reference {
+                                name: String::from("java/lang/String"),
+                            }),
+                        }],
+                    ),
+                    class("Leaf", Vec::new()),
+                ],
+            }
+        );
+        let names = NameResolver::new("");
+        assert_eq!(
+            signature.to_java(&names),
+            "a.Outer<T>.Inner<java.lang.String>.Leaf"
+        );
+    }
+
+    #[test]
+    fn inner_class_of_raw_outer_class() {
+        let signature = parse_field("La/Outer$Inner<TT;>;".chars()).unwrap();
+        let mut names = NameResolver::new("");
+        names.add_inner_class("a/Outer$Inner", Some("a/Outer"), Some("Inner"));
+        assert_eq!(signature.to_java(&names), "a.Outer.Inner<T>");
+    }
+
+    #[test]
+    fn throws() {
+        let signature =
+            parse_method("<E:Ljava/lang/Exception;>(I)V^TE;^Ljava/io/IOException;".chars())
+                .unwrap();
+        assert_eq!(
+            signature.params,
+            vec![TypeSignature::Base {
+                base_type: FieldType::Int,
+            }]
+        );
+        assert_eq!(
+            signature.return_type,
+            TypeSignature::Base {
+                base_type: FieldType::Void,
+            }
+        );
+        assert_eq!(
+            signature.throws,
+            vec![
+                type_variable("E"),
+                TypeSignature::from(FieldType::Reference {
+                    name: String::from("java/io/IOException"),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_signatures() {
+        assert!(parse_field("I".chars()).is_err());
+        assert!(parse_method("(I)V^I".chars()).is_err());
+        assert!(parse_class("Ljava/lang/Object".chars()).is_err());
+    }
+}
@@ -0,0 +1,1338 @@
+use super::{BinaryOperator, Case, Catch, DecompilerError, AST};
+use javaclass::ConstClassData;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Where control continues after a node.
+#[derive(Debug, Clone)]
+pub enum Exit {
+    /// Continues at `target`, either by falling through or by a `goto`.
+    Next { target: u64 },
+    /// Jumps to `target` if `condition` holds and continues at `next` otherwise.
+    Branch {
+        condition: AST,
+        target: u64,
+        next: u64,
+    },
+    /// Jumps to the target of the case matching `value`, or to `default` if there is none.
+    Switch {
+        value: AST,
+        cases: Vec<(i32, u64)>,
+        default: u64,
+    },
+    /// Leaves the method by returning or throwing.
+    End,
+}
+
+impl Exit {
+    pub fn successors(&self) -> Vec<u64> {
+        match self {
+            Exit::Next { target } => vec![*target],
+            Exit::Branch { target, next, .. } => vec![*target, *next],
+            Exit::Switch { cases, default, .. } => {
+                let mut successors: Vec<u64> = cases.iter().map(|(_, target)| *target).collect();
+                successors.push(*default);
+                successors.sort_unstable();
+                successors.dedup();
+                successors
+            }
+            Exit::End => Vec::new(),
+        }
+    }
+
+    fn redirect(&mut self, redirect: impl Fn(u64) -> u64) {
+        match self {
+            Exit::Next { target } => *target = redirect(*target),
+            Exit::Branch { target, next, .. } => {
+                *target = redirect(*target);
+                *next = redirect(*next);
+            }
+            Exit::Switch { cases, default, .. } => {
+                for (_, target) in cases {
+                    *target = redirect(*target);
+                }
+                *default = redirect(*default);
+            }
+            Exit::End => {}
+        }
+    }
+}
+
+/// Positions standing in for the targets of `continue` and `break` while the body of a loop is
+/// structured on its own.
+const CONTINUE: u64 = u64::MAX;
+const BREAK: u64 = u64::MAX - 1;
+
+fn get_jump(target: u64) -> Option<AST> {
+    match target {
+        CONTINUE => Some(AST::Continue),
+        BREAK => Some(AST::Break),
+        _ => None,
+    }
+}
+
+/// A decompiled region of code with a single entry. `inputs` is the amount of values the node
+/// pops from the stack of its predecessor, `stack` the values it leaves for its successor.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub statements: Vec<AST>,
+    pub stack: Vec<AST>,
+    pub inputs: usize,
+    pub exit: Exit,
+}
+
+impl Node {
+    fn is_pure_condition(&self) -> bool {
+        self.statements.is_empty()
+            && self.stack.is_empty()
+            && self.inputs == 0
+            && matches!(self.exit, Exit::Branch { .. })
+    }
+
+    fn get_value(&self) -> Option<&AST> {
+        match self.stack.as_slice() {
+            [value] if self.statements.is_empty() && self.inputs == 0 => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn is_statement_block(&self) -> bool {
+        self.stack.is_empty() && self.inputs == 0
+    }
+}
+
+/// An exception handler and the classes of the exceptions it catches.
+#[derive(Debug, Clone)]
+pub struct Handler {
+    pub position: u64,
+    pub classes: Vec<ConstClassData>,
+}
+
+/// The code from `start` up to `end` protected by a list of handlers.
+#[derive(Debug, Clone)]
+pub struct TryRegion {
+    pub start: u64,
+    pub end: u64,
+    pub handlers: Vec<Handler>,
+}
+
+type Reduction = fn(&mut BTreeMap<u64, Node>, u64, &dyn Fn(u64, u64) -> bool) -> bool;
+
+fn get_predecessors(nodes: &BTreeMap<u64, Node>) -> HashMap<u64, Vec<u64>> {
+    let mut predecessors: HashMap<u64, Vec<u64>> = HashMap::new();
+    for (pos, node) in nodes {
+        for successor in node.exit.successors() {
+            if !nodes.contains_key(&successor) {
+                continue;
+            }
+            predecessors.entry(successor).or_default().push(*pos);
+        }
+    }
+    predecessors
+}
+
+fn and(lhs: AST, rhs: AST) -> AST {
+    AST::Binary {
+        operator: BinaryOperator::And,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    }
+}
+
+fn or(lhs: AST, rhs: AST) -> AST {
+    AST::Binary {
+        operator: BinaryOperator::Or,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    }
+}
+
+/// Merges a branch into a following node that only evaluates another condition, which is how
+/// javac compiles `&&` and `||`.
+fn reduce_condition(
+    nodes: &mut BTreeMap<u64, Node>,
+    pos: u64,
+    has_single_predecessor: &dyn Fn(u64, u64) -> bool,
+) -> bool {
+    let (condition, target, next) = match &nodes[&pos].exit {
+        Exit::Branch {
+            condition,
+            target,
+            next,
+        } => (condition, *target, *next),
+        _ => return false,
+    };
+    for (inner, other, inner_is_target) in [(next, target, false), (target, next, true)] {
+        if inner == pos || !has_single_predecessor(inner, pos) || !nodes[&inner].is_pure_condition()
+        {
+            continue;
+        }
+        let (inner_condition, inner_target, inner_next) = match &nodes[&inner].exit {
+            Exit::Branch {
+                condition,
+                target,
+                next,
+            } => (condition, *target, *next),
+            _ => continue,
+        };
+        // the condition under which the outer branch skips the inner condition
+        let skip_condition = if inner_is_target {
+            condition.clone().negate()
+        } else {
+            condition.clone()
+        };
+        let condition = if other == inner_target {
+            or(skip_condition, inner_condition.clone())
+        } else if other == inner_next {
+            and(skip_condition.negate(), inner_condition.clone())
+        } else {
+            continue;
+        };
+        nodes.remove(&inner);
+        nodes.get_mut(&pos).unwrap().exit = Exit::Branch {
+            condition,
+            target: inner_target,
+            next: inner_next,
+        };
+        return true;
+    }
+    false
+}
+
+/// Merges a branch into two nodes that only evaluate conditions with the same targets, which is
+/// how javac compiles a conditional expression used as a condition.
+fn reduce_condition_ternary(
+    nodes: &mut BTreeMap<u64, Node>,
+    pos: u64,
+    has_single_predecessor: &dyn Fn(u64, u64) -> bool,
+) -> bool {
+    let (condition, target, next) = match &nodes[&pos].exit {
+        Exit::Branch {
+            condition,
+            target,
+            next,
+        } => (condition, *target, *next),
+        _ => return false,
+    };
+    if target == next
+        || [target, next].iter().any(|branch| {
+            *branch == pos
+                || !has_single_predecessor(*branch, pos)
+                || !nodes[branch].is_pure_condition()
+        })
+    {
+        return false;
+    }
+    let exit = match (&nodes[&next].exit, &nodes[&target].exit) {
+        (
+            Exit::Branch {
+                condition: then,
+                target: then_target,
+                next: then_next,
+            },
+            Exit::Branch {
+                condition: otherwise,
+                target: otherwise_target,
+                next: otherwise_next,
+            },
+        ) if then_target == otherwise_target && then_next == otherwise_next => Exit::Branch {
+            condition: AST::Ternary {
+                condition: Box::new(condition.clone().negate()),
+                then: Box::new(then.clone()),
+                otherwise: Box::new(otherwise.clone()),
+            },
+            target: *then_target,
+            next: *then_next,
+        },
+        _ => return false,
+    };
+    nodes.remove(&target);
+    nodes.remove(&next);
+    nodes.get_mut(&pos).unwrap().exit = exit;
+    true
+}
+
+/// Redirects jumps to a node that does nothing but jump on.
+fn reduce_forwarding(
+    nodes: &mut BTreeMap<u64, Node>,
+    pos: u64,
+    has_single_predecessor: &dyn Fn(u64, u64) -> bool,
+) -> bool {
+    let get_forward = |branch: u64| {
+        let node = nodes.get(&branch)?;
+        match node.exit {
+            Exit::Next { target }
+                if branch != pos
+                    && target != branch
+                    && has_single_predecessor(branch, pos)
+                    && node.statements.is_empty()
+                    && node.is_statement_block() =>
+            {
+                Some(target)
+            }
+            _ => None,
+        }
+    };
+    let (branch, forward) = match &nodes[&pos].exit {
+        Exit::Branch { target, next, .. } => match (get_forward(*target), get_forward(*next)) {
+            (Some(forward), _) => (*target, forward),
+            (_, Some(forward)) => (*next, forward),
+            _ => return false,
+        },
+        _ => return false,
+    };
+    nodes.remove(&branch);
+    if let Exit::Branch { target, next, .. } = &mut nodes.get_mut(&pos).unwrap().exit {
+        for successor in [target, next] {
+            if *successor == branch {
+                *successor = forward;
+            }
+        }
+    }
+    true
+}
+
+/// Merges a branch into two nodes that each push a single value onto the stack of a common
+/// successor into a conditional expression.
+fn reduce_ternary(
+    nodes: &mut BTreeMap<u64, Node>,
+    pos: u64,
+    has_single_predecessor: &dyn Fn(u64, u64) -> bool,
+) -> bool {
+    let (condition, target, next) = match &nodes[&pos].exit {
+        Exit::Branch {
+            condition,
+            target,
+            next,
+        } => (condition, *target, *next),
+        _ => return false,
+    };
+    if target == next
+        || [target, next]
+            .iter()
+            .any(|branch| *branch == pos || !has_single_predecessor(*branch, pos))
+    {
+        return false;
+    }
+    let (then, otherwise) = (&nodes[&next], &nodes[&target]);
+    let join = match (&then.exit, &otherwise.exit) {
+        (Exit::Next { target: a }, Exit::Next { target: b }) if a == b => *a,
+        _ => return false,
+    };
+    let value = match (then.get_value(), otherwise.get_value()) {
+        (Some(then), Some(otherwise)) => AST::Ternary {
+            condition: Box::new(condition.clone().negate()),
+            then: Box::new(then.clone()),
+            otherwise: Box::new(otherwise.clone()),
+        },
+        _ => return false,
+    };
+    nodes.remove(&target);
+    nodes.remove(&next);
+    let node = nodes.get_mut(&pos).unwrap();
+    node.stack.push(value);
+    node.exit = Exit::Next { target: join };
+    true
+}
+
+/// Merges a branch with the code it guards into an `if` statement, with an `else` block if both
+/// sides continue at the same node.
+fn reduce_if(
+    nodes: &mut BTreeMap<u64, Node>,
+    pos: u64,
+    has_single_predecessor: &dyn Fn(u64, u64) -> bool,
+) -> bool {
+    let (condition, target, next) = match &nodes[&pos].exit {
+        Exit::Branch {
+            condition,
+            target,
+            next,
+        } => (condition.clone(), *target, *next),
+        _ => return false,
+    };
+    if target == next {
+        let node = nodes.get_mut(&pos).unwrap();
+        node.statements.push(AST::If {
+            condition: Box::new(condition.negate()),
+            then: Vec::new(),
+            otherwise: Vec::new(),
+        });
+        node.exit = Exit::Next { target };
+        return true;
+    }
+    // the exit of a branch that can be turned into a block, `None` inside if it ends the method
+    let get_block_exit = |branch: u64| {
+        let node = nodes.get(&branch)?;
+        if branch == pos || !has_single_predecessor(branch, pos) || !node.is_statement_block() {
+            return None;
+        }
+        match node.exit {
+            Exit::Next { target } => Some(Some(target)),
+            Exit::End => Some(None),
+            Exit::Branch { .. } | Exit::Switch { .. } => None,
+        }
+    };
+
+    let (condition, then, otherwise, exit) = match (get_block_exit(next), get_block_exit(target)) {
+        (Some(Some(join)), Some(Some(other))) if join == other && join != pos => (
+            condition.negate(),
+            Some(next),
+            Some(target),
+            Exit::Next { target: join },
+        ),
+        (Some(Some(join)), Some(None)) if join != pos && join != target => (
+            condition.negate(),
+            Some(next),
+            Some(target),
+            Exit::Next { target: join },
+        ),
+        (Some(None), _) => (condition.negate(), Some(next), None, Exit::Next { target }),
+        (Some(Some(join)), _) if join == target => {
+            (condition.negate(), Some(next), None, Exit::Next { target })
+        }
+        (_, Some(None)) => (condition, Some(target), None, Exit::Next { target: next }),
+        (_, Some(Some(join))) if join == next => {
+            (condition, Some(target), None, Exit::Next { target: next })
+        }
+        _ => return false,
+    };
+
+    let mut take_statements = |branch: Option<u64>| match branch {
+        Some(branch) => nodes.remove(&branch).unwrap().statements,
+        None => Vec::new(),
+    };
+    let then = take_statements(then);
+    let otherwise = take_statements(otherwise);
+    let node = nodes.get_mut(&pos).unwrap();
+    node.statements.push(AST::If {
+        condition: Box::new(condition),
+        then,
+        otherwise,
+    });
+    node.exit = exit;
+    true
+}
+
+/// Merges a branch to a `continue` or `break`, or to code ending with one, into an `if`
+/// statement. This is only tried once no other reduction applies, so that branches that
+/// rejoin are preferably structured into blocks.
+fn reduce_jump(
+    nodes: &mut BTreeMap<u64, Node>,
+    pos: u64,
+    has_single_predecessor: &dyn Fn(u64, u64) -> bool,
+) -> bool {
+    let (condition, target, next) = match &nodes[&pos].exit {
+        Exit::Branch {
+            condition,
+            target,
+            next,
+        } => (condition.clone(), *target, *next),
+        _ => return false,
+    };
+    // prefer jumps out of the loop, so that the code after the `if` continues the loop
+    let mut candidates = [
+        (target, next, condition.clone()),
+        (next, target, condition.negate()),
+    ];
+    candidates.sort_by_key(|(branch, ..)| {
+        let jump_target = match nodes.get(branch) {
+            Some(Node {
+                exit: Exit::Next { target },
+                ..
+            }) => *target,
+            _ => *branch,
+        };
+        jump_target == CONTINUE
+    });
+    for (branch, other, condition) in candidates {
+        let then = match nodes.get(&branch) {
+            Some(node)
+                if branch != pos
+                    && has_single_predecessor(branch, pos)
+                    && node.is_statement_block() =>
+            {
+                match node.exit {
+                    Exit::Next { target } => get_jump(target),
+                    _ => None,
+                }
+                .map(|jump| {
+                    let mut statements = nodes.remove(&branch).unwrap().statements;
+                    statements.push(jump);
+                    statements
+                })
+            }
+            Some(_) => None,
+            None => get_jump(branch).map(|jump| vec![jump]),
+        };
+        if let Some(then) = then {
+            let node = nodes.get_mut(&pos).unwrap();
+            node.statements.push(AST::If {
+                condition: Box::new(condition),
+                then,
+                otherwise: Vec::new(),
+            });
+            node.exit = Exit::Next { target: other };
+            return true;
+        }
+    }
+    false
+}
+
+/// Turns a node leaving a value for the code following a `switch` expression into a `yield`.
+/// This is only tried once no other reduction applies, so that values computed by conditional
+/// expressions are merged first.
+fn reduce_yield(
+    nodes: &mut BTreeMap<u64, Node>,
+    pos: u64,
+    _has_single_predecessor: &dyn Fn(u64, u64) -> bool,
+) -> bool {
+    let node = nodes.get_mut(&pos).unwrap();
+    if !matches!(node.exit, Exit::Next { target: BREAK }) || node.stack.len() != 1 {
+        return false;
+    }
+    let value = node.stack.pop().unwrap();
+    node.statements.push(AST::Yield {
+        value: Box::new(value),
+    });
+    node.exit = Exit::End;
+    true
+}
+
+/// Removes the `continue` statements that end a loop body, as the loop continues there anyway.
+fn strip_continue(statements: &mut Vec<AST>) {
+    match statements.last_mut() {
+        Some(AST::Continue) => {
+            statements.pop();
+        }
+        Some(AST::If {
+            then, otherwise, ..
+        }) => {
+            strip_continue(then);
+            strip_continue(otherwise);
+        }
+        _ => {}
+    }
+}
+
+/// Collapses the loop starting at `header` into a single node. The body of the loop is taken
+/// out of the graph and structured on its own, with the jumps back to the loop and out of it
+/// turned into `continue` and `break`. Nested loops have to be collapsed first.
+fn reduce_loop(
+    nodes: &mut BTreeMap<u64, Node>,
+    header: u64,
+    predecessors: &HashMap<u64, Vec<u64>>,
+) -> Result<(), DecompilerError> {
+    let get_predecessors = |pos: u64| predecessors.get(&pos).into_iter().flatten().copied();
+    let latches: Vec<u64> = get_predecessors(header)
+        .filter(|pos| *pos >= header)
+        .collect();
+    let mut body = BTreeSet::from([header]);
+    let mut stack = latches.clone();
+    while let Some(pos) = stack.pop() {
+        if body.insert(pos) {
+            stack.extend(get_predecessors(pos));
+        }
+    }
+    if let Some(pos) = body
+        .iter()
+        .find(|pos| **pos != header && get_predecessors(**pos).any(|pos| !body.contains(&pos)))
+    {
+        return Err(DecompilerError::UnstructuredControlFlow { position: *pos });
+    }
+    let mut region: BTreeMap<u64, Node> = body
+        .iter()
+        .map(|pos| (*pos, nodes.remove(pos).unwrap()))
+        .collect();
+    let is_outside = |pos: u64| !body.contains(&pos);
+
+    let mut entry = header;
+    let mut exit = None;
+    let mut condition = None;
+    let mut do_condition = None;
+    let mut continue_target = header;
+    let mut update = Vec::new();
+    let header_node = &region[&header];
+    if let Exit::Branch {
+        condition: header_condition,
+        target,
+        next,
+    } = &header_node.exit
+    {
+        // a condition checked before each iteration
+        if header_node.is_pure_condition() && is_outside(*target) != is_outside(*next) {
+            (condition, entry, exit) = if is_outside(*target) {
+                (
+                    Some(header_condition.clone().negate()),
+                    *next,
+                    Some(*target),
+                )
+            } else {
+                (Some(header_condition.clone()), *target, Some(*next))
+            };
+            region.remove(&header);
+        }
+    }
+    if let [latch] = *latches.as_slice() {
+        match &region.get(&latch).map(|node| &node.exit) {
+            // a condition checked after each iteration
+            Some(Exit::Branch {
+                condition: latch_condition,
+                target,
+                next,
+            }) if condition.is_none()
+                && ((*target == header && is_outside(*next))
+                    || (*next == header && is_outside(*target))) =>
+            {
+                (do_condition, exit) = if *target == header {
+                    (Some(latch_condition.clone()), Some(*next))
+                } else {
+                    (Some(latch_condition.clone().negate()), Some(*target))
+                };
+                region.get_mut(&latch).unwrap().exit = Exit::Next { target: CONTINUE };
+            }
+            // the update of a `for` loop, which `continue` jumps to
+            Some(Exit::Next { .. })
+                if latch != header
+                    && region[&latch].is_statement_block()
+                    && get_predecessors(latch).count() > 1 =>
+            {
+                update = region.remove(&latch).unwrap().statements;
+                continue_target = latch;
+            }
+            _ => {}
+        }
+    }
+
+    let mut outside_targets: BTreeSet<u64> = region
+        .values()
+        .flat_map(|node| node.exit.successors())
+        .filter(|pos| is_outside(*pos) && get_jump(*pos).is_none())
+        .collect();
+    match exit {
+        Some(exit) => {
+            outside_targets.remove(&exit);
+        }
+        None => exit = outside_targets.pop_first(),
+    }
+    if let Some(pos) = outside_targets.pop_first() {
+        return Err(DecompilerError::UnstructuredControlFlow { position: pos });
+    }
+    for node in region.values_mut() {
+        node.exit.redirect(|pos| {
+            if pos == continue_target {
+                CONTINUE
+            } else if Some(pos) == exit {
+                BREAK
+            } else {
+                pos
+            }
+        });
+    }
+
+    let mut statements = if region.contains_key(&entry) {
+        let node = reduce_region(region, entry)?;
+        if node.inputs > 0 || !node.stack.is_empty() {
+            return Err(DecompilerError::UnstructuredControlFlow { position: entry });
+        }
+        let mut statements = node.statements;
+        match node.exit {
+            Exit::Next { target: CONTINUE } | Exit::End => {}
+            Exit::Next { target: BREAK } => statements.push(AST::Break),
+            _ => return Err(DecompilerError::UnstructuredControlFlow { position: entry }),
+        }
+        statements
+    } else {
+        // the body is empty and continues right at the update
+        Vec::new()
+    };
+    strip_continue(&mut statements);
+
+    let always = || Box::new(AST::ConstBoolean { value: true });
+    let statement = match (condition, do_condition) {
+        (_, Some(condition)) => AST::DoWhile {
+            body: statements,
+            condition: Box::new(condition),
+        },
+        (condition, None) if !update.is_empty() => AST::For {
+            condition: condition.map_or_else(always, Box::new),
+            update,
+            body: statements,
+        },
+        (condition, None) => AST::While {
+            condition: condition.map_or_else(always, Box::new),
+            body: statements,
+        },
+    };
+    nodes.insert(
+        header,
+        Node {
+            statements: vec![statement],
+            stack: Vec::new(),
+            inputs: 0,
+            exit: match exit {
+                Some(target) => Exit::Next { target },
+                None => Exit::End,
+            },
+        },
+    );
+    Ok(())
+}
+
+/// Collapses the code protected by a group of exception handlers together with the handlers into
+/// a single node. Like loop bodies the protected code and each handler are structured on their
+/// own, a handler consisting of the nodes only reachable through it.
+fn reduce_try(
+    nodes: &mut BTreeMap<u64, Node>,
+    try_region: TryRegion,
+    enclosing: &[TryRegion],
+    predecessors: &HashMap<u64, Vec<u64>>,
+) -> Result<(), DecompilerError> {
+    let start = try_region.start;
+    let get_predecessors = |pos: u64| predecessors.get(&pos).into_iter().flatten().copied();
+    let protected = start..try_region.end;
+    let body: Vec<u64> = nodes
+        .range(protected.clone())
+        .map(|(pos, _)| *pos)
+        .collect();
+    if body.first() != Some(&start) {
+        return Err(DecompilerError::UnstructuredControlFlow { position: start });
+    }
+    if let Some(pos) = body
+        .iter()
+        .find(|pos| **pos != start && get_predecessors(**pos).any(|pos| !protected.contains(&pos)))
+    {
+        return Err(DecompilerError::UnstructuredControlFlow { position: *pos });
+    }
+    let region = body
+        .iter()
+        .map(|pos| (*pos, nodes.remove(pos).unwrap()))
+        .collect();
+    let mut node = reduce_region(region, start)?;
+    // javac leaves the `return` of a value computed in the protected code outside of it
+    if let Exit::Next { target } = node.exit {
+        if !node.stack.is_empty() && get_predecessors(target).count() == 1 {
+            let successor = nodes.remove(&target).unwrap();
+            let mut sequence = BTreeMap::from([(start, node), (target, successor)]);
+            reduce_sequence(&mut sequence, start, &|_, _| true);
+            node = sequence.remove(&start).unwrap();
+        }
+    }
+    if node.inputs > 0 || !node.stack.is_empty() {
+        return Err(DecompilerError::UnstructuredControlFlow { position: start });
+    }
+
+    let mut exits = vec![node.exit];
+    let mut catches = Vec::new();
+    for handler in try_region.handlers {
+        if !nodes.contains_key(&handler.position) {
+            return Err(DecompilerError::UnstructuredControlFlow {
+                position: handler.position,
+            });
+        }
+        let mut positions = BTreeSet::from([handler.position]);
+        loop {
+            let reachable: Vec<u64> = positions
+                .iter()
+                .flat_map(|pos| nodes[pos].exit.successors())
+                .filter(|pos| {
+                    nodes.contains_key(pos)
+                        && !positions.contains(pos)
+                        && get_predecessors(*pos).all(|pos| positions.contains(&pos))
+                })
+                .collect();
+            if reachable.is_empty() {
+                break;
+            }
+            positions.extend(reachable);
+        }
+        let region = positions
+            .iter()
+            .map(|pos| (*pos, nodes.remove(pos).unwrap()))
+            .collect();
+        let handler_node = reduce_region(region, handler.position)?;
+        // the caught exception is the only value on the stack of a handler
+        let index = match handler_node.statements.first() {
+            Some(AST::Set { index, value })
+                if handler_node.inputs == 1
+                    && handler_node.stack.is_empty()
+                    && matches!(value.as_ref(), AST::StackInput { index: 0 }) =>
+            {
+                *index
+            }
+            _ => {
+                return Err(DecompilerError::UnstructuredControlFlow {
+                    position: handler.position,
+                })
+            }
+        };
+        catches.push(Catch {
+            classes: handler.classes,
+            index,
+            body: handler_node.statements[1..].to_vec(),
+        });
+        exits.push(handler_node.exit);
+    }
+
+    // javac inlines a `finally` block after the protected code, where it is only covered by the
+    // handler running the block for exceptions, so it has to join the protected code
+    let joins = |joined: u64| {
+        exits
+            .iter()
+            .any(|exit| matches!(exit, Exit::Next { target } if *target == joined))
+    };
+    let is_inlined_finally = |target: u64| {
+        let is_enclosed = |try_region: &TryRegion| {
+            (try_region.start..try_region.end).contains(&start)
+                && (try_region.start..try_region.end).contains(&target)
+        };
+        nodes.get(&target).is_some_and(|successor| {
+            successor.is_statement_block()
+                && get_predecessors(target).count() == 1
+                && enclosing.iter().any(is_enclosed)
+                && matches!(successor.exit, Exit::Next { target: joined } if joins(joined))
+        })
+    };
+    let inlined_finally = exits
+        .iter()
+        .enumerate()
+        .find_map(|(index, exit)| match exit {
+            Exit::Next { target } if is_inlined_finally(*target) => Some((index, *target)),
+            _ => None,
+        });
+    if let Some((index, target)) = inlined_finally {
+        let successor = nodes.remove(&target).unwrap();
+        match index {
+            0 => node.statements.extend(successor.statements),
+            _ => catches[index - 1].body.extend(successor.statements),
+        }
+        exits[index] = successor.exit;
+    }
+
+    let mut targets = BTreeSet::new();
+    for exit in exits {
+        match exit {
+            Exit::Next { target } => {
+                // skip the `goto` jumping over the handlers
+                let target = match nodes.get(&target) {
+                    Some(Node {
+                        statements,
+                        exit: Exit::Next { target: forward },
+                        ..
+                    }) if statements.is_empty()
+                        && nodes[&target].is_statement_block()
+                        && get_predecessors(target).count() == 1 =>
+                    {
+                        let forward = *forward;
+                        nodes.remove(&target);
+                        forward
+                    }
+                    _ => target,
+                };
+                targets.insert(target);
+            }
+            Exit::End => {}
+            Exit::Branch { .. } | Exit::Switch { .. } => {
+                return Err(DecompilerError::UnstructuredControlFlow { position: start })
+            }
+        }
+    }
+    let exit = match targets.pop_first() {
+        Some(_) if !targets.is_empty() => {
+            return Err(DecompilerError::UnstructuredControlFlow { position: start })
+        }
+        Some(target) => Exit::Next { target },
+        None => Exit::End,
+    };
+    nodes.insert(
+        start,
+        Node {
+            statements: vec![AST::Try {
+                resources: Vec::new(),
+                body: node.statements,
+                catches,
+                finally: Vec::new(),
+            }],
+            stack: Vec::new(),
+            inputs: 0,
+            exit,
+        },
+    );
+    Ok(())
+}
+
+/// Collects the nodes reachable from `starts` without passing through `header` or `stop`.
+fn get_reachable(
+    nodes: &BTreeMap<u64, Node>,
+    starts: &BTreeSet<u64>,
+    header: u64,
+    stop: Option<u64>,
+) -> BTreeSet<u64> {
+    let mut reachable = BTreeSet::new();
+    let mut stack: Vec<u64> = starts.iter().copied().collect();
+    while let Some(pos) = stack.pop() {
+        if pos == header || Some(pos) == stop || !nodes.contains_key(&pos) {
+            continue;
+        }
+        if reachable.insert(pos) {
+            stack.extend(nodes[&pos].exit.successors());
+        }
+    }
+    reachable
+}
+
+/// Checks if the cases at `starts` form a region that is only entered through the `switch` at
+/// `header` and continues at a single node, `follow` if it is known. Returns the region and the
+/// node following it.
+fn get_switch_region(
+    nodes: &BTreeMap<u64, Node>,
+    starts: &BTreeSet<u64>,
+    header: u64,
+    follow: Option<u64>,
+    predecessors: &HashMap<u64, Vec<u64>>,
+) -> Option<(BTreeSet<u64>, Option<u64>)> {
+    let region = get_reachable(nodes, starts, header, follow);
+    let first = starts.iter().find(|pos| region.contains(pos));
+    for pos in &region {
+        let predecessors = predecessors.get(pos).into_iter().flatten();
+        if first.is_none_or(|first| pos < first)
+            || follow.is_some_and(|follow| nodes.contains_key(&follow) && *pos > follow)
+            || predecessors
+                .into_iter()
+                .any(|pos| *pos != header && !region.contains(pos))
+        {
+            return None;
+        }
+    }
+    let mut outside_targets: BTreeSet<u64> = region
+        .iter()
+        .flat_map(|pos| nodes[pos].exit.successors())
+        .filter(|pos| !region.contains(pos) && Some(*pos) != follow)
+        .collect();
+    if follow.is_some() {
+        outside_targets.remove(&CONTINUE);
+        return outside_targets.is_empty().then_some((region, follow));
+    }
+    // the cases may continue the enclosing loop while also leaving the `switch` normally
+    if outside_targets.len() > 1 {
+        outside_targets.remove(&CONTINUE);
+    }
+    match outside_targets.len() {
+        0 | 1 => Some((region, outside_targets.pop_first())),
+        _ => None,
+    }
+}
+
+/// Collapses the `switch` at `header` and its cases into a single node, structuring each case
+/// on its own with the jumps to the code following the `switch` turned into `break`. javac lays
+/// out the cases in order, each of them followed by the next one it may fall through to. If
+/// there is no `default` case, the `default` target is the code following the `switch`. Returns
+/// whether the cases could be structured.
+fn reduce_switch(
+    nodes: &mut BTreeMap<u64, Node>,
+    header: u64,
+    predecessors: &HashMap<u64, Vec<u64>>,
+) -> bool {
+    let (value, default, mut labels, stack_size) = match &nodes[&header] {
+        Node {
+            stack,
+            exit:
+                Exit::Switch {
+                    value,
+                    cases,
+                    default,
+                },
+            ..
+        } => {
+            let mut labels: BTreeMap<u64, Vec<i32>> = BTreeMap::new();
+            for (label, target) in cases {
+                labels.entry(*target).or_default().push(*label);
+            }
+            (value.clone(), *default, labels, stack.len())
+        }
+        _ => return false,
+    };
+    let case_starts: BTreeSet<u64> = labels.keys().copied().collect();
+    let mut candidates = Vec::new();
+    if case_starts.last().is_none_or(|last| *last < default) {
+        candidates.push((case_starts.clone(), Some(default)));
+    }
+    let mut starts = case_starts;
+    starts.insert(default);
+    // cases that continue or break the enclosing loop are not laid out after the `switch`
+    let last = starts.iter().rev().find(|pos| get_jump(**pos).is_none());
+    if let Some(last) = last {
+        for follow in get_reachable(nodes, &starts, header, None).range(last + 1..) {
+            candidates.push((starts.clone(), Some(*follow)));
+        }
+    }
+    candidates.push((starts, None));
+    // the code following a `switch` expression pops its value
+    let is_expression = |follow: Option<u64>| {
+        follow
+            .and_then(|follow| nodes.get(&follow))
+            .is_some_and(|node| node.inputs > stack_size)
+    };
+    let (starts, region, follow) = match candidates.into_iter().find_map(|(starts, follow)| {
+        get_switch_region(nodes, &starts, header, follow, predecessors)
+            .filter(|(_, follow)| stack_size == 0 || is_expression(*follow))
+            .map(|(region, follow)| (starts, region, follow))
+    }) {
+        Some(layout) => layout,
+        None => return false,
+    };
+    let is_expression = is_expression(follow);
+
+    // the cases are structured on copies, as the graph is left untouched if one of them fails
+    let positions = region;
+    let mut region: BTreeMap<u64, Node> = positions
+        .iter()
+        .map(|pos| (*pos, nodes[pos].clone()))
+        .collect();
+    if let Some(follow) = follow {
+        for node in region.values_mut() {
+            node.exit
+                .redirect(|pos| if pos == follow { BREAK } else { pos });
+        }
+    }
+    let region_starts: Vec<u64> = starts
+        .iter()
+        .copied()
+        .filter(|pos| region.contains_key(pos))
+        .collect();
+    let mut cases = Vec::new();
+    for (i, start) in region_starts.iter().enumerate() {
+        let end = region_starts.get(i + 1).copied();
+        let case_region: Vec<u64> = region
+            .range(start..&end.unwrap_or(u64::MAX))
+            .map(|(pos, _)| *pos)
+            .collect();
+        let case_region = case_region
+            .iter()
+            .map(|pos| (*pos, region.remove(pos).unwrap()))
+            .collect();
+        let node = match reduce_region(case_region, *start) {
+            Ok(node) if node.inputs == 0 && node.stack.is_empty() => node,
+            _ => return false,
+        };
+        let mut body = node.statements;
+        match node.exit {
+            Exit::End => {}
+            Exit::Next { target } if Some(target) == end => {}
+            // the cases of a `switch` expression cannot be left without a value
+            Exit::Next { .. } if is_expression => return false,
+            Exit::Next { target } => match get_jump(target) {
+                Some(jump) => body.push(jump),
+                None => return false,
+            },
+            _ => return false,
+        }
+        cases.push((*start, body));
+    }
+    // cases that only leave the `switch` or jump out of it have no code of their own
+    for start in starts.iter().filter(|pos| !region_starts.contains(pos)) {
+        if is_expression {
+            return false;
+        }
+        let jump = if Some(*start) == follow {
+            AST::Break
+        } else {
+            match get_jump(*start) {
+                Some(jump) => jump,
+                None => return false,
+            }
+        };
+        cases.push((*start, vec![jump]));
+    }
+    if let Some((_, body)) = cases.last_mut() {
+        if let Some(AST::Break) = body.last() {
+            body.pop();
+        }
+    }
+
+    let cases = cases
+        .into_iter()
+        .map(|(start, body)| Case {
+            labels: labels
+                .remove(&start)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|value| AST::ConstInt { value })
+                .collect(),
+            pattern: None,
+            guard: None,
+            is_default: start == default,
+            body,
+        })
+        .collect();
+    for pos in &positions {
+        nodes.remove(pos);
+    }
+    let node = nodes.get_mut(&header).unwrap();
+    if is_expression {
+        node.stack.push(AST::SwitchExpression {
+            value: Box::new(value),
+            cases,
+        });
+    } else {
+        node.statements.push(AST::Switch {
+            value: Box::new(value),
+            cases,
+        });
+    }
+    node.exit = match follow {
+        Some(target) => Exit::Next { target },
+        None => Exit::End,
+    };
+    true
+}
+
+/// Appends a node to its only predecessor, replacing the placeholders for the values it pops
+/// with the values left on the stack by the predecessor.
+fn reduce_sequence(
+    nodes: &mut BTreeMap<u64, Node>,
+    pos: u64,
+    has_single_predecessor: &dyn Fn(u64, u64) -> bool,
+) -> bool {
+    let next = match &nodes[&pos].exit {
+        Exit::Next { target } if *target != pos && has_single_predecessor(*target, pos) => *target,
+        _ => return false,
+    };
+    // values left by a `switch` expression for the code following it are only known once the
+    // `switch` is collapsed, even if all but one of its cases throw
+    let node = &nodes[&pos];
+    if node.inputs == 0 && nodes[&next].inputs > node.stack.len() {
+        return false;
+    }
+    let mut successor = nodes.remove(&next).unwrap();
+    let node = nodes.get_mut(&pos).unwrap();
+    let available = node.stack.len().min(successor.inputs);
+    let mut values: Vec<AST> = node
+        .stack
+        .drain(node.stack.len() - available..)
+        .rev()
+        .collect();
+    values.extend(
+        (0..successor.inputs - available).map(|index| AST::StackInput {
+            index: node.inputs + index,
+        }),
+    );
+    node.inputs += successor.inputs - available;
+
+    for statement in &mut successor.statements {
+        statement.substitute_inputs(&values);
+    }
+    for value in &mut successor.stack {
+        value.substitute_inputs(&values);
+    }
+    match &mut successor.exit {
+        Exit::Branch { condition, .. }
+        | Exit::Switch {
+            value: condition, ..
+        } => condition.substitute_inputs(&values),
+        _ => {}
+    }
+    node.statements.extend(successor.statements);
+    node.stack.extend(successor.stack);
+    node.exit = successor.exit;
+    true
+}
+
+/// Structures a region of code taken out of the graph into a single node.
+fn reduce_region(mut region: BTreeMap<u64, Node>, entry: u64) -> Result<Node, DecompilerError> {
+    reduce(&mut region, entry, &mut Vec::new())?;
+    if let Some(pos) = region.keys().find(|pos| **pos != entry) {
+        return Err(DecompilerError::UnstructuredControlFlow { position: *pos });
+    }
+    Ok(region.remove(&entry).unwrap())
+}
+
+/// Repeatedly merges nodes that form a known pattern. Once no pattern is left, the innermost
+/// protected region is collapsed, or the innermost loop or `switch` if there are none left.
+fn reduce(
+    nodes: &mut BTreeMap<u64, Node>,
+    entry: u64,
+    try_regions: &mut Vec<TryRegion>,
+) -> Result<(), DecompilerError> {
+    let reductions: [Reduction; 6] = [
+        reduce_forwarding,
+        reduce_condition,
+        reduce_condition_ternary,
+        reduce_ternary,
+        reduce_if,
+        reduce_sequence,
+    ];
+    'reduce: loop {
+        let predecessors = get_predecessors(nodes);
+        // code is only merged with code protected by the same handlers
+        let get_try_regions = |pos: u64| -> Vec<usize> {
+            (0..try_regions.len())
+                .filter(|i| (try_regions[*i].start..try_regions[*i].end).contains(&pos))
+                .collect()
+        };
+        let has_single_predecessor = |pos: u64, predecessor: u64| {
+            pos != entry
+                && predecessors.get(&pos).map(Vec::as_slice) == Some(&[predecessor])
+                && get_try_regions(pos) == get_try_regions(predecessor)
+        };
+        let is_loop_header = |pos: &u64| {
+            predecessors
+                .get(pos)
+                .is_some_and(|predecessors| predecessors.iter().any(|p| p >= pos))
+        };
+        let positions: Vec<u64> = nodes.keys().copied().collect();
+        for reductions in [&reductions[..], &[reduce_jump, reduce_yield]] {
+            for pos in &positions {
+                // the branches entering and leaving a loop are left for `reduce_loop`
+                let is_loop_branch = match nodes.get(pos) {
+                    Some(node) => node.exit.successors().iter().any(|target| target <= pos),
+                    None => continue,
+                };
+                if is_loop_branch || is_loop_header(pos) {
+                    continue;
+                }
+                for reduce in reductions {
+                    if reduce(nodes, *pos, &has_single_predecessor) {
+                        continue 'reduce;
+                    }
+                }
+            }
+        }
+        if !try_regions.is_empty() {
+            let try_region = try_regions.remove(0);
+            reduce_try(nodes, try_region, try_regions, &predecessors)?;
+            continue;
+        }
+        // loops are only entered at their header, which precedes the rest of the body
+        for pos in positions.iter().rev() {
+            if is_loop_header(pos) {
+                reduce_loop(nodes, *pos, &predecessors)?;
+                continue 'reduce;
+            }
+            if matches!(nodes[pos].exit, Exit::Switch { .. })
+                && reduce_switch(nodes, *pos, &predecessors)
+            {
+                continue 'reduce;
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Structures the control flow graph of a method into nested statements by repeatedly merging
+/// nodes that form a known pattern, until only the entry node is left.
+pub fn structure(
+    mut nodes: BTreeMap<u64, Node>,
+    mut try_regions: Vec<TryRegion>,
+) -> Result<Vec<AST>, DecompilerError> {
+    let entry = match nodes.keys().next() {
+        Some(entry) => *entry,
+        None => return Ok(Vec::new()),
+    };
+    // code is unreachable if its exception handler was dropped, e.g. the one rethrowing
+    // exceptions of record accessors
+    let mut starts: BTreeSet<u64> = try_regions
+        .iter()
+        .flat_map(|try_region| try_region.handlers.iter().map(|handler| handler.position))
+        .collect();
+    starts.insert(entry);
+    let reachable = get_reachable(&nodes, &starts, CONTINUE, None);
+    nodes.retain(|pos, _| reachable.contains(pos));
+    // inner regions start after or end before the regions they are nested in
+    try_regions.sort_by_key(|try_region| (std::cmp::Reverse(try_region.start), try_region.end));
+    reduce(&mut nodes, entry, &mut try_regions)?;
+
+    if let Some(position) = nodes.keys().find(|pos| **pos != entry) {
+        return Err(DecompilerError::UnstructuredControlFlow {
+            position: *position,
+        });
+    }
+    let node = nodes.remove(&entry).unwrap();
+    if node.inputs > 0 {
+        return Err(DecompilerError::EmptyStack);
+    }
+    if !node.stack.is_empty() {
+        return Err(DecompilerError::StackSize {
+            size: node.stack.len(),
+        });
+    }
+    if !matches!(node.exit, Exit::End) {
+        return Err(DecompilerError::UnstructuredControlFlow { position: entry });
+    }
+    Ok(node.statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VarType;
+
+    fn variable(index: u16) -> AST {
+        AST::Variable {
+            index,
+            vartype: VarType::Int,
+        }
+    }
+
+    fn node(statements: Vec<AST>, exit: Exit) -> Node {
+        Node {
+            statements,
+            stack: Vec::new(),
+            inputs: 0,
+            exit,
+        }
+    }
+
+    fn statement(index: u16) -> AST {
+        AST::Set {
+            index,
+            value: Box::new(AST::ConstInt { value: 0 }),
+        }
+    }
+
+    /// `while (var1) { switch (var2) { case 1: continue; case 2: break; } var3 = 0; }`
+    fn loop_with_switch(case_target: u64) -> BTreeMap<u64, Node> {
+        BTreeMap::from([
+            (
+                0,
+                node(
+                    Vec::new(),
+                    Exit::Branch {
+                        condition: variable(1),
+                        target: 1,
+                        next: 30,
+                    },
+                ),
+            ),
+            (
+                1,
+                node(
+                    Vec::new(),
+                    Exit::Switch {
+                        value: variable(2),
+                        cases: vec![(1, case_target), (2, 20)],
+                        default: 20,
+                    },
+                ),
+            ),
+            (20, node(vec![statement(3)], Exit::Next { target: 0 })),
+            (30, node(vec![AST::VoidReturn], Exit::End)),
+        ])
+    }
+
+    #[test]
+    fn switch_case_continues_loop() {
+        let statements = structure(loop_with_switch(0), Vec::new()).unwrap();
+        assert!(matches!(
+            statements.as_slice(),
+            [AST::While { .. }, AST::VoidReturn]
+        ));
+    }
+
+    #[test]
+    fn switch_case_breaks_loop() {
+        let statements = structure(loop_with_switch(30), Vec::new()).unwrap();
+        assert!(matches!(
+            statements.as_slice(),
+            [AST::While { .. }, AST::VoidReturn]
+        ));
+    }
+}
@@ -0,0 +1,509 @@
+use super::disassembler::Instruction;
+use super::DecompilerError;
+use javaclass::ExceptionTableInfo;
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
+
+/// The jump targets of an instruction, the target of a `jsr` included.
+fn get_targets(instruction: &Instruction) -> Vec<u64> {
+    match instruction {
+        Instruction::IfNe { branch }
+        | Instruction::IfEq { branch }
+        | Instruction::IfLe { branch }
+        | Instruction::IfGe { branch }
+        | Instruction::IfGt { branch }
+        | Instruction::IfLt { branch }
+        | Instruction::IfICmpEq { branch }
+        | Instruction::IfICmpNe { branch }
+        | Instruction::IfICmpGt { branch }
+        | Instruction::IfICmpGe { branch }
+        | Instruction::IfICmpLt { branch }
+        | Instruction::IfICmpLe { branch }
+        | Instruction::IfACmpEq { branch }
+        | Instruction::IfACmpNe { branch }
+        | Instruction::IfNull { branch }
+        | Instruction::IfNonNull { branch }
+        | Instruction::Goto { branch }
+        | Instruction::JSr { branch } => vec![*branch as u64],
+        Instruction::TableSwitch {
+            default, offsets, ..
+        } => std::iter::once(default)
+            .chain(offsets)
+            .map(|target| *target as u64)
+            .collect(),
+        Instruction::LookupSwitch { default, pairs } => std::iter::once(default)
+            .chain(pairs.iter().map(|(_, target)| target))
+            .map(|target| *target as u64)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Replaces the jump targets of an instruction.
+fn redirect(
+    instruction: &mut Instruction,
+    mut redirect: impl FnMut(u64) -> Result<u16, DecompilerError>,
+) -> Result<(), DecompilerError> {
+    match instruction {
+        Instruction::IfNe { branch }
+        | Instruction::IfEq { branch }
+        | Instruction::IfLe { branch }
+        | Instruction::IfGe { branch }
+        | Instruction::IfGt { branch }
+        | Instruction::IfLt { branch }
+        | Instruction::IfICmpEq { branch }
+        | Instruction::IfICmpNe { branch }
+        | Instruction::IfICmpGt { branch }
+        | Instruction::IfICmpGe { branch }
+        | Instruction::IfICmpLt { branch }
+        | Instruction::IfICmpLe { branch }
+        | Instruction::IfACmpEq { branch }
+        | Instruction::IfACmpNe { branch }
+        | Instruction::IfNull { branch }
+        | Instruction::IfNonNull { branch }
+        | Instruction::Goto { branch }
+        | Instruction::JSr { branch } => *branch = redirect(*branch as u64)?,
+        Instruction::TableSwitch {
+            default, offsets, ..
+        } => {
+            for target in std::iter::once(default).chain(offsets) {
+                *target = redirect(*target as u64)? as u32;
+            }
+        }
+        Instruction::LookupSwitch { default, pairs } => {
+            for target in std::iter::once(default).chain(pairs.iter_mut().map(|(_, target)| target))
+            {
+                *target = redirect(*target as u64)? as u32;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Whether control can continue at the following instruction. A `jsr` continues there once its
+/// subroutine returns.
+fn falls_through(instruction: &Instruction) -> bool {
+    !matches!(
+        instruction,
+        Instruction::Goto { .. }
+            | Instruction::Ret { .. }
+            | Instruction::TableSwitch { .. }
+            | Instruction::LookupSwitch { .. }
+            | Instruction::Return
+            | Instruction::AReturn
+            | Instruction::IReturn
+            | Instruction::LReturn
+            | Instruction::DReturn
+            | Instruction::FReturn
+            | Instruction::AThrow
+    )
+}
+
+/// Collects the instructions reachable from `roots` without entering subroutines or leaving
+/// them by `ret`, leaving out those in `excluded`.
+fn get_reachable(
+    instructions: &[(u64, Instruction)],
+    indices: &HashMap<u64, usize>,
+    roots: impl IntoIterator<Item = u64>,
+    excluded: &BTreeSet<usize>,
+) -> BTreeSet<usize> {
+    let mut reachable = BTreeSet::new();
+    let mut stack: Vec<usize> = roots
+        .into_iter()
+        .filter_map(|pos| indices.get(&pos).copied())
+        .collect();
+    while let Some(i) = stack.pop() {
+        if excluded.contains(&i) || !reachable.insert(i) {
+            continue;
+        }
+        let instruction = &instructions[i].1;
+        if !matches!(instruction, Instruction::JSr { .. }) {
+            stack.extend(
+                get_targets(instruction)
+                    .into_iter()
+                    .filter_map(|pos| indices.get(&pos).copied()),
+            );
+        }
+        if falls_through(instruction) && i + 1 < instructions.len() {
+            stack.push(i + 1);
+        }
+    }
+    reachable
+}
+
+/// A copy of a subroutine, or of the code of the method itself, laid out in the inlined code.
+struct Context {
+    parent: Option<usize>,
+    return_pos: u64,
+}
+
+/// An instruction of the inlined code.
+struct Inlined {
+    /// The copy the instruction belongs to.
+    context: usize,
+    /// The copy the targets of the instruction refer to.
+    target_context: usize,
+    /// The position the instruction was copied from.
+    pos: u64,
+    instruction: Instruction,
+}
+
+struct Inliner<'a> {
+    instructions: &'a [(u64, Instruction)],
+    subroutines: HashMap<u64, Vec<usize>>,
+    contexts: Vec<Context>,
+    positions: HashMap<(usize, u64), usize>,
+    output: Vec<Inlined>,
+    active: Vec<u64>,
+}
+
+impl<'a> Inliner<'a> {
+    fn emit(&mut self, context: usize, body: &[usize]) -> Result<(), DecompilerError> {
+        for &i in body {
+            let (pos, instruction) = &self.instructions[i];
+            self.positions.insert((context, *pos), self.output.len());
+            match instruction {
+                Instruction::JSr { branch } => self.emit_subroutine(context, i, *branch as u64)?,
+                Instruction::Ret { .. } => {
+                    let Context { parent, return_pos } = self.contexts[context];
+                    let parent = parent.ok_or(DecompilerError::Subroutine { position: *pos })?;
+                    self.output.push(Inlined {
+                        context,
+                        target_context: parent,
+                        pos: *pos,
+                        instruction: Instruction::Goto {
+                            branch: return_pos as u16,
+                        },
+                    });
+                }
+                _ => self.output.push(Inlined {
+                    context,
+                    target_context: context,
+                    pos: *pos,
+                    instruction: instruction.clone(),
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Lays out a copy of the subroutine at `entry` in place of the `jsr` at index `call`. The
+    /// copy leaves out storing the return address and returns by jumping behind the call.
+    fn emit_subroutine(
+        &mut self,
+        context: usize,
+        call: usize,
+        entry: u64,
+    ) -> Result<(), DecompilerError> {
+        let call_pos = self.instructions[call].0;
+        let error = || DecompilerError::Subroutine { position: call_pos };
+        if self.active.contains(&entry) {
+            return Err(error());
+        }
+        let body = self.subroutines[&entry].clone();
+        let (&first, rest) = body.split_first().ok_or_else(error)?;
+        let return_index = match &self.instructions[first].1 {
+            Instruction::AStore { index } => *index,
+            _ => return Err(error()),
+        };
+        if rest.iter().any(|&i| {
+            matches!(self.instructions[i].1, Instruction::Ret { index } if index != return_index)
+        }) {
+            return Err(error());
+        }
+        let return_pos = self.instructions.get(call + 1).ok_or_else(error)?.0;
+        self.contexts.push(Context {
+            parent: Some(context),
+            return_pos,
+        });
+        let child = self.contexts.len() - 1;
+        if rest.first() != Some(&(first + 1)) {
+            let branch = self.instructions.get(first + 1).ok_or_else(error)?.0 as u16;
+            self.output.push(Inlined {
+                context: child,
+                target_context: child,
+                pos: call_pos,
+                instruction: Instruction::Goto { branch },
+            });
+        }
+        self.active.push(entry);
+        self.emit(child, rest)?;
+        self.active.pop();
+        Ok(())
+    }
+
+    /// Finds the inlined position of `pos` as seen from `context`. Subroutines can jump to code
+    /// of the context they were called from, e.g. for a `break` in a `finally` block.
+    fn resolve(&self, mut context: usize, pos: u64) -> Option<usize> {
+        loop {
+            if let Some(position) = self.positions.get(&(context, pos)) {
+                return Some(*position);
+            }
+            context = self.contexts[context].parent?;
+        }
+    }
+}
+
+fn to_position(position: usize, pos: u64) -> Result<u16, DecompilerError> {
+    u16::try_from(position).map_err(|_| DecompilerError::Subroutine { position: pos })
+}
+
+/// Inlines the subroutines called by `jsr` at each of their calls, which older compilers used
+/// for `finally` blocks. The instructions are renumbered by their index and the exception table
+/// is split to cover the same instructions as before.
+pub fn inline_subroutines(
+    instructions: &mut Vec<(u64, Instruction)>,
+    exception_table: &mut Vec<ExceptionTableInfo>,
+) -> Result<(), DecompilerError> {
+    if !instructions
+        .iter()
+        .any(|(_, instruction)| matches!(instruction, Instruction::JSr { .. }))
+    {
+        return Ok(());
+    }
+    let indices: HashMap<u64, usize> = instructions
+        .iter()
+        .enumerate()
+        .map(|(i, (pos, _))| (*pos, i))
+        .collect();
+    let roots = std::iter::once(0).chain(
+        exception_table
+            .iter()
+            .map(|exception| exception.handler_pc as u64),
+    );
+    let main = get_reachable(instructions, &indices, roots, &BTreeSet::new());
+    let mut subroutines = HashMap::new();
+    for (_, instruction) in instructions.iter() {
+        if let Instruction::JSr { branch } = instruction {
+            let entry = *branch as u64;
+            subroutines.entry(entry).or_insert_with(|| {
+                get_reachable(instructions, &indices, [entry], &main)
+                    .into_iter()
+                    .collect::<Vec<usize>>()
+            });
+        }
+    }
+    // a subroutine is entered at the instruction storing its return address
+    for (entry, body) in subroutines.iter_mut() {
+        if let Some(first) = body.iter().position(|i| indices[entry] == *i) {
+            let first = body.remove(first);
+            body.insert(0, first);
+        }
+    }
+
+    let mut inliner = Inliner {
+        instructions,
+        subroutines,
+        contexts: vec![Context {
+            parent: None,
+            return_pos: 0,
+        }],
+        positions: HashMap::new(),
+        output: Vec::new(),
+        active: Vec::new(),
+    };
+    let main: Vec<usize> = main.into_iter().collect();
+    inliner.emit(0, &main)?;
+
+    let mut inlined = Vec::new();
+    for (position, output) in inliner.output.iter().enumerate() {
+        let mut instruction = output.instruction.clone();
+        redirect(&mut instruction, |target| {
+            let position = inliner.resolve(output.target_context, target).ok_or(
+                DecompilerError::Subroutine {
+                    position: output.pos,
+                },
+            )?;
+            to_position(position, output.pos)
+        })?;
+        inlined.push((to_position(position, output.pos)? as u64, instruction));
+    }
+
+    // every copy of a protected instruction is protected by a handler of the same copy
+    let mut inlined_table = Vec::new();
+    for exception in exception_table.iter() {
+        let covers = |pos: u64| (exception.start_pc as u64..exception.end_pc as u64).contains(&pos);
+        let mut start: Option<(usize, usize)> = None;
+        let end = inliner.output.len();
+        for position in 0..=end {
+            let covered = inliner
+                .output
+                .get(position)
+                .filter(|output| covers(output.pos))
+                .map(|output| output.context);
+            if let Some((start_pc, context)) = start {
+                if covered == Some(context) {
+                    continue;
+                }
+                let handler_pos = exception.handler_pc as u64;
+                let handler_pc =
+                    inliner
+                        .resolve(context, handler_pos)
+                        .ok_or(DecompilerError::Subroutine {
+                            position: handler_pos,
+                        })?;
+                inlined_table.push(ExceptionTableInfo {
+                    start_pc: to_position(start_pc, handler_pos)?,
+                    end_pc: to_position(position, handler_pos)?,
+                    handler_pc: to_position(handler_pc, handler_pos)?,
+                    catch_type: exception.catch_type,
+                });
+            }
+            start = covered.map(|context| (position, context));
+        }
+    }
+    *instructions = inlined;
+    *exception_table = inlined_table;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(instructions: Vec<Instruction>) -> Vec<(u64, Instruction)> {
+        instructions
+            .into_iter()
+            .enumerate()
+            .map(|(pos, instruction)| (pos as u64, instruction))
+            .collect()
+    }
+
+    fn catch_any(start_pc: u16, end_pc: u16, handler_pc: u16) -> ExceptionTableInfo {
+        ExceptionTableInfo {
+            start_pc,
+            end_pc,
+            handler_pc,
+            catch_type: 0,
+        }
+    }
+
+    #[test]
+    fn inlines_finally_block() {
+        // try { a = 1; } finally { a = 2; }
+        let mut instructions = numbered(vec![
+            Instruction::IConst { value: 1 },
+            Instruction::IStore { index: 1 },
+            Instruction::JSr { branch: 8 },
+            Instruction::Return,
+            Instruction::AStore { index: 2 },
+            Instruction::JSr { branch: 8 },
+            Instruction::ALoad { index: 2 },
+            Instruction::AThrow,
+            Instruction::AStore { index: 3 },
+            Instruction::IConst { value: 2 },
+            Instruction::IStore { index: 1 },
+            Instruction::Ret { index: 3 },
+        ]);
+        let mut exception_table = vec![catch_any(0, 2, 4)];
+        inline_subroutines(&mut instructions, &mut exception_table).unwrap();
+        let expected = numbered(vec![
+            Instruction::IConst { value: 1 },
+            Instruction::IStore { index: 1 },
+            Instruction::IConst { value: 2 },
+            Instruction::IStore { index: 1 },
+            Instruction::Goto { branch: 5 },
+            Instruction::Return,
+            Instruction::AStore { index: 2 },
+            Instruction::IConst { value: 2 },
+            Instruction::IStore { index: 1 },
+            Instruction::Goto { branch: 10 },
+            Instruction::ALoad { index: 2 },
+            Instruction::AThrow,
+        ]);
+        assert_eq!(format!("{:?}", instructions), format!("{:?}", expected));
+        assert_eq!(
+            format!("{:?}", exception_table),
+            format!("{:?}", vec![catch_any(0, 2, 6)])
+        );
+    }
+
+    #[test]
+    fn protects_every_copy_of_a_subroutine() {
+        // try { try { a = 1; } finally { a = 2; } } catch (Throwable t) { return; }
+        let mut instructions = numbered(vec![
+            Instruction::IConst { value: 1 },
+            Instruction::IStore { index: 1 },
+            Instruction::JSr { branch: 8 },
+            Instruction::Return,
+            Instruction::AStore { index: 2 },
+            Instruction::JSr { branch: 8 },
+            Instruction::ALoad { index: 2 },
+            Instruction::AThrow,
+            Instruction::AStore { index: 3 },
+            Instruction::IConst { value: 2 },
+            Instruction::IStore { index: 1 },
+            Instruction::Ret { index: 3 },
+            Instruction::AStore { index: 4 },
+            Instruction::Return,
+        ]);
+        let mut exception_table = vec![catch_any(0, 2, 4), catch_any(0, 12, 12)];
+        inline_subroutines(&mut instructions, &mut exception_table).unwrap();
+        assert_eq!(instructions.len(), 14);
+        // the outer handler is split at the boundaries of the inlined copies
+        assert_eq!(
+            format!("{:?}", exception_table),
+            format!(
+                "{:?}",
+                vec![
+                    catch_any(0, 2, 6),
+                    catch_any(0, 2, 12),
+                    catch_any(2, 5, 12),
+                    catch_any(5, 7, 12),
+                    catch_any(7, 10, 12),
+                    catch_any(10, 12, 12),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn leaves_code_without_subroutines_unchanged() {
+        let mut instructions = vec![
+            (0, Instruction::IConst { value: 1 }),
+            (1, Instruction::IStore { index: 1 }),
+            (2, Instruction::Goto { branch: 5 }),
+            (5, Instruction::Return),
+        ];
+        let expected = format!("{:?}", instructions);
+        let mut exception_table = vec![catch_any(0, 2, 5)];
+        inline_subroutines(&mut instructions, &mut exception_table).unwrap();
+        assert_eq!(format!("{:?}", instructions), expected);
+        assert_eq!(
+            format!("{:?}", exception_table),
+            format!("{:?}", vec![catch_any(0, 2, 5)])
+        );
+    }
+
+    #[test]
+    fn rejects_recursive_subroutine() {
+        let mut instructions = numbered(vec![
+            Instruction::JSr { branch: 2 },
+            Instruction::Return,
+            Instruction::AStore { index: 1 },
+            Instruction::JSr { branch: 2 },
+            Instruction::Ret { index: 1 },
+        ]);
+        let result = inline_subroutines(&mut instructions, &mut Vec::new());
+        assert!(matches!(
+            result,
+            Err(DecompilerError::Subroutine { position: 3 })
+        ));
+    }
+
+    #[test]
+    fn rejects_return_through_other_variable() {
+        let mut instructions = numbered(vec![
+            Instruction::JSr { branch: 2 },
+            Instruction::Return,
+            Instruction::AStore { index: 1 },
+            Instruction::Ret { index: 2 },
+        ]);
+        let result = inline_subroutines(&mut instructions, &mut Vec::new());
+        assert!(matches!(
+            result,
+            Err(DecompilerError::Subroutine { position: 0 })
+        ));
+    }
+}
@@ -0,0 +1,22 @@
+public class Accessors implements Comparable<Accessors> {
+    private int value;
+
+    private int twice() {
+        return value * 2;
+    }
+
+    static int read(Accessors a) {
+        return a.value;
+    }
+
+    public int compareTo(Accessors other) {
+        return value - other.value;
+    }
+
+    class Inner {
+        int get() {
+            value = 3;
+            return twice() + read(Accessors.this);
+        }
+    }
+}
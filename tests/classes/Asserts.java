@@ -0,0 +1,14 @@
+public class Asserts {
+    static int limit = 3;
+
+    void check(int x) {
+        assert x > 0 : "negative " + x;
+        assert x < limit;
+    }
+
+    void manual(int x) {
+        if (x < 0) {
+            throw new AssertionError("negative");
+        }
+    }
+}
@@ -0,0 +1,39 @@
+import java.util.List;
+
+public class Boxing<T> {
+    void object(Object value) {
+    }
+
+    void generic(T value) {
+    }
+
+    void varargs(Object... values) {
+    }
+
+    void overloaded(int value) {
+    }
+
+    void overloaded(Integer value) {
+    }
+
+    void arguments(List<Integer> list, Boxing<Long> boxing) {
+        list.add(5);
+        boxing.generic(3L);
+        object(true);
+        varargs(1, 'c', "s");
+    }
+
+    void kept(List<Integer> list) {
+        list.remove(Integer.valueOf(5));
+        overloaded(Integer.valueOf(5));
+        object((byte) 1);
+    }
+
+    int unboxing(List<Integer> list) {
+        int sum = 0;
+        for (Integer value : list) {
+            sum += value;
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,18 @@
+import java.util.List;
+
+public class Calls {
+    static int twice(int x) {
+        return x * 2;
+    }
+
+    int calls(List<String> list, Runnable r) {
+        list.add("x");
+        r.run();
+        Math.abs(-1);
+        return twice(list.size()) + Integer.parseInt("3");
+    }
+
+    void instance() {
+        this.calls(null, null);
+    }
+}
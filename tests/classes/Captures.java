@@ -0,0 +1,33 @@
+import java.util.function.IntSupplier;
+
+public class Captures {
+    interface Op {
+        int apply(int x, int y);
+    }
+
+    Op withParameters(int a, int b) {
+        return new Op() {
+            public int apply(int x, int y) {
+                int z = x * y;
+                return z + a + b;
+            }
+        };
+    }
+
+    static Op fromStatic(int a) {
+        return new Op() {
+            public int apply(int x, int y) {
+                return a + x;
+            }
+        };
+    }
+
+    Runnable inLambda(int a) {
+        return new Runnable() {
+            public void run() {
+                IntSupplier supplier = () -> a + 1;
+                System.out.println(supplier.getAsInt());
+            }
+        };
+    }
+}
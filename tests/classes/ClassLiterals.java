@@ -0,0 +1,11 @@
+public class ClassLiterals {
+    static final Class<?> TYPE = ClassLiterals.class;
+
+    Class<?>[] literals() {
+        return new Class<?>[] {String.class, int[].class, int.class, void.class, ClassLiterals[][].class};
+    }
+
+    Object[] notLiterals() {
+        return new Object[] {ClassLiterals.TYPE, Integer.MAX_VALUE, "java.lang.String"};
+    }
+}
@@ -0,0 +1,29 @@
+import java.util.List;
+import java.util.Map;
+
+public class Concat {
+    String entry(Map.Entry<String, Object> e) {
+        return e.getKey() + "=" + e.getValue();
+    }
+
+    String pair(Map.Entry<String, Integer> e) {
+        return e.getKey() + e.getValue();
+    }
+
+    String append(String s, Object o) {
+        s += o;
+        return s;
+    }
+
+    String chars(char[] c, String s) {
+        return s + String.valueOf(c);
+    }
+
+    String strings(List<String> l) {
+        return l.get(0) + l.get(1);
+    }
+
+    String ints(int a, int b) {
+        return String.valueOf(a) + b;
+    }
+}
@@ -0,0 +1,15 @@
+public class Fields {
+    static int shared;
+    int a;
+    int b;
+
+    void chained(Fields other) {
+        this.a = this.b = 0;
+        other.a = Integer.MAX_VALUE - other.b;
+        shared = Math.max(shared, System.out.hashCode());
+    }
+
+    int local(int a) {
+        return a + this.a;
+    }
+}
@@ -0,0 +1,35 @@
+import java.util.Iterator;
+import java.util.List;
+
+public class ForEach {
+    int array(int[] values) {
+        int sum = 0;
+        for (int value : values) {
+            sum += value;
+        }
+        return sum;
+    }
+
+    void iterable(List<String> names) {
+        for (String name : names) {
+            System.out.println(name);
+        }
+    }
+
+    int indexed(int[] values) {
+        int sum = 0;
+        for (int i = 0; i < values.length; i++) {
+            sum += values[i] * i;
+        }
+        return sum;
+    }
+
+    void removing(List<String> names) {
+        Iterator<String> iterator = names.iterator();
+        while (iterator.hasNext()) {
+            if (iterator.next().isEmpty()) {
+                iterator.remove();
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+public class Increments {
+    int count;
+
+    void locals(int x, int[] arr, int i, int v) {
+        x += 5;
+        x++;
+        arr[i] *= 2;
+        arr[i++] = v;
+        this.count--;
+        System.out.println(++x + x);
+    }
+
+    int sum(int a, int b) {
+        int c = a + 5;
+        return c + b;
+    }
+
+    int[] values(int[] a) {
+        a[0] = a[1] + 2;
+        return a;
+    }
+}
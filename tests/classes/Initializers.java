@@ -0,0 +1,21 @@
+import java.util.ArrayList;
+import java.util.List;
+
+public class Initializers {
+    static final int CONSTANT = 5;
+    static long created = System.nanoTime();
+    int count = 3;
+    String name = "n" + count;
+    List<String> names = new ArrayList<>();
+    int fromParam;
+
+    {
+        System.out.println(count);
+    }
+
+    int last = 7;
+
+    Initializers(int param) {
+        fromParam = param;
+    }
+}
@@ -0,0 +1,17 @@
+import java.util.List;
+import java.util.Objects;
+import java.util.function.Supplier;
+
+public class MethodReferences {
+    String name = "n";
+
+    void bound(List<String> list) {
+        list.forEach(System.out::println);
+        Supplier<Integer> length = name::length;
+    }
+
+    void checked(List<String> list) {
+        Objects.requireNonNull(name);
+        list.forEach(name::concat);
+    }
+}
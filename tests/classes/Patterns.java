@@ -0,0 +1,32 @@
+public class Patterns {
+    int binding(Object o) {
+        if (o instanceof String s) {
+            return s.length();
+        }
+        return 0;
+    }
+
+    int plain(Object o) {
+        if (o instanceof String) {
+            return ((String) o).length();
+        }
+        return 0;
+    }
+
+    int guarded(Object o) {
+        switch (o) {
+            case null -> {
+                return -1;
+            }
+            case String s && s.length() > 2 -> {
+                return 1;
+            }
+            case Integer i -> {
+                return i;
+            }
+            default -> {
+                return 0;
+            }
+        }
+    }
+}
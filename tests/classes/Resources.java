@@ -0,0 +1,47 @@
+import java.io.*;
+
+public class Resources {
+    int two(File f, File g) throws IOException {
+        try (InputStream a = new FileInputStream(f); OutputStream b = new FileOutputStream(g)) {
+            b.write(a.read());
+            return a.available();
+        }
+    }
+
+    int withCatchFinally(File f) {
+        try (InputStream a = new FileInputStream(f)) {
+            return a.read();
+        } catch (IOException e) {
+            return -1;
+        } finally {
+            System.out.println("done");
+        }
+    }
+
+    void catchFinally(InputStream a) {
+        try {
+            a.read();
+        } catch (IOException e) {
+            e.printStackTrace();
+        } finally {
+            System.out.println("done");
+        }
+    }
+
+    void notResource(InputStream a) throws IOException {
+        try {
+            a.read();
+        } finally {
+            a.close();
+        }
+    }
+
+    void rethrow(InputStream a) throws IOException {
+        try {
+            a.read();
+        } catch (Throwable t) {
+            System.out.println("failed");
+            throw t;
+        }
+    }
+}
@@ -0,0 +1,40 @@
+public class StringSwitch {
+    int statement(String s) {
+        switch (s) {
+            case "foo":
+                return 1;
+            case "Aa":
+            case "BB":
+                return 2;
+            default:
+                return 0;
+        }
+    }
+
+    int collision(String s) {
+        switch (s) {
+            case "Aa":
+                return 1;
+            case "BB":
+                return 2;
+        }
+        return 0;
+    }
+
+    String expression(String s) {
+        return switch (s) {
+            case "a" -> "x";
+            case "b" -> "y";
+            default -> "z";
+        };
+    }
+
+    int hash(String s) {
+        switch (s.hashCode()) {
+            case 1:
+                return 1;
+            default:
+                return 0;
+        }
+    }
+}
@@ -0,0 +1,32 @@
+public class SwitchExpressions {
+    String arrows(int k) {
+        var x = switch (k) {
+            case 1 -> "a";
+            case 2, 3 -> "b";
+            default -> {
+                System.out.println(k);
+                yield "c";
+            }
+        };
+        return x;
+    }
+
+    int argument(int k) {
+        return Math.abs(switch (k) {
+            case 1 -> -1;
+            default -> k;
+        });
+    }
+
+    int statement(int k) {
+        int x;
+        switch (k) {
+            case 1:
+                x = 10;
+                break;
+            default:
+                x = 20;
+        }
+        return x;
+    }
+}
@@ -0,0 +1,18 @@
+public class Synchronized {
+    private final Object lock = new Object();
+    private int count;
+
+    synchronized void method() {
+        count++;
+    }
+
+    int block() {
+        synchronized (lock) {
+            return count;
+        }
+    }
+
+    void unlocked() {
+        count--;
+    }
+}